@@ -0,0 +1,467 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::ast::ASTNode;
+use crate::diagnostic::Diagnostic;
+
+/// Data a do action reads and writes instances of.
+/// An action that creates entities, or reads a field of an instance whose data is not known, is exclusive and runs alone.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DataAccess {
+    pub reads: BTreeSet<String>,
+    pub writes: BTreeSet<String>,
+    pub exclusive: bool,
+}
+
+impl DataAccess {
+    /// Two actions conflict when one writes a data the other reads or writes.
+    pub fn conflicts_with(&self, other: &DataAccess) -> bool {
+        self.exclusive
+            || other.exclusive
+            || self.writes.iter().any(|data_name| other.reads.contains(data_name) || other.writes.contains(data_name))
+            || other.writes.iter().any(|data_name| self.reads.contains(data_name))
+    }
+}
+
+struct AccessAnalyzer<'a> {
+    data_names: HashSet<&'a str>,
+    /// The data each variable in scope is an instance of, if any.
+    scopes: Vec<HashMap<String, Option<String>>>,
+    access: DataAccess,
+    context: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// What a variable declared inside a parallel foreach holds.
+#[derive(Clone, PartialEq)]
+enum IterationBinding {
+    /// An instance of the entity the iteration is about, which no other iteration touches.
+    OwnInstance(String),
+    /// An instance some other iteration may also reach.
+    SharedInstance(String),
+    Other,
+}
+
+/// Checks that the iterations of a parallel foreach only write state that belongs to them:
+/// fields of their own instances and variables declared in the loop.
+struct ParallelLoopChecker<'s> {
+    data_names: &'s HashSet<&'s str>,
+    outer_scopes: &'s [HashMap<String, Option<String>>],
+    scopes: Vec<HashMap<String, IterationBinding>>,
+    written_data: BTreeSet<String>,
+    shared_reads: BTreeSet<String>,
+    errors: Vec<String>,
+}
+
+/// Finds the data each do action accesses, by following the variables bound to instances through foreach and match.
+pub fn analyze_accesses(program: &ASTNode) -> HashMap<String, DataAccess> {
+    analyze(program).0
+}
+
+/// Reports parallel foreach loops whose iterations could write the same state, which would make the result depend on timing.
+pub fn check_parallel_loops(program: &ASTNode) -> Vec<Diagnostic> {
+    analyze(program).1
+}
+
+fn analyze(program: &ASTNode) -> (HashMap<String, DataAccess>, Vec<Diagnostic>) {
+    let statements = match program {
+        ASTNode::Program(statements) => statements,
+        _ => return (HashMap::new(), Vec::new()),
+    };
+
+    let data_names = statements
+        .iter()
+        .filter_map(|statement| match statement.as_ref() {
+            ASTNode::Data(name, _, _) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let mut analyzer = AccessAnalyzer {
+        data_names,
+        scopes: Vec::new(),
+        access: DataAccess::default(),
+        context: "".to_string(),
+        diagnostics: Vec::new(),
+    };
+
+    let mut accesses = HashMap::new();
+
+    for statement in statements {
+        match statement.as_ref() {
+            ASTNode::Do(name, instructions, _) => {
+                analyzer.context = format!("do {}", name);
+                analyzer.visit_block(instructions, HashMap::new());
+                accesses.insert(name.clone(), std::mem::take(&mut analyzer.access));
+            },
+            ASTNode::Run(actions_to_do, instructions) => {
+                analyzer.context = format!("run ({})", actions_to_do.join(", "));
+                analyzer.visit_block(instructions, HashMap::new());
+                analyzer.access = DataAccess::default();
+            },
+            _ => (),
+        }
+    }
+
+    (accesses, analyzer.diagnostics)
+}
+
+impl<'a> AccessAnalyzer<'a> {
+    fn visit_block(&mut self, instructions: &[Box<ASTNode>], bindings: HashMap<String, Option<String>>) {
+        self.scopes.push(bindings);
+
+        for instruction in instructions {
+            self.visit(instruction);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn visit(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Value(value) => self.visit_value(value),
+            ASTNode::Declaration(variable_name, value) => {
+                self.visit(value);
+                let data_name = self.data_of(value);
+                self.scopes.last_mut().unwrap().insert(variable_name.clone(), data_name);
+            },
+            ASTNode::Assignment(target, value) => {
+                self.visit(value);
+
+                match target.split_once('.') {
+                    Some((variable_name, _)) => match self.lookup(variable_name) {
+                        Some(Some(data_name)) => {
+                            self.access.writes.insert(data_name);
+                        },
+                        _ => self.access.exclusive = true,
+                    },
+                    None => {
+                        let data_name = self.data_of(value);
+
+                        if let Some(binding) = self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(target)) {
+                            *binding = data_name;
+                        }
+                    },
+                }
+            },
+            ASTNode::Foreach(values, collections, instructions, parallel) => {
+                if *parallel {
+                    self.check_parallel_loop(values, collections, instructions);
+                }
+
+                let mut bindings = HashMap::new();
+
+                for (value, collection) in values.iter().zip(collections) {
+                    self.visit(collection);
+
+                    let data_name = match collection.as_ref() {
+                        ASTNode::Value(name) if self.data_names.contains(name.as_str()) => Some(name.clone()),
+                        _ => None,
+                    };
+
+                    bindings.insert(value.clone(), data_name);
+                }
+
+                self.visit_block(instructions, bindings);
+            },
+            ASTNode::If(condition, instructions) => {
+                self.visit(condition);
+                self.visit_block(instructions, HashMap::new());
+            },
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                self.scopes.push(HashMap::new());
+                self.visit(declaration);
+                self.visit(condition);
+                self.visit(progression);
+                self.visit_block(instructions, HashMap::new());
+                self.scopes.pop();
+            },
+            ASTNode::Match(value, arms) => {
+                self.visit(value);
+                let data_name = self.data_of(value);
+
+                for arm in arms {
+                    if let ASTNode::MatchArm(pattern, instructions) = arm.as_ref() {
+                        let mut bindings = HashMap::new();
+                        self.visit_pattern(pattern, data_name.clone(), &mut bindings);
+                        self.visit_block(instructions, bindings);
+                    }
+                }
+            },
+            ASTNode::CreateInstruction(_, parameter_values) => {
+                self.access.exclusive = true;
+
+                for parameter_value in parameter_values {
+                    self.visit(parameter_value);
+                }
+            },
+            node => {
+                for child in node.children() {
+                    self.visit(child);
+                }
+            },
+        }
+    }
+
+    /// A field read goes through a variable bound to an instance, a bare data name is a whole table.
+    fn visit_value(&mut self, value: &str) {
+        match value.split_once('.') {
+            Some((variable_name, _)) => match self.lookup(variable_name) {
+                Some(Some(data_name)) => {
+                    self.access.reads.insert(data_name);
+                },
+                Some(None) => self.access.exclusive = true,
+                None => (),
+            },
+            None if self.data_names.contains(value) => {
+                self.access.reads.insert(value.to_string());
+            },
+            None => (),
+        }
+    }
+
+    fn visit_pattern(&mut self, pattern: &ASTNode, data_name: Option<String>, bindings: &mut HashMap<String, Option<String>>) {
+        match pattern {
+            ASTNode::BindingPattern(name) => {
+                bindings.insert(name.clone(), data_name);
+            },
+            ASTNode::VariantPattern(_, field_patterns) => {
+                for field_pattern in field_patterns {
+                    self.visit_pattern(field_pattern, None, bindings);
+                }
+            },
+            ASTNode::DataPattern(name, field_patterns) => {
+                self.access.reads.insert(name.clone());
+
+                for field_pattern in field_patterns {
+                    if let ASTNode::FieldPattern(_, sub_pattern) = field_pattern.as_ref() {
+                        self.visit_pattern(sub_pattern, None, bindings);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn check_parallel_loop(&mut self, values: &[String], collections: &[Box<ASTNode>], instructions: &[Box<ASTNode>]) {
+        let mut checker = ParallelLoopChecker {
+            data_names: &self.data_names,
+            outer_scopes: &self.scopes,
+            scopes: Vec::new(),
+            written_data: BTreeSet::new(),
+            shared_reads: BTreeSet::new(),
+            errors: Vec::new(),
+        };
+
+        let bindings = values
+            .iter()
+            .zip(collections)
+            .map(|(value, collection)| {
+                let binding = match collection.as_ref() {
+                    ASTNode::Value(name) if self.data_names.contains(name.as_str()) => IterationBinding::OwnInstance(name.clone()),
+                    _ => IterationBinding::Other,
+                };
+
+                (value.clone(), binding)
+            })
+            .collect();
+
+        checker.visit_block(instructions, bindings);
+
+        for data_name in checker.written_data.intersection(&checker.shared_reads) {
+            checker.errors.push(format!(
+                "Parallel foreach writes {} and reads instances of {} that other iterations write.",
+                data_name,
+                data_name
+            ));
+        }
+
+        for error in checker.errors {
+            self.diagnostics.push(
+                Diagnostic::error(format!("In {}: {}", self.context, error))
+                    .with_note("remove parallel to run the iterations one after the other".to_string())
+            );
+        }
+    }
+
+    /// The data a value is an instance of, when it is a variable bound to one.
+    fn data_of(&self, node: &ASTNode) -> Option<String> {
+        match node {
+            ASTNode::Value(value) if !value.contains('.') => self.lookup(value).flatten(),
+            _ => None,
+        }
+    }
+
+    fn lookup(&self, variable_name: &str) -> Option<Option<String>> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(variable_name)).cloned()
+    }
+}
+
+impl ParallelLoopChecker<'_> {
+    fn visit_block(&mut self, instructions: &[Box<ASTNode>], bindings: HashMap<String, IterationBinding>) {
+        self.scopes.push(bindings);
+
+        for instruction in instructions {
+            self.visit(instruction);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn visit(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Value(value) => self.visit_value(value),
+            ASTNode::Declaration(variable_name, value) => {
+                self.visit(value);
+                let binding = self.binding_of(value);
+                self.scopes.last_mut().unwrap().insert(variable_name.clone(), binding);
+            },
+            ASTNode::Assignment(target, value) => {
+                self.visit(value);
+
+                match target.split_once('.') {
+                    Some((variable_name, field_name)) => match self.lookup(variable_name) {
+                        Some(IterationBinding::OwnInstance(data_name)) => {
+                            self.written_data.insert(data_name);
+                        },
+                        Some(_) => self.errors.push(format!(
+                            "Parallel foreach assigns field {} of {}, which may be an instance of another iteration.",
+                            field_name,
+                            variable_name
+                        )),
+                        None => self.errors.push(format!(
+                            "Parallel foreach assigns field {} of {}, which is declared outside the loop.",
+                            field_name,
+                            variable_name
+                        )),
+                    },
+                    None => {
+                        let binding = self.binding_of(value);
+
+                        match self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(target)) {
+                            Some(variable) => *variable = binding,
+                            None => self.errors.push(format!(
+                                "Parallel foreach assigns {}, which is declared outside the loop.",
+                                target
+                            )),
+                        }
+                    },
+                }
+            },
+            ASTNode::Foreach(values, collections, instructions, _) => {
+                let mut bindings = HashMap::new();
+
+                for (value, collection) in values.iter().zip(collections) {
+                    self.visit(collection);
+
+                    let binding = match collection.as_ref() {
+                        ASTNode::Value(name) if self.data_names.contains(name.as_str()) => {
+                            IterationBinding::SharedInstance(name.clone())
+                        },
+                        _ => IterationBinding::Other,
+                    };
+
+                    bindings.insert(value.clone(), binding);
+                }
+
+                self.visit_block(instructions, bindings);
+            },
+            ASTNode::If(condition, instructions) => {
+                self.visit(condition);
+                self.visit_block(instructions, HashMap::new());
+            },
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                self.scopes.push(HashMap::new());
+                self.visit(declaration);
+                self.visit(condition);
+                self.visit(progression);
+                self.visit_block(instructions, HashMap::new());
+                self.scopes.pop();
+            },
+            ASTNode::Match(value, arms) => {
+                self.visit(value);
+                let binding = self.binding_of(value);
+
+                if let IterationBinding::SharedInstance(data_name) = &binding {
+                    self.shared_reads.insert(data_name.clone());
+                }
+
+                for arm in arms {
+                    if let ASTNode::MatchArm(pattern, instructions) = arm.as_ref() {
+                        let mut bindings = HashMap::new();
+                        collect_pattern_bindings(pattern, &binding, &mut bindings);
+                        self.visit_block(instructions, bindings);
+                    }
+                }
+            },
+            ASTNode::CreateInstruction(group_name, _) => {
+                self.errors.push(format!("Parallel foreach creates a {}, but entities can only be created one at a time.", group_name));
+            },
+            node => {
+                for child in node.children() {
+                    self.visit(child);
+                }
+            },
+        }
+    }
+
+    /// Reading a field of an instance that is not the iteration's own may race with the iteration that owns it.
+    fn visit_value(&mut self, value: &str) {
+        let variable_name = match value.split_once('.') {
+            Some((variable_name, _)) => variable_name,
+            None => return,
+        };
+
+        match self.lookup(variable_name) {
+            Some(IterationBinding::SharedInstance(data_name)) => {
+                self.shared_reads.insert(data_name);
+            },
+            Some(_) => (),
+            None => {
+                let outer_data_name = self.outer_scopes.iter().rev().find_map(|scope| scope.get(variable_name)).cloned().flatten();
+
+                if let Some(data_name) = outer_data_name {
+                    self.shared_reads.insert(data_name);
+                }
+            },
+        }
+    }
+
+    fn binding_of(&self, node: &ASTNode) -> IterationBinding {
+        match node {
+            ASTNode::Value(value) if !value.contains('.') => match self.lookup(value) {
+                Some(binding) => binding,
+                None => match self.outer_scopes.iter().rev().find_map(|scope| scope.get(value)).cloned().flatten() {
+                    Some(data_name) => IterationBinding::SharedInstance(data_name),
+                    None => IterationBinding::Other,
+                },
+            },
+            _ => IterationBinding::Other,
+        }
+    }
+
+    fn lookup(&self, variable_name: &str) -> Option<IterationBinding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(variable_name)).cloned()
+    }
+}
+
+/// A name bound by a pattern holds the matched value itself only at the top of the pattern.
+fn collect_pattern_bindings(pattern: &ASTNode, binding: &IterationBinding, bindings: &mut HashMap<String, IterationBinding>) {
+    match pattern {
+        ASTNode::BindingPattern(name) => {
+            bindings.insert(name.clone(), binding.clone());
+        },
+        ASTNode::VariantPattern(_, field_patterns) => {
+            for field_pattern in field_patterns {
+                collect_pattern_bindings(field_pattern, &IterationBinding::Other, bindings);
+            }
+        },
+        ASTNode::DataPattern(_, field_patterns) => {
+            for field_pattern in field_patterns {
+                if let ASTNode::FieldPattern(_, sub_pattern) = field_pattern.as_ref() {
+                    collect_pattern_bindings(sub_pattern, &IterationBinding::Other, bindings);
+                }
+            }
+        },
+        _ => (),
+    }
+}
@@ -1,53 +1,135 @@
+use std::fmt;
+
+use crate::token::Location;
+
 #[derive(Debug)]
 pub enum ASTNode {
     Program(Vec<Box<ASTNode>>),
-    Data(String, Vec<Box<ASTNode>>),
-    Group(String, Vec<Box<ASTNode>>, Vec<Box<ASTNode>>),
-    Do(String, Vec<Box<ASTNode>>),
+    /// Declarations, variants, fields and parameters keep where their name is, to point to a first definition.
+    Data(String, Vec<Box<ASTNode>>, Location),
+    Group(String, Vec<Box<ASTNode>>, Vec<Box<ASTNode>>, Location),
+    Do(String, Vec<Box<ASTNode>>, Location),
     Run(Vec<String>, Vec<Box<ASTNode>>),
-    Parameter(String, String),
-    Field(String, String),
+    Enum(String, Vec<Box<ASTNode>>, Location),
+    Import(String),
+    Use(String),
+    Attribute(String, Vec<String>),
+    Variant(String, Vec<Box<ASTNode>>, Location),
+    Parameter(String, TypeExpr, Location),
+    Field(String, TypeExpr, Option<Box<ASTNode>>, Location),
     FieldValue(String, String),
     Value(String),
-    Expression(Box<ASTNode>),
     Declaration(String, Box<ASTNode>),
+    Assignment(String, Box<ASTNode>),
     DataInstanciation(String, Vec<Box<ASTNode>>),
     CreateInstruction(String, Vec<Box<ASTNode>>),
+    NamedArgument(String, Box<ASTNode>),
     Sum(Box<ASTNode>, Box<ASTNode>),
     Substraction(Box<ASTNode>, Box<ASTNode>),
     Multiplication(Box<ASTNode>, Box<ASTNode>),
-    Division(Box<ASTNode>, Box<ASTNode>),
-    Modulo(Box<ASTNode>, Box<ASTNode>),
+    /// Division and modulo keep where their operator is, to point to a division by zero.
+    Division(Box<ASTNode>, Box<ASTNode>, Location),
+    Modulo(Box<ASTNode>, Box<ASTNode>, Location),
+    Equality(Box<ASTNode>, Box<ASTNode>),
+    Inequality(Box<ASTNode>, Box<ASTNode>),
+    GreaterThan(Box<ASTNode>, Box<ASTNode>),
+    LessThan(Box<ASTNode>, Box<ASTNode>),
+    GreaterOrEqual(Box<ASTNode>, Box<ASTNode>),
+    LessOrEqual(Box<ASTNode>, Box<ASTNode>),
+    ListLiteral(Vec<Box<ASTNode>>),
+    /// Builds a variant that carries fields, as in Moving(2.0). The values are given in the order of the fields.
+    VariantConstructor(String, Vec<Box<ASTNode>>),
     If(Box<ASTNode>, Vec<Box<ASTNode>>),
-    Foreach(Vec<String>, Vec<Box<ASTNode>>, Vec<Box<ASTNode>>),
+    /// Binds one name per collection. When every collection is a data, as in foreach p, v in Position, Velocity,
+    /// it iterates over the entities that have an instance of each data. When every collection is a list or an array,
+    /// it zips them, and they must all have the same length.
+    /// A parallel foreach splits its iterations into chunks run on several threads.
+    Foreach(Vec<String>, Vec<Box<ASTNode>>, Vec<Box<ASTNode>>, bool),
     For(Box<ASTNode>, Box<ASTNode>, Box<ASTNode>, Vec<Box<ASTNode>>),
+    Match(Box<ASTNode>, Vec<Box<ASTNode>>),
+    MatchArm(Box<ASTNode>, Vec<Box<ASTNode>>),
+    WildcardPattern,
+    LiteralPattern(String),
+    BindingPattern(String),
+    VariantPattern(String, Vec<Box<ASTNode>>),
+    DataPattern(String, Vec<Box<ASTNode>>),
+    FieldPattern(String, Box<ASTNode>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeExpr {
+    Named(String),
+    Generic(String, Vec<TypeExpr>),
+    Array(Box<TypeExpr>, usize),
+    Optional(Box<TypeExpr>),
+}
+
+impl TypeExpr {
+    pub fn new_generic(name: String, arguments: Vec<TypeExpr>) -> TypeExpr {
+        TypeExpr::Generic(name, arguments)
+    }
+
+    pub fn new_array(element_type: TypeExpr, length: usize) -> TypeExpr {
+        TypeExpr::Array(Box::new(element_type), length)
+    }
+
+    pub fn new_optional(inner_type: TypeExpr) -> TypeExpr {
+        TypeExpr::Optional(Box::new(inner_type))
+    }
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeExpr::Named(name) => write!(f, "{}", name),
+            TypeExpr::Generic(name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+                write!(f, "{}<{}>", name, arguments.join(", "))
+            },
+            TypeExpr::Array(element_type, length) => write!(f, "[{}; {}]", element_type, length),
+            TypeExpr::Optional(inner_type) => write!(f, "Option<{}>", inner_type),
+        }
+    }
 }
 
 impl ASTNode {
-    pub fn new_data(name: String, fields: Vec<ASTNode>) -> ASTNode {
-        ASTNode::Data(name, fields.into_iter().map(Box::new).collect())
+    pub fn new_data(name: String, fields: Vec<ASTNode>, location: Location) -> ASTNode {
+        ASTNode::Data(name, fields.into_iter().map(Box::new).collect(), location)
     }
 
-    pub fn new_group(name: String, parameters: Vec<ASTNode>, data_instanciations: Vec<ASTNode>) -> ASTNode {
+    pub fn new_group(name: String, parameters: Vec<ASTNode>, data_instanciations: Vec<ASTNode>, location: Location) -> ASTNode {
         ASTNode::Group(
             name,
             parameters.into_iter().map(Box::new).collect(),
-            data_instanciations.into_iter().map(Box::new).collect()
+            data_instanciations.into_iter().map(Box::new).collect(),
+            location
         )
     }
 
-    pub fn new_do(name: String, instructions: Vec<ASTNode>) -> ASTNode {
-        ASTNode::Do(name, instructions.into_iter().map(Box::new).collect())
+    pub fn new_do(name: String, instructions: Vec<ASTNode>, location: Location) -> ASTNode {
+        ASTNode::Do(name, instructions.into_iter().map(Box::new).collect(), location)
     }
 
     pub fn new_run(actions_to_do: Vec<String>, instructions: Vec<ASTNode>) -> ASTNode {
         ASTNode::Run(actions_to_do, instructions.into_iter().map(Box::new).collect())
     }
 
+    pub fn new_enum(name: String, variants: Vec<ASTNode>, location: Location) -> ASTNode {
+        ASTNode::Enum(name, variants.into_iter().map(Box::new).collect(), location)
+    }
+
+    pub fn new_variant(name: String, fields: Vec<ASTNode>, location: Location) -> ASTNode {
+        ASTNode::Variant(name, fields.into_iter().map(Box::new).collect(), location)
+    }
+
     pub fn new_create_instruction(groupe_name: String, parameter_values: Vec<ASTNode>) -> ASTNode {
         ASTNode::CreateInstruction(groupe_name, parameter_values.into_iter().map(Box::new).collect())
     }
 
+    pub fn new_named_argument(parameter_name: String, value: ASTNode) -> ASTNode {
+        ASTNode::NamedArgument(parameter_name, Box::new(value))
+    }
+
     pub fn new_sum(lhs: ASTNode, rhs: ASTNode) -> ASTNode {
         ASTNode::Sum(Box::new(lhs), Box::new(rhs))
     }
@@ -60,20 +142,44 @@ impl ASTNode {
         ASTNode::Multiplication(Box::new(lhs), Box::new(rhs))
     }
 
-    pub fn new_division(lhs: ASTNode, rhs: ASTNode) -> ASTNode {
-        ASTNode::Division(Box::new(lhs), Box::new(rhs))
+    pub fn new_division(lhs: ASTNode, rhs: ASTNode, location: Location) -> ASTNode {
+        ASTNode::Division(Box::new(lhs), Box::new(rhs), location)
+    }
+
+    pub fn new_modulo(lhs: ASTNode, rhs: ASTNode, location: Location) -> ASTNode {
+        ASTNode::Modulo(Box::new(lhs), Box::new(rhs), location)
     }
 
-    pub fn new_modulo(lhs: ASTNode, rhs: ASTNode) -> ASTNode {
-        ASTNode::Modulo(Box::new(lhs), Box::new(rhs))
+    pub fn new_equality(lhs: ASTNode, rhs: ASTNode) -> ASTNode {
+        ASTNode::Equality(Box::new(lhs), Box::new(rhs))
     }
 
-    pub fn new_parameter(name: String, parameter_type: String) -> ASTNode {
-        ASTNode::Parameter(name, parameter_type)
+    pub fn new_inequality(lhs: ASTNode, rhs: ASTNode) -> ASTNode {
+        ASTNode::Inequality(Box::new(lhs), Box::new(rhs))
     }
 
-    pub fn new_field(name: String, field_type: String) -> ASTNode {
-        ASTNode::Field(name, field_type)
+    pub fn new_greater_than(lhs: ASTNode, rhs: ASTNode) -> ASTNode {
+        ASTNode::GreaterThan(Box::new(lhs), Box::new(rhs))
+    }
+
+    pub fn new_less_than(lhs: ASTNode, rhs: ASTNode) -> ASTNode {
+        ASTNode::LessThan(Box::new(lhs), Box::new(rhs))
+    }
+
+    pub fn new_greater_or_equal(lhs: ASTNode, rhs: ASTNode) -> ASTNode {
+        ASTNode::GreaterOrEqual(Box::new(lhs), Box::new(rhs))
+    }
+
+    pub fn new_less_or_equal(lhs: ASTNode, rhs: ASTNode) -> ASTNode {
+        ASTNode::LessOrEqual(Box::new(lhs), Box::new(rhs))
+    }
+
+    pub fn new_parameter(name: String, parameter_type: TypeExpr, location: Location) -> ASTNode {
+        ASTNode::Parameter(name, parameter_type, location)
+    }
+
+    pub fn new_field(name: String, field_type: TypeExpr, default_value: Option<ASTNode>, location: Location) -> ASTNode {
+        ASTNode::Field(name, field_type, default_value.map(Box::new), location)
     }
 
     pub fn new_field_value(name: String, value: String) -> ASTNode {
@@ -84,27 +190,36 @@ impl ASTNode {
         ASTNode::Value(value)
     }
 
-    pub fn new_expression(value: ASTNode) -> ASTNode {
-        ASTNode::Expression(Box::new(value))
-    }
-
     pub fn new_declaration(variable_name: String, value: ASTNode) -> ASTNode {
         ASTNode::Declaration(variable_name, Box::new(value))
     }
 
+    pub fn new_assignment(target: String, value: ASTNode) -> ASTNode {
+        ASTNode::Assignment(target, Box::new(value))
+    }
+
     pub fn new_data_instanciation(data_name: String, field_values: Vec<ASTNode>) -> ASTNode {
         ASTNode::DataInstanciation(data_name, field_values.into_iter().map(Box::new).collect())
     }
 
+    pub fn new_list_literal(elements: Vec<ASTNode>) -> ASTNode {
+        ASTNode::ListLiteral(elements.into_iter().map(Box::new).collect())
+    }
+
+    pub fn new_variant_constructor(variant_name: String, field_values: Vec<ASTNode>) -> ASTNode {
+        ASTNode::VariantConstructor(variant_name, field_values.into_iter().map(Box::new).collect())
+    }
+
     pub fn new_if(condition: ASTNode, instructions: Vec<ASTNode>) -> ASTNode {
         ASTNode::If(Box::new(condition), instructions.into_iter().map(Box::new).collect())
     }
 
-    pub fn new_foreach(values: Vec<String>, collections: Vec<ASTNode>, instructions: Vec<ASTNode>) -> ASTNode {
+    pub fn new_foreach(values: Vec<String>, collections: Vec<ASTNode>, instructions: Vec<ASTNode>, parallel: bool) -> ASTNode {
         ASTNode::Foreach(
             values.clone(),
             collections.into_iter().map(Box::new).collect(),
-            instructions.into_iter().map(Box::new).collect()
+            instructions.into_iter().map(Box::new).collect(),
+            parallel
         )
     }
 
@@ -117,7 +232,141 @@ impl ASTNode {
         )
     }
 
+    pub fn new_match(value: ASTNode, arms: Vec<ASTNode>) -> ASTNode {
+        ASTNode::Match(Box::new(value), arms.into_iter().map(Box::new).collect())
+    }
+
+    pub fn new_match_arm(pattern: ASTNode, instructions: Vec<ASTNode>) -> ASTNode {
+        ASTNode::MatchArm(Box::new(pattern), instructions.into_iter().map(Box::new).collect())
+    }
+
+    pub fn new_variant_pattern(variant_name: String, field_patterns: Vec<ASTNode>) -> ASTNode {
+        ASTNode::VariantPattern(variant_name, field_patterns.into_iter().map(Box::new).collect())
+    }
+
+    pub fn new_data_pattern(data_name: String, field_patterns: Vec<ASTNode>) -> ASTNode {
+        ASTNode::DataPattern(data_name, field_patterns.into_iter().map(Box::new).collect())
+    }
+
+    pub fn new_field_pattern(field_name: String, pattern: ASTNode) -> ASTNode {
+        ASTNode::FieldPattern(field_name, Box::new(pattern))
+    }
+
     pub fn new_program(statements: Vec<ASTNode>) -> ASTNode {
         ASTNode::Program(statements.into_iter().map(Box::new).collect())
     }
+
+    /// Lists the nodes directly below this one, in source order.
+    pub fn children(&self) -> Vec<&ASTNode> {
+        match self {
+            ASTNode::Program(children)
+            | ASTNode::Data(_, children, _)
+            | ASTNode::Do(_, children, _)
+            | ASTNode::Run(_, children)
+            | ASTNode::Enum(_, children, _)
+            | ASTNode::Variant(_, children, _)
+            | ASTNode::DataInstanciation(_, children)
+            | ASTNode::CreateInstruction(_, children)
+            | ASTNode::VariantPattern(_, children)
+            | ASTNode::DataPattern(_, children)
+            | ASTNode::VariantConstructor(_, children)
+            | ASTNode::ListLiteral(children) => children.iter().map(Box::as_ref).collect(),
+            ASTNode::Group(_, parameters, data_instanciations, _) => {
+                parameters.iter().chain(data_instanciations).map(Box::as_ref).collect()
+            },
+            ASTNode::Field(_, _, default_value, _) => default_value.iter().map(Box::as_ref).collect(),
+            ASTNode::Declaration(_, child)
+            | ASTNode::Assignment(_, child)
+            | ASTNode::NamedArgument(_, child)
+            | ASTNode::FieldPattern(_, child) => vec![child],
+            ASTNode::Sum(lhs, rhs)
+            | ASTNode::Substraction(lhs, rhs)
+            | ASTNode::Multiplication(lhs, rhs)
+            | ASTNode::Division(lhs, rhs, _)
+            | ASTNode::Modulo(lhs, rhs, _)
+            | ASTNode::Equality(lhs, rhs)
+            | ASTNode::Inequality(lhs, rhs)
+            | ASTNode::GreaterThan(lhs, rhs)
+            | ASTNode::LessThan(lhs, rhs)
+            | ASTNode::GreaterOrEqual(lhs, rhs)
+            | ASTNode::LessOrEqual(lhs, rhs) => vec![lhs, rhs],
+            ASTNode::If(condition, instructions) | ASTNode::Match(condition, instructions) | ASTNode::MatchArm(condition, instructions) => {
+                [condition].into_iter().chain(instructions).map(Box::as_ref).collect()
+            },
+            ASTNode::Foreach(_, collections, instructions, _) => collections.iter().chain(instructions).map(Box::as_ref).collect(),
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                [declaration, condition, progression].into_iter().chain(instructions).map(Box::as_ref).collect()
+            },
+            ASTNode::Import(_)
+            | ASTNode::Use(_)
+            | ASTNode::Attribute(_, _)
+            | ASTNode::Parameter(_, _, _)
+            | ASTNode::FieldValue(_, _)
+            | ASTNode::Value(_)
+            | ASTNode::WildcardPattern
+            | ASTNode::LiteralPattern(_)
+            | ASTNode::BindingPattern(_) => Vec::new(),
+        }
+    }
+
+    /// Same as children, for passes that rewrite the tree in place.
+    pub fn children_mut(&mut self) -> Vec<&mut ASTNode> {
+        match self {
+            ASTNode::Program(children)
+            | ASTNode::Data(_, children, _)
+            | ASTNode::Do(_, children, _)
+            | ASTNode::Run(_, children)
+            | ASTNode::Enum(_, children, _)
+            | ASTNode::Variant(_, children, _)
+            | ASTNode::DataInstanciation(_, children)
+            | ASTNode::CreateInstruction(_, children)
+            | ASTNode::VariantPattern(_, children)
+            | ASTNode::DataPattern(_, children)
+            | ASTNode::VariantConstructor(_, children)
+            | ASTNode::ListLiteral(children) => children.iter_mut().map(Box::as_mut).collect(),
+            ASTNode::Group(_, parameters, data_instanciations, _) => {
+                parameters.iter_mut().chain(data_instanciations).map(Box::as_mut).collect()
+            },
+            ASTNode::Field(_, _, default_value, _) => default_value.iter_mut().map(Box::as_mut).collect(),
+            ASTNode::Declaration(_, child)
+            | ASTNode::Assignment(_, child)
+            | ASTNode::NamedArgument(_, child)
+            | ASTNode::FieldPattern(_, child) => vec![child],
+            ASTNode::Sum(lhs, rhs)
+            | ASTNode::Substraction(lhs, rhs)
+            | ASTNode::Multiplication(lhs, rhs)
+            | ASTNode::Division(lhs, rhs, _)
+            | ASTNode::Modulo(lhs, rhs, _)
+            | ASTNode::Equality(lhs, rhs)
+            | ASTNode::Inequality(lhs, rhs)
+            | ASTNode::GreaterThan(lhs, rhs)
+            | ASTNode::LessThan(lhs, rhs)
+            | ASTNode::GreaterOrEqual(lhs, rhs)
+            | ASTNode::LessOrEqual(lhs, rhs) => vec![lhs, rhs],
+            ASTNode::If(condition, instructions) | ASTNode::Match(condition, instructions) | ASTNode::MatchArm(condition, instructions) => {
+                [condition].into_iter().chain(instructions).map(Box::as_mut).collect()
+            },
+            ASTNode::Foreach(_, collections, instructions, _) => collections.iter_mut().chain(instructions).map(Box::as_mut).collect(),
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                [declaration, condition, progression].into_iter().chain(instructions).map(Box::as_mut).collect()
+            },
+            ASTNode::Import(_)
+            | ASTNode::Use(_)
+            | ASTNode::Attribute(_, _)
+            | ASTNode::Parameter(_, _, _)
+            | ASTNode::FieldValue(_, _)
+            | ASTNode::Value(_)
+            | ASTNode::WildcardPattern
+            | ASTNode::LiteralPattern(_)
+            | ASTNode::BindingPattern(_) => Vec::new(),
+        }
+    }
+}
+
+/// Tells whether a value is written as a number, a boolean or a string rather than a name.
+pub fn is_literal(value: &str) -> bool {
+    value.trim_start_matches('-').starts_with(|character: char| character.is_ascii_digit())
+        || value.starts_with('"')
+        || value == "true"
+        || value == "false"
 }
@@ -1,8 +1,8 @@
 use crate::lookahead_iterator::{LookAheadIterator, ToLookaheadIterator};
-use crate::token::Token;
-use crate::ast::ASTNode;
+use crate::token::{Location, Token};
+use crate::ast::{is_literal, ASTNode, TypeExpr};
 
-pub fn parse_ast(tokens: Vec<Token>) -> ASTNode {
+pub fn parse_ast(tokens: Vec<(Token, Location)>) -> ASTNode {
     let mut iterator = tokens.to_lookahead_iter();
     parse_program(&mut iterator)
 }
@@ -23,6 +23,10 @@ fn parse_primitive_bloc(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
         Some(Token::Group) => parse_group(iterator),
         Some(Token::Do)    => parse_do(iterator),
         Some(Token::Run)   => parse_run(iterator),
+        Some(Token::Enum)  => parse_enum(iterator),
+        Some(Token::Import) => parse_import(iterator),
+        Some(Token::Use)   => parse_use(iterator),
+        Some(Token::Hash)  => parse_attribute(iterator),
         None               => panic!("No token provided."),
         Some(unexpected)   => panic!("Unexpected token: {}", unexpected.get_value()),
     }
@@ -30,8 +34,8 @@ fn parse_primitive_bloc(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
 
 fn parse_data(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     if is_symbol(&iterator.peek()) {
+        let location = iterator.location();
         let name_token = iterator.next();
-        let mut fields: Vec<ASTNode> = Vec::new();
 
         if !token_is(iterator, Token::LBrace) {
             panic!("Expected left brace to open data structure body.");
@@ -39,19 +43,9 @@ fn parse_data(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
 
         iterator.next();
 
-        while !token_is(iterator, Token::RBrace) {
-            if token_is(iterator, Token::Comma) {
-                iterator.next();
-            }
-
-            fields.push(parse_field(iterator));
-        }
-
-        if token_is(iterator, Token::RBrace) {
-            iterator.next();
-        }
+        let fields = parse_separated_list(iterator, Token::RBrace, "data field", parse_field);
 
-        ASTNode::new_data(name_token.unwrap().get_value(), fields)
+        ASTNode::new_data(name_token.unwrap().get_value(), fields, location)
     }
     else {
         panic!("Data structure requires a name.");
@@ -60,8 +54,8 @@ fn parse_data(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
 
 fn parse_group(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     if is_symbol(&iterator.peek()) {
+        let location = iterator.location();
         let name_token = iterator.next();
-        let mut parameters: Vec<ASTNode> = Vec::new();
 
         if !token_is(iterator, Token::LParenthesis) {
             panic!("Expected left parenthesis to open group parameters.");
@@ -69,19 +63,7 @@ fn parse_group(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
 
         iterator.next();
 
-        while !token_is(iterator, Token::RParenthesis) {
-            if token_is(iterator, Token::Comma) {
-                iterator.next();
-            }
-
-            parameters.push(parse_parameter(iterator));
-        }
-
-        if token_is(iterator, Token::RParenthesis) {
-            iterator.next();
-        }
-
-        let mut data_instanciations: Vec<ASTNode> = Vec::new();
+        let parameters = parse_separated_list(iterator, Token::RParenthesis, "group parameter", parse_parameter);
 
         if !token_is(iterator, Token::LBrace) {
             panic!("Expected left brace to open group body.");
@@ -89,19 +71,9 @@ fn parse_group(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
 
         iterator.next();
 
-        while !token_is(iterator, Token::RBrace) {
-            if token_is(iterator, Token::Comma) {
-                iterator.next();
-            }
-
-            data_instanciations.push(parse_data_instanciation(iterator));
-        }
-
-        if token_is(iterator, Token::RBrace) {
-            iterator.next();
-        }
+        let data_instanciations = parse_separated_list(iterator, Token::RBrace, "data instanciation", parse_data_instanciation);
 
-        ASTNode::new_group(name_token.unwrap().get_value(), parameters, data_instanciations)
+        ASTNode::new_group(name_token.unwrap().get_value(), parameters, data_instanciations, location)
     }
     else {
         panic!("Group requires a name.");
@@ -110,6 +82,7 @@ fn parse_group(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
 
 fn parse_do(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     if is_symbol(&iterator.peek()) {
+        let location = iterator.location();
         let name_token = iterator.next();
         let mut instructions: Vec<ASTNode> = Vec::new();
 
@@ -127,7 +100,7 @@ fn parse_do(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
             iterator.next();
         }
 
-        ASTNode::new_do(name_token.unwrap().get_value(), instructions)
+        ASTNode::new_do(name_token.unwrap().get_value(), instructions, location)
     }
     else {
         panic!("Do requires a name.");
@@ -135,27 +108,13 @@ fn parse_do(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
 }
 
 fn parse_run(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
-    let mut actions_to_do: Vec<String> = Vec::new();
-
     if !token_is(iterator, Token::LParenthesis) {
         panic!("Expected left parenthesis to open list of actions to do.");
     }
 
     iterator.next();
 
-    while !token_is(iterator, Token::RParenthesis) {
-        if is_symbol(&iterator.peek()) {
-            let action_to_do_token = iterator.next();
-            actions_to_do.push(action_to_do_token.unwrap().get_value());
-        }
-        else {
-            iterator.next();
-        }
-    }
-
-    if token_is(iterator, Token::RParenthesis) {
-        iterator.next();
-    }
+    let actions_to_do = parse_separated_list(iterator, Token::RParenthesis, "action to do", parse_action_name);
 
     let mut instructions: Vec<ASTNode> = Vec::new();
 
@@ -176,27 +135,140 @@ fn parse_run(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     ASTNode::new_run(actions_to_do, instructions)
 }
 
-fn parse_field(iterator: &mut LookAheadIterator<Token>) -> ASTNode {    
+fn parse_enum(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     if is_symbol(&iterator.peek()) {
+        let location = iterator.location();
         let name_token = iterator.next();
 
+        if !token_is(iterator, Token::LBrace) {
+            panic!("Expected left brace to open enum body.");
+        }
+
+        iterator.next();
+
+        let variants = parse_separated_list(iterator, Token::RBrace, "enum variant", parse_variant);
+
+        ASTNode::new_enum(name_token.unwrap().get_value(), variants, location)
+    }
+    else {
+        panic!("Enum requires a name.");
+    }
+}
+
+fn parse_variant(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    if is_symbol(&iterator.peek()) {
+        let location = iterator.location();
+        let name_token = iterator.next();
+        let mut fields: Vec<ASTNode> = Vec::new();
+
+        if token_is(iterator, Token::LParenthesis) {
+            iterator.next();
+            fields = parse_separated_list(iterator, Token::RParenthesis, "variant field", parse_field);
+        }
+
+        ASTNode::new_variant(name_token.unwrap().get_value(), fields, location)
+    }
+    else {
+        panic!("Expected name of enum variant but found {}.", describe_token(iterator.peek()));
+    }
+}
+
+fn parse_import(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    match iterator.next() {
+        Some(Token::StringLiteral(path)) => ASTNode::Import(path),
+        unexpected => panic!("Expected path of file to import but found {}.", describe_token(unexpected.as_ref())),
+    }
+}
+
+fn parse_use(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    if !is_symbol(&iterator.peek()) {
+        panic!("Expected name to use but found {}.", describe_token(iterator.peek()));
+    }
+
+    let name = parse_name(iterator);
+
+    if !name.contains("::") {
+        panic!("Expected a name qualified by its module in use {}.", name);
+    }
+
+    ASTNode::Use(name)
+}
+
+/// An attribute such as #[allow(unused_let, empty_body)] sets lint levels for the declaration that follows it.
+fn parse_attribute(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    if !token_is(iterator, Token::LBracket) {
+        panic!("Expected [ after # but found {}.", describe_token(iterator.peek()));
+    }
+
+    iterator.next();
+
+    let level = match iterator.next() {
+        Some(Token::Symbol(level)) => level,
+        unexpected => panic!("Expected attribute name but found {}.", describe_token(unexpected.as_ref())),
+    };
+
+    if !token_is(iterator, Token::LParenthesis) {
+        panic!("Expected ( after attribute {} but found {}.", level, describe_token(iterator.peek()));
+    }
+
+    iterator.next();
+
+    let lint_names = parse_separated_list(iterator, Token::RParenthesis, "lint name", |iterator| match iterator.next() {
+        Some(Token::Symbol(lint_name)) => lint_name,
+        unexpected => panic!("Expected lint name but found {}.", describe_token(unexpected.as_ref())),
+    });
+
+    if !token_is(iterator, Token::RBracket) {
+        panic!("Expected ] to close attribute {} but found {}.", level, describe_token(iterator.peek()));
+    }
+
+    iterator.next();
+
+    ASTNode::Attribute(level, lint_names)
+}
+
+fn parse_action_name(iterator: &mut LookAheadIterator<Token>) -> String {
+    if is_symbol(&iterator.peek()) {
+        parse_name(iterator)
+    }
+    else {
+        panic!("Expected name of action to do but found {}.", describe_token(iterator.peek()));
+    }
+}
+
+/// A field written name?: T is a shorthand for name: Option<T>.
+fn parse_field(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    if is_symbol(&iterator.peek()) {
+        let location = iterator.location();
+        let name_token = iterator.next();
+
+        let is_optional = token_is(iterator, Token::QuestionMark);
+        if is_optional {
+            iterator.next();
+        }
+
         if token_is(iterator, Token::Colon) {
-           iterator.next(); 
+            iterator.next();
         }
         else {
-            panic!("Expected : before field type.");
+            panic!("Expected : before field type but found {}.", describe_token(iterator.peek()));
         }
-        
-        if is_symbol(&iterator.peek()) {
-            let field_type_token = iterator.next();
-            ASTNode::new_field(name_token.unwrap().get_value(), field_type_token.unwrap().get_value())
+
+        let mut field_type = parse_type_expression(iterator);
+        if is_optional {
+            field_type = TypeExpr::new_optional(field_type);
         }
-        else {
-            panic!("Expected type for field with name: {}", name_token.unwrap().get_value());
+
+        let mut default_value = None;
+        if token_is(iterator, Token::Equal) {
+            iterator.next();
+            default_value = Some(parse_expression(iterator));
         }
+
+        ASTNode::new_field(name_token.unwrap().get_value(), field_type, default_value, location)
     }
     else {
-        panic!("Expected name for field.");
+        panic!("Expected name for field but found {}.", describe_token(iterator.peek()));
     }
 }
 
@@ -204,52 +276,103 @@ fn parse_field_value(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     if is_symbol(&iterator.peek()) {
         let name_token = iterator.next();
         if token_is(iterator, Token::Equal) {
-           iterator.next(); 
+            iterator.next();
         }
         else {
-            panic!("Expected : before field value.");
+            panic!("Expected = before field value but found {}.", describe_token(iterator.peek()));
         }
 
-        if is_symbol(&iterator.peek()) {
-            let field_type_token = iterator.next();
-            ASTNode::new_field_value(name_token.unwrap().get_value(), field_type_token.unwrap().get_value())
-        }
-        else {
-            panic!("Expected value for field: {}", name_token.unwrap().get_value());
+        match iterator.peek() {
+            Some(Token::Symbol(_)) | Some(Token::StringLiteral(_)) => {
+                let field_value = match parse_value(iterator) {
+                    ASTNode::Value(field_value) => field_value,
+                    _ => unreachable!(),
+                };
+                ASTNode::new_field_value(name_token.unwrap().get_value(), field_value)
+            },
+            _ => panic!("Expected value for field: {}", name_token.unwrap().get_value()),
         }
     }
     else {
-        panic!("Expected name of field.");
+        panic!("Expected name of field but found {}.", describe_token(iterator.peek()));
     }
 }
 
 fn parse_parameter(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     if is_symbol(&iterator.peek()) {
+        let location = iterator.location();
         let name_token = iterator.next();
         if token_is(iterator, Token::Colon) {
-           iterator.next(); 
+            iterator.next();
         }
         else {
-            panic!("Expected : before parameter type.");
+            panic!("Expected : before parameter type but found {}.", describe_token(iterator.peek()));
         }
 
-        if is_symbol(&iterator.peek()) {
-            let parameter_type_token = iterator.next();
-            ASTNode::new_parameter(name_token.unwrap().get_value(), parameter_type_token.unwrap().get_value())
+        let parameter_type = parse_type_expression(iterator);
+        ASTNode::new_parameter(name_token.unwrap().get_value(), parameter_type, location)
+    }
+    else {
+        panic!("Expected name for parameter but found {}.", describe_token(iterator.peek()));
+    }
+}
+
+fn parse_type_expression(iterator: &mut LookAheadIterator<Token>) -> TypeExpr {
+    if token_is(iterator, Token::LBracket) {
+        iterator.next();
+
+        let element_type = parse_type_expression(iterator);
+
+        if !token_is(iterator, Token::Semicolon) {
+            panic!("Expected ; before array length but found {}.", describe_token(iterator.peek()));
         }
-        else {
-            panic!("Expected type for parameter with name: {}", name_token.unwrap().get_value());
+        iterator.next();
+
+        let length = match iterator.next() {
+            Some(Token::Symbol(length)) if length.parse::<usize>().is_ok() => length.parse::<usize>().unwrap(),
+            unexpected => panic!("Expected array length but found {}.", describe_token(unexpected.as_ref())),
+        };
+
+        if !token_is(iterator, Token::RBracket) {
+            panic!("Expected ] to close array type but found {}.", describe_token(iterator.peek()));
         }
+        iterator.next();
+
+        return TypeExpr::new_array(element_type, length);
     }
-    else {
-        panic!("Expected name for parameter.");
+
+    if !is_symbol(&iterator.peek()) {
+        panic!("Expected a type but found {}.", describe_token(iterator.peek()));
     }
+
+    let type_name = parse_name(iterator);
+
+    if !token_is(iterator, Token::LessThan) {
+        return TypeExpr::Named(type_name);
+    }
+
+    iterator.next();
+
+    let type_arguments = parse_separated_list(iterator, Token::GreaterThan, "type argument", parse_type_expression);
+
+    if type_arguments.is_empty() {
+        panic!("Expected type arguments for generic type {}.", type_name);
+    }
+
+    if type_name == "Option" {
+        if type_arguments.len() != 1 {
+            panic!("Option expects exactly one type argument but got {}.", type_arguments.len());
+        }
+
+        return TypeExpr::new_optional(type_arguments.into_iter().next().unwrap());
+    }
+
+    TypeExpr::new_generic(type_name, type_arguments)
 }
 
 fn parse_data_instanciation(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     if is_symbol(&iterator.peek()) {
-        let name_token = iterator.next();
-        let mut field_values: Vec<ASTNode> = Vec::new();
+        let data_name = parse_name(iterator);
 
         if !token_is(iterator, Token::LParenthesis) {
             panic!("Expected left parenthesis to open data instanciation values.");
@@ -257,22 +380,12 @@ fn parse_data_instanciation(iterator: &mut LookAheadIterator<Token>) -> ASTNode
 
         iterator.next();
 
-        while !token_is(iterator, Token::RParenthesis) {
-            if token_is(iterator, Token::Comma) {
-                iterator.next();
-            }
+        let field_values = parse_separated_list(iterator, Token::RParenthesis, "field value", parse_field_value);
 
-            field_values.push(parse_field_value(iterator));
-        }
-
-        if token_is(iterator, Token::RParenthesis) {
-            iterator.next();
-        }
-
-        ASTNode::new_data_instanciation(name_token.unwrap().get_value(), field_values)
+        ASTNode::new_data_instanciation(data_name, field_values)
     }
     else {
-        panic!("Expected name of data structure to instanciate.");
+        panic!("Expected name of data structure to instanciate but found {}.", describe_token(iterator.peek()));
     }
 }
 
@@ -284,7 +397,16 @@ fn parse_instruction(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
         return parse_if(iterator);
     }
     else if token_is(iterator, Token::Foreach) {
-        return parse_foreach(iterator);
+        return parse_foreach(iterator, false);
+    }
+    else if token_is(iterator, Token::Parallel) {
+        iterator.next();
+
+        if !token_is(iterator, Token::Foreach) {
+            panic!("Expected foreach after parallel but found {}.", describe_token(iterator.peek()));
+        }
+
+        return parse_foreach(iterator, true);
     }
     else if token_is(iterator, Token::For) {
         return parse_for(iterator);
@@ -292,6 +414,12 @@ fn parse_instruction(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     else if token_is(iterator, Token::Let) {
         return parse_declaration(iterator);
     }
+    else if token_is(iterator, Token::Match) {
+        return parse_match(iterator);
+    }
+    else if is_symbol(&iterator.peek()) {
+        return parse_assignment(iterator);
+    }
 
     panic!("Expected an instruction.");
 }
@@ -300,8 +428,7 @@ fn parse_create_instruction(iterator: &mut LookAheadIterator<Token>) -> ASTNode
     iterator.next();
 
     if is_symbol(&iterator.peek()) {
-        let name_token = iterator.next();
-        let mut parameter_values: Vec<ASTNode> = Vec::new();
+        let group_name = parse_name(iterator);
 
         if !token_is(iterator, Token::LParenthesis) {
             panic!("Expected left parenthesis to open group creation parameters.");
@@ -309,31 +436,48 @@ fn parse_create_instruction(iterator: &mut LookAheadIterator<Token>) -> ASTNode
 
         iterator.next();
 
-        while !token_is(iterator, Token::RParenthesis) {
-            if is_symbol(&iterator.peek()) {
-                parameter_values.push(parse_value(iterator));
-            }
-            else {
-                iterator.next();
-            }
-        }
+        let parameter_values = parse_separated_list(iterator, Token::RParenthesis, "group creation argument", parse_create_argument);
 
-        if token_is(iterator, Token::RParenthesis) {
-            iterator.next();
+        let first_named_argument = parameter_values
+            .iter()
+            .position(|value| matches!(value, ASTNode::NamedArgument(..)));
+
+        if let Some(first_named_argument) = first_named_argument {
+            if parameter_values[first_named_argument..].iter().any(|value| !matches!(value, ASTNode::NamedArgument(..))) {
+                panic!("Positional argument found after named arguments in creation of group {}.", group_name);
+            }
         }
 
-        ASTNode::new_create_instruction(name_token.unwrap().get_value(), parameter_values)
+        ASTNode::new_create_instruction(group_name, parameter_values)
     }
     else {
         panic!("Expected the name of a group to create.");
     }
 }
 
+fn parse_create_argument(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    if is_symbol(&iterator.peek()) && iterator.lookahead(1) == Some(&Token::Equal) {
+        let parameter_name = iterator.next().unwrap().get_value();
+        iterator.next();
+
+        return ASTNode::new_named_argument(parameter_name, parse_expression(iterator));
+    }
+
+    parse_expression(iterator)
+}
+
 fn parse_value(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     let mut value: String = "".to_string();
 
+    if let Some(Token::StringLiteral(_)) = iterator.peek() {
+        return ASTNode::new_value(iterator.next().unwrap().get_value());
+    }
+
     if is_symbol(&iterator.peek()) {
-        value += iterator.next().unwrap().get_value().as_str();
+        value += parse_name(iterator).as_str();
+    }
+    else {
+        panic!("Expected a value but found {}.", describe_token(iterator.peek()));
     }
 
     if token_is(iterator, Token::Dot) {
@@ -349,7 +493,26 @@ fn parse_value(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
 }
 
 fn parse_expression(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
-    parse_add_sub_expression(iterator)
+    parse_comparison_expression(iterator)
+}
+
+fn parse_comparison_expression(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    let lhs = parse_add_sub_expression(iterator);
+
+    let comparison = match iterator.peek() {
+        Some(Token::DoubleEqual) => ASTNode::new_equality,
+        Some(Token::NotEqual) => ASTNode::new_inequality,
+        Some(Token::GreaterThan) => ASTNode::new_greater_than,
+        Some(Token::LessThan) => ASTNode::new_less_than,
+        Some(Token::GreaterOrEqual) => ASTNode::new_greater_or_equal,
+        Some(Token::LessOrEqual) => ASTNode::new_less_or_equal,
+        _ => return lhs,
+    };
+
+    iterator.next();
+    let rhs = parse_add_sub_expression(iterator);
+
+    comparison(lhs, rhs)
 }
 
 fn parse_add_sub_expression(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
@@ -374,17 +537,18 @@ fn parse_add_sub_expression(iterator: &mut LookAheadIterator<Token>) -> ASTNode
 }
 
 fn parse_mul_div_mod_expression(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
-    let mut lhs = parse_value(iterator);
+    let mut lhs = parse_operand(iterator);
 
     while let Some(token) = iterator.peek() {
         match token {
             Token::MulSign | Token::DivSign | Token::ModSign => {
+                let location = iterator.location();
                 let op = iterator.next().unwrap();
-                let rhs = parse_value(iterator);
+                let rhs = parse_operand(iterator);
                 lhs = match op {
                     Token::MulSign => ASTNode::new_multiplication(lhs, rhs),
-                    Token::DivSign => ASTNode::new_division(lhs, rhs),
-                    Token::ModSign => ASTNode::new_modulo(lhs, rhs),
+                    Token::DivSign => ASTNode::new_division(lhs, rhs, location),
+                    Token::ModSign => ASTNode::new_modulo(lhs, rhs, location),
                     _ => unreachable!(),
                 };
             }
@@ -395,9 +559,50 @@ fn parse_mul_div_mod_expression(iterator: &mut LookAheadIterator<Token>) -> ASTN
     lhs
 }
 
-fn parse_statement(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+fn parse_operand(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    if token_is(iterator, Token::LBracket) {
+        iterator.next();
+        return ASTNode::new_list_literal(parse_separated_list(iterator, Token::RBracket, "list element", parse_expression));
+    }
+
+    if !token_is(iterator, Token::LParenthesis) {
+        return match parse_value(iterator) {
+            ASTNode::Value(variant_name) if token_is(iterator, Token::LParenthesis) => {
+                iterator.next();
+                ASTNode::new_variant_constructor(
+                    variant_name,
+                    parse_separated_list(iterator, Token::RParenthesis, "variant field value", parse_expression)
+                )
+            },
+            value => value,
+        };
+    }
+
+    iterator.next();
+    let expression = parse_expression(iterator);
+
+    if !token_is(iterator, Token::RParenthesis) {
+        panic!("Expected ) to close parenthesized expression but found {}.", describe_token(iterator.peek()));
+    }
+
     iterator.next();
-    ASTNode::new_expression(ASTNode::Value("".to_string()))
+    expression
+}
+
+/// Assigns a variable or a field of the data a variable refers to, as in p.x = p.x + 1.
+fn parse_assignment(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    let target = match parse_value(iterator) {
+        ASTNode::Value(target) => target,
+        _ => unreachable!(),
+    };
+
+    if !token_is(iterator, Token::Equal) {
+        panic!("Expected = after {} but found {}.", target, describe_token(iterator.peek()));
+    }
+
+    iterator.next();
+
+    ASTNode::new_assignment(target, parse_expression(iterator))
 }
 
 fn parse_declaration(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
@@ -443,51 +648,19 @@ fn parse_if(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     ASTNode::new_if(condition, instructions)
 }
 
-fn parse_foreach(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+fn parse_foreach(iterator: &mut LookAheadIterator<Token>, parallel: bool) -> ASTNode {
     iterator.next();
 
-    let mut values: Vec<String> = Vec::new();
-
     if !is_symbol(&iterator.peek()) {
         panic!("Unexpected token in foreach: {}.", iterator.peek().unwrap().get_value());
     }
 
-    while !token_is(iterator, Token::In) {
-        if token_is(iterator, Token::Comma) {
-            iterator.next();
-        }
+    let values = parse_separated_list(iterator, Token::In, "foreach value", |iterator| match iterator.next() {
+        Some(Token::Symbol(value)) => value,
+        unexpected => panic!("Expected a value name in foreach but found {}.", describe_token(unexpected.as_ref())),
+    });
 
-        if is_symbol(&iterator.peek()) {
-            values.push(iterator.next().unwrap().get_value());
-        }
-        else {
-            panic!("Expected a value name in foreach.");
-        }
-    }
-
-    if token_is(iterator, Token::In) {
-        iterator.next();
-    }
-    else {
-        panic!("Expected keyword in between values and collections in foreach.");
-    }
-
-    let mut collections: Vec<ASTNode> = Vec::new();
-
-    while !token_is(iterator, Token::LBrace) {
-        if token_is(iterator, Token::Comma) {
-            iterator.next();
-        }
-
-        collections.push(parse_expression(iterator));
-    }
-
-    if token_is(iterator, Token::LBrace) {
-        iterator.next();
-    }
-    else {
-        panic!("Expected left brace to open foreach body.");
-    }
+    let collections = parse_separated_list(iterator, Token::LBrace, "foreach collection", parse_expression);
 
     let mut instructions: Vec<ASTNode> = Vec::new();
 
@@ -499,7 +672,7 @@ fn parse_foreach(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
         iterator.next();
     }
 
-    ASTNode::new_foreach(values, collections, instructions)
+    ASTNode::new_foreach(values, collections, instructions, parallel)
 }
 
 fn parse_for(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
@@ -523,7 +696,7 @@ fn parse_for(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
         panic!("Expected semicolon.");
     }
 
-    let progression = parse_statement(iterator);
+    let progression = parse_assignment(iterator);
 
     let mut instructions: Vec<ASTNode> = Vec::new();
 
@@ -544,15 +717,175 @@ fn parse_for(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
     ASTNode::new_for(declaration, condition, progression, instructions)
 }
 
+/// Parses a name that may be qualified by its module, as in physics::Position.
+fn parse_name(iterator: &mut LookAheadIterator<Token>) -> String {
+    let mut name = match iterator.next() {
+        Some(Token::Symbol(name)) => name,
+        unexpected => panic!("Expected a name but found {}.", describe_token(unexpected.as_ref())),
+    };
+
+    while token_is(iterator, Token::DoubleColon) {
+        iterator.next();
+
+        match iterator.next() {
+            Some(Token::Symbol(segment)) => name = format!("{}::{}", name, segment),
+            unexpected => panic!("Expected a name after :: but found {}.", describe_token(unexpected.as_ref())),
+        }
+    }
+
+    name
+}
+
+fn parse_match(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    iterator.next();
+
+    let value = parse_expression(iterator);
+
+    if !token_is(iterator, Token::LBrace) {
+        panic!("Expected left brace to open match body.");
+    }
+
+    iterator.next();
+
+    let arms = parse_separated_list(iterator, Token::RBrace, "match arm", parse_match_arm);
+
+    ASTNode::new_match(value, arms)
+}
+
+fn parse_match_arm(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    let pattern = parse_pattern(iterator);
+
+    if !token_is(iterator, Token::FatArrow) {
+        panic!("Expected => after match pattern but found {}.", describe_token(iterator.peek()));
+    }
+
+    iterator.next();
+
+    if !token_is(iterator, Token::LBrace) {
+        panic!("Expected left brace to open match arm body.");
+    }
+
+    iterator.next();
+
+    let mut instructions: Vec<ASTNode> = Vec::new();
+
+    while !token_is(iterator, Token::RBrace) {
+        instructions.push(parse_instruction(iterator));
+    }
+
+    iterator.next();
+
+    ASTNode::new_match_arm(pattern, instructions)
+}
+
+/// A bare name is a variant pattern when it is capitalized and a binding otherwise,
+/// a name followed by parentheses always is a variant and a name followed by braces destructures data.
+fn parse_pattern(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    let name = match iterator.peek() {
+        Some(Token::Symbol(name)) => name.clone(),
+        Some(Token::StringLiteral(_)) => return ASTNode::LiteralPattern(iterator.next().unwrap().get_value()),
+        unexpected => panic!("Expected a pattern but found {}.", describe_token(unexpected)),
+    };
+
+    if name == "_" {
+        iterator.next();
+        return ASTNode::WildcardPattern;
+    }
+
+    if is_literal(&name) {
+        if let ASTNode::Value(literal) = parse_value(iterator) {
+            return ASTNode::LiteralPattern(literal);
+        }
+    }
+
+    let name = parse_name(iterator);
+
+    if token_is(iterator, Token::LParenthesis) {
+        iterator.next();
+        let field_patterns = parse_separated_list(iterator, Token::RParenthesis, "variant field pattern", parse_pattern);
+        ASTNode::new_variant_pattern(name, field_patterns)
+    }
+    else if token_is(iterator, Token::LBrace) {
+        iterator.next();
+        let field_patterns = parse_separated_list(iterator, Token::RBrace, "data field pattern", parse_field_pattern);
+        ASTNode::new_data_pattern(name, field_patterns)
+    }
+    else if name.rsplit("::").next().unwrap().starts_with(char::is_uppercase) {
+        ASTNode::new_variant_pattern(name, Vec::new())
+    }
+    else {
+        ASTNode::BindingPattern(name)
+    }
+}
+
+fn parse_field_pattern(iterator: &mut LookAheadIterator<Token>) -> ASTNode {
+    if !is_symbol(&iterator.peek()) {
+        panic!("Expected name of field to match but found {}.", describe_token(iterator.peek()));
+    }
+
+    let field_name = iterator.next().unwrap().get_value();
+
+    if token_is(iterator, Token::Equal) {
+        iterator.next();
+        return ASTNode::new_field_pattern(field_name, parse_pattern(iterator));
+    }
+
+    ASTNode::new_field_pattern(field_name.clone(), ASTNode::BindingPattern(field_name))
+}
+
+/// Parses items separated by commas until the closing token, which is consumed.
+/// The opening token must already have been consumed. A trailing comma is allowed.
+fn parse_separated_list<T>(
+    iterator: &mut LookAheadIterator<Token>,
+    closing_token: Token,
+    item_description: &str,
+    mut parse_item: impl FnMut(&mut LookAheadIterator<Token>) -> T
+) -> Vec<T> {
+    let mut items: Vec<T> = Vec::new();
+
+    while !token_is(iterator, closing_token.clone()) {
+        items.push(parse_item(iterator));
+
+        if token_is(iterator, Token::Comma) {
+            iterator.next();
+        }
+        else if !token_is(iterator, closing_token.clone()) {
+            panic!(
+                "Expected , or {} after {} but found {}.",
+                closing_token.get_value(),
+                item_description,
+                describe_next_token(iterator)
+            );
+        }
+    }
+
+    iterator.next();
+
+    items
+}
+
 fn token_is(iterator: &mut LookAheadIterator<Token>, token: Token) -> bool {
     iterator.peek().is_some() && iterator.peek().unwrap() == &token
 }
 
 fn is_symbol(token: &Option<&Token>) -> bool {
-    if let Some(Token::Symbol(_)) = token {
-        true
+    matches!(token, Some(Token::Symbol(_)))
+}
+
+/// Describes the next token along with where it starts, as the tokenizer does.
+fn describe_next_token(iterator: &mut LookAheadIterator<Token>) -> String {
+    match iterator.peek() {
+        Some(token) => {
+            let value = token.get_value();
+            format!("{} at {}", value, iterator.location())
+        },
+        None => "end of file".to_string(),
     }
-    else {
-        false
+}
+
+fn describe_token(token: Option<&Token>) -> String {
+    match token {
+        Some(token) => token.get_value(),
+        None        => "end of file".to_string(),
     }
 }
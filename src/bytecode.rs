@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::access_analysis::DataAccess;
+use crate::runtime::{ColumnType, Value};
+
+/// Instructions of the stack machine. Operands index the constants, names or locals of the function they are in,
+/// or its instructions for jumps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    Constant(usize),
+    Pop,
+    LoadLocal(usize),
+    StoreLocal(usize),
+    /// Pops an instance and pushes its field of the given name.
+    LoadField(usize),
+    /// Pops a value and an instance, and stores the value in the field of the given name.
+    StoreField(usize),
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+    GreaterOrEqual,
+    LessOrEqual,
+    /// Pops that many values and pushes a list of them.
+    MakeList(usize),
+    /// Pops one value per field and pushes the named variant carrying them.
+    MakeVariant(usize, usize),
+    Jump(usize),
+    /// Pops a bool and jumps when it is false.
+    JumpIfFalse(usize),
+    /// Pops one value per parameter and calls the function of a group.
+    Create(usize),
+    NewEntity,
+    /// Pops one value per field then an entity, and adds the row to the table of the named data.
+    PushRow(usize, usize),
+    /// Starts iterating over the entities that have an instance of each of the consecutive named data.
+    StartJoin(usize, usize),
+    /// Pops that many lists and starts iterating over them together.
+    StartZip(usize),
+    /// Stores the values of the next iteration in consecutive locals, or ends the loop by jumping.
+    NextIteration(usize, usize),
+    /// Runs every iteration of the loop body that follows on several threads, then jumps.
+    RunParallel(usize, usize),
+    EndIteration,
+    /// Pops a value and pushes whether it is the named variant, or an instance of the named data.
+    IsVariant(usize),
+    /// Pops a value and pushes whether it is an instance of the named data.
+    IsData(usize),
+    /// Pops a variant and pushes its field at that position.
+    VariantField(usize),
+    /// Pops the value no match arm matched and stops with an error.
+    NoMatch,
+    Return,
+}
+
+/// The code of a do action, a run block or a group.
+pub struct Function {
+    pub name: String,
+    pub parameter_types: Vec<ColumnType>,
+    pub local_count: usize,
+    pub constants: Vec<Value>,
+    pub names: Vec<String>,
+    pub code: Vec<Instruction>,
+}
+
+pub struct RunBlock {
+    pub actions_to_do: Vec<String>,
+    pub function: usize,
+}
+
+/// A whole program once compiled: the tables to create, every function, and the run blocks to execute in order.
+pub struct BytecodeProgram {
+    pub tables: Vec<(String, Vec<(String, ColumnType)>)>,
+    pub functions: Vec<Function>,
+    pub actions: HashMap<String, usize>,
+    pub run_blocks: Vec<RunBlock>,
+    /// The types of the fields of each variant, to widen the values a variant is made of.
+    pub variant_fields: HashMap<String, Vec<ColumnType>>,
+    pub accesses: HashMap<String, DataAccess>,
+}
+
+impl Function {
+    pub fn new(name: String, parameter_types: Vec<ColumnType>) -> Function {
+        Function {
+            name,
+            local_count: parameter_types.len(),
+            parameter_types,
+            constants: Vec::new(),
+            names: Vec::new(),
+            code: Vec::new(),
+        }
+    }
+
+    /// Describes an instruction with the constant or name it refers to.
+    fn disassemble(&self, instruction: &Instruction) -> String {
+        match instruction {
+            Instruction::Constant(index) => format!("Constant {} ({})", index, self.constants[*index]),
+            Instruction::LoadField(index)
+            | Instruction::StoreField(index)
+            | Instruction::IsVariant(index)
+            | Instruction::IsData(index) => {
+                let operation = format!("{:?}", instruction);
+                format!("{} {}", operation.split('(').next().unwrap(), self.names[*index])
+            },
+            Instruction::PushRow(index, field_count) => format!("PushRow {} ({} fields)", self.names[*index], field_count),
+            Instruction::MakeVariant(index, field_count) => format!("MakeVariant {} ({} fields)", self.names[*index], field_count),
+            Instruction::StartJoin(index, count) => format!("StartJoin {}", self.names[*index..*index + count].join(", ")),
+            other => {
+                let operation = format!("{:?}", other);
+
+                match operation.split_once('(') {
+                    Some((name, operands)) => format!("{} {}", name, operands.trim_end_matches(')')),
+                    None => operation,
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Display for BytecodeProgram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (data_name, columns) in &self.tables {
+            let columns: Vec<String> = columns.iter().map(|(name, column_type)| format!("{}: {:?}", name, column_type)).collect();
+            writeln!(f, "table {} ({})", data_name, columns.join(", "))?;
+        }
+
+        for (index, function) in self.functions.iter().enumerate() {
+            writeln!(
+                f,
+                "\nfunction {}: {} ({} parameters, {} locals)",
+                index,
+                function.name,
+                function.parameter_types.len(),
+                function.local_count
+            )?;
+
+            for (position, instruction) in function.code.iter().enumerate() {
+                writeln!(f, "  {:04}  {}", position, function.disassemble(instruction))?;
+            }
+        }
+
+        for run_block in &self.run_blocks {
+            writeln!(f, "\nrun function {} then ({})", run_block.function, run_block.actions_to_do.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
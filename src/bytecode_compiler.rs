@@ -0,0 +1,513 @@
+use std::collections::HashMap;
+
+use crate::access_analysis::analyze_accesses;
+use crate::ast::{is_literal, ASTNode};
+use crate::bytecode::{BytecodeProgram, Function, Instruction, RunBlock};
+use crate::runtime::{parse_literal, ColumnType, Value};
+
+/// Declarations every function can refer to.
+#[derive(Default)]
+struct Declarations<'a> {
+    data_fields: HashMap<String, &'a [Box<ASTNode>]>,
+    group_parameters: HashMap<String, Vec<String>>,
+    group_functions: HashMap<String, usize>,
+    variant_fields: HashMap<String, Vec<ColumnType>>,
+}
+
+struct FunctionCompiler<'a, 'd> {
+    declarations: &'d Declarations<'a>,
+    function: Function,
+    /// The local each variable in scope is stored in.
+    scopes: Vec<HashMap<String, usize>>,
+}
+
+/// Compiles a program that passed every check. Groups come first among the functions,
+/// then do actions and run blocks in the order they are declared.
+pub fn compile_program(program: &ASTNode) -> BytecodeProgram {
+    let statements = match program {
+        ASTNode::Program(statements) => statements,
+        _ => unreachable!(),
+    };
+
+    let mut declarations = Declarations::default();
+    let mut tables = Vec::new();
+    let mut groups = Vec::new();
+
+    for statement in statements {
+        match statement.as_ref() {
+            ASTNode::Data(name, fields, _) => {
+                let columns = fields
+                    .iter()
+                    .filter_map(|field| match field.as_ref() {
+                        ASTNode::Field(field_name, field_type, _, _) => Some((field_name.clone(), ColumnType::of(field_type))),
+                        _ => None,
+                    })
+                    .collect();
+
+                tables.push((name.clone(), columns));
+                declarations.data_fields.insert(name.clone(), fields);
+            },
+            ASTNode::Group(name, parameters, data_instanciations, _) => {
+                let parameters: Vec<(String, ColumnType)> = parameters
+                    .iter()
+                    .filter_map(|parameter| match parameter.as_ref() {
+                        ASTNode::Parameter(parameter_name, parameter_type, _) => {
+                            Some((parameter_name.clone(), ColumnType::of(parameter_type)))
+                        },
+                        _ => None,
+                    })
+                    .collect();
+
+                declarations.group_functions.insert(name.clone(), groups.len());
+                declarations.group_parameters.insert(name.clone(), parameters.iter().map(|(name, _)| name.clone()).collect());
+                groups.push((name, parameters, data_instanciations));
+            },
+            ASTNode::Enum(_, variants, _) => {
+                for variant in variants {
+                    if let ASTNode::Variant(variant_name, fields, _) = variant.as_ref() {
+                        let field_types = fields
+                            .iter()
+                            .filter_map(|field| match field.as_ref() {
+                                ASTNode::Field(_, field_type, _, _) => Some(ColumnType::of(field_type)),
+                                _ => None,
+                            })
+                            .collect();
+
+                        declarations.variant_fields.insert(variant_name.clone(), field_types);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    let mut functions = Vec::new();
+
+    for (name, parameters, data_instanciations) in groups {
+        let mut compiler = FunctionCompiler::new(&declarations, format!("group {}", name), parameters);
+        compiler.compile_group(data_instanciations);
+        functions.push(compiler.function);
+    }
+
+    let mut actions = HashMap::new();
+    let mut run_blocks = Vec::new();
+
+    for statement in statements {
+        let name = match statement.as_ref() {
+            ASTNode::Do(name, _, _) => format!("do {}", name),
+            ASTNode::Run(actions_to_do, _) => format!("run ({})", actions_to_do.join(", ")),
+            _ => continue,
+        };
+
+        let mut compiler = FunctionCompiler::new(&declarations, name, Vec::new());
+
+        match statement.as_ref() {
+            ASTNode::Do(name, instructions, _) => {
+                compiler.compile_block(instructions);
+                actions.insert(name.clone(), functions.len());
+            },
+            ASTNode::Run(actions_to_do, instructions) => {
+                compiler.compile_block(instructions);
+                run_blocks.push(RunBlock { actions_to_do: actions_to_do.clone(), function: functions.len() });
+            },
+            _ => unreachable!(),
+        }
+
+        compiler.emit(Instruction::Return);
+        functions.push(compiler.function);
+    }
+
+    BytecodeProgram {
+        tables,
+        functions,
+        actions,
+        run_blocks,
+        variant_fields: declarations.variant_fields,
+        accesses: analyze_accesses(program),
+    }
+}
+
+impl<'a, 'd> FunctionCompiler<'a, 'd> {
+    fn new(declarations: &'d Declarations<'a>, name: String, parameters: Vec<(String, ColumnType)>) -> FunctionCompiler<'a, 'd> {
+        let scope = parameters.iter().enumerate().map(|(index, (name, _))| (name.clone(), index)).collect();
+
+        FunctionCompiler {
+            declarations,
+            function: Function::new(name, parameters.into_iter().map(|(_, column_type)| column_type).collect()),
+            scopes: vec![scope],
+        }
+    }
+
+    /// Creates an entity, then one row per data the group instanciates. A field takes the value given by the group,
+    /// or else its default value, or else none.
+    fn compile_group(&mut self, data_instanciations: &[Box<ASTNode>]) {
+        let entity = self.new_slot();
+        self.emit(Instruction::NewEntity);
+        self.emit(Instruction::StoreLocal(entity));
+
+        for data_instanciation in data_instanciations {
+            if let ASTNode::DataInstanciation(data_name, field_values) = data_instanciation.as_ref() {
+                self.emit(Instruction::LoadLocal(entity));
+
+                let fields = self.declarations.data_fields[data_name];
+
+                for field in fields {
+                    if let ASTNode::Field(field_name, _, default_value, _) = field.as_ref() {
+                        let field_value = field_values.iter().find_map(|field_value| match field_value.as_ref() {
+                            ASTNode::FieldValue(name, value) if name == field_name => Some(value),
+                            _ => None,
+                        });
+
+                        match (field_value, default_value) {
+                            (Some(value), _) => self.compile_value(value),
+                            (None, Some(default_value)) => self.compile_expression(default_value),
+                            (None, None) => self.emit_constant(Value::None),
+                        }
+                    }
+                }
+
+                let data_name = self.name(data_name);
+                self.emit(Instruction::PushRow(data_name, fields.len()));
+            }
+        }
+
+        self.emit(Instruction::Return);
+    }
+
+    fn compile_block(&mut self, instructions: &[Box<ASTNode>]) {
+        self.scopes.push(HashMap::new());
+
+        for instruction in instructions {
+            self.compile_instruction(instruction);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn compile_instruction(&mut self, instruction: &ASTNode) {
+        match instruction {
+            ASTNode::Declaration(variable_name, value) => {
+                self.compile_expression(value);
+                let local = self.new_local(variable_name);
+                self.emit(Instruction::StoreLocal(local));
+            },
+            ASTNode::Assignment(target, value) => match target.split_once('.') {
+                Some((variable_name, field_name)) => {
+                    let local = self.lookup(variable_name);
+                    self.emit(Instruction::LoadLocal(local));
+                    self.compile_expression(value);
+                    let field_name = self.name(field_name);
+                    self.emit(Instruction::StoreField(field_name));
+                },
+                None => {
+                    self.compile_expression(value);
+                    let local = self.lookup(target);
+                    self.emit(Instruction::StoreLocal(local));
+                },
+            },
+            ASTNode::If(condition, instructions) => {
+                self.compile_expression(condition);
+                let jump = self.emit(Instruction::JumpIfFalse(0));
+                self.compile_block(instructions);
+                self.patch_jump(jump);
+            },
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                self.scopes.push(HashMap::new());
+                self.compile_instruction(declaration);
+
+                let loop_start = self.function.code.len();
+                self.compile_expression(condition);
+                let jump = self.emit(Instruction::JumpIfFalse(0));
+                self.compile_block(instructions);
+                self.compile_instruction(progression);
+                self.emit(Instruction::Jump(loop_start));
+                self.patch_jump(jump);
+
+                self.scopes.pop();
+            },
+            ASTNode::Foreach(values, collections, instructions, parallel) => {
+                self.compile_foreach(values, collections, instructions, *parallel);
+            },
+            ASTNode::Match(value, arms) => self.compile_match(value, arms),
+            ASTNode::CreateInstruction(group_name, parameter_values) => {
+                let parameter_names = &self.declarations.group_parameters[group_name];
+
+                for (index, parameter_name) in parameter_names.iter().enumerate() {
+                    let parameter_value = parameter_values
+                        .iter()
+                        .enumerate()
+                        .find_map(|(position, parameter_value)| match parameter_value.as_ref() {
+                            ASTNode::NamedArgument(name, value) if name == parameter_name => Some(value.as_ref()),
+                            ASTNode::NamedArgument(..) => None,
+                            value if position == index => Some(value),
+                            _ => None,
+                        });
+
+                    match parameter_value {
+                        Some(value) => self.compile_expression(value),
+                        None => self.emit_constant(Value::None),
+                    }
+                }
+
+                self.emit(Instruction::Create(self.declarations.group_functions[group_name]));
+            },
+            node => {
+                self.compile_expression(node);
+                self.emit(Instruction::Pop);
+            },
+        }
+    }
+
+    /// A sequential loop asks for its next iteration at the top of the body and jumps back at the end,
+    /// while the body of a parallel loop ends the iteration so that each thread can run it on its own.
+    fn compile_foreach(&mut self, values: &[String], collections: &[Box<ASTNode>], instructions: &[Box<ASTNode>], parallel: bool) {
+        let data_names: Vec<&str> = collections
+            .iter()
+            .filter_map(|collection| match collection.as_ref() {
+                ASTNode::Value(name) if self.declarations.data_fields.contains_key(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if data_names.len() == collections.len() {
+            let first_name = self.function.names.len();
+            self.function.names.extend(data_names.iter().map(|data_name| data_name.to_string()));
+            self.emit(Instruction::StartJoin(first_name, data_names.len()));
+        }
+        else {
+            for collection in collections {
+                self.compile_expression(collection);
+            }
+
+            self.emit(Instruction::StartZip(collections.len()));
+        }
+
+        self.scopes.push(HashMap::new());
+
+        let first_local = self.function.local_count;
+
+        for value in values {
+            self.new_local(value);
+        }
+
+        if parallel {
+            let loop_start = self.emit(Instruction::RunParallel(first_local, 0));
+            self.compile_block(instructions);
+            self.emit(Instruction::EndIteration);
+            self.patch_jump(loop_start);
+        }
+        else {
+            let loop_start = self.emit(Instruction::NextIteration(first_local, 0));
+            self.compile_block(instructions);
+            self.emit(Instruction::Jump(loop_start));
+            self.patch_jump(loop_start);
+        }
+
+        self.scopes.pop();
+    }
+
+    /// Each arm tests its pattern against the matched value and jumps to the next arm as soon as a test fails.
+    fn compile_match(&mut self, value: &ASTNode, arms: &[Box<ASTNode>]) {
+        self.compile_expression(value);
+        let matched_value = self.new_slot();
+        self.emit(Instruction::StoreLocal(matched_value));
+
+        let mut jumps_to_end = Vec::new();
+
+        for arm in arms {
+            if let ASTNode::MatchArm(pattern, instructions) = arm.as_ref() {
+                self.scopes.push(HashMap::new());
+
+                let mut jumps_to_next_arm = Vec::new();
+                self.compile_pattern(pattern, matched_value, &mut jumps_to_next_arm);
+                self.compile_block(instructions);
+                jumps_to_end.push(self.emit(Instruction::Jump(0)));
+
+                for jump in jumps_to_next_arm {
+                    self.patch_jump(jump);
+                }
+
+                self.scopes.pop();
+            }
+        }
+
+        self.emit(Instruction::LoadLocal(matched_value));
+        self.emit(Instruction::NoMatch);
+
+        for jump in jumps_to_end {
+            self.patch_jump(jump);
+        }
+    }
+
+    fn compile_pattern(&mut self, pattern: &ASTNode, local: usize, jumps_to_next_arm: &mut Vec<usize>) {
+        match pattern {
+            ASTNode::BindingPattern(name) => {
+                self.emit(Instruction::LoadLocal(local));
+                let binding = self.new_local(name);
+                self.emit(Instruction::StoreLocal(binding));
+            },
+            ASTNode::LiteralPattern(literal) => {
+                self.emit(Instruction::LoadLocal(local));
+                self.emit_constant(parse_literal(literal));
+                self.emit(Instruction::Equal);
+                jumps_to_next_arm.push(self.emit(Instruction::JumpIfFalse(0)));
+            },
+            ASTNode::VariantPattern(name, field_patterns) => {
+                self.emit(Instruction::LoadLocal(local));
+                let name = self.name(name);
+                self.emit(Instruction::IsVariant(name));
+                jumps_to_next_arm.push(self.emit(Instruction::JumpIfFalse(0)));
+
+                for (index, field_pattern) in field_patterns.iter().enumerate() {
+                    self.emit(Instruction::LoadLocal(local));
+                    self.emit(Instruction::VariantField(index));
+                    self.compile_sub_pattern(field_pattern, jumps_to_next_arm);
+                }
+            },
+            ASTNode::DataPattern(name, field_patterns) => {
+                self.emit(Instruction::LoadLocal(local));
+                let name = self.name(name);
+                self.emit(Instruction::IsData(name));
+                jumps_to_next_arm.push(self.emit(Instruction::JumpIfFalse(0)));
+
+                for field_pattern in field_patterns {
+                    if let ASTNode::FieldPattern(field_name, sub_pattern) = field_pattern.as_ref() {
+                        self.emit(Instruction::LoadLocal(local));
+                        let field_name = self.name(field_name);
+                        self.emit(Instruction::LoadField(field_name));
+                        self.compile_sub_pattern(sub_pattern, jumps_to_next_arm);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Matches the value on top of the stack, kept in a local of its own.
+    fn compile_sub_pattern(&mut self, pattern: &ASTNode, jumps_to_next_arm: &mut Vec<usize>) {
+        let local = self.new_slot();
+        self.emit(Instruction::StoreLocal(local));
+        self.compile_pattern(pattern, local, jumps_to_next_arm);
+    }
+
+    fn compile_expression(&mut self, node: &ASTNode) {
+        let (lhs, rhs, operation) = match node {
+            ASTNode::Value(value) => return self.compile_value(value),
+            ASTNode::ListLiteral(elements) => {
+                for element in elements {
+                    self.compile_expression(element);
+                }
+
+                self.emit(Instruction::MakeList(elements.len()));
+                return;
+            },
+            ASTNode::VariantConstructor(variant_name, field_values) => {
+                for field_value in field_values {
+                    self.compile_expression(field_value);
+                }
+
+                let variant_name = self.name(variant_name);
+                self.emit(Instruction::MakeVariant(variant_name, field_values.len()));
+                return;
+            },
+            ASTNode::Sum(lhs, rhs) => (lhs, rhs, Instruction::Add),
+            ASTNode::Substraction(lhs, rhs) => (lhs, rhs, Instruction::Subtract),
+            ASTNode::Multiplication(lhs, rhs) => (lhs, rhs, Instruction::Multiply),
+            ASTNode::Division(lhs, rhs, _) => (lhs, rhs, Instruction::Divide),
+            ASTNode::Modulo(lhs, rhs, _) => (lhs, rhs, Instruction::Modulo),
+            ASTNode::Equality(lhs, rhs) => (lhs, rhs, Instruction::Equal),
+            ASTNode::Inequality(lhs, rhs) => (lhs, rhs, Instruction::NotEqual),
+            ASTNode::GreaterThan(lhs, rhs) => (lhs, rhs, Instruction::Greater),
+            ASTNode::LessThan(lhs, rhs) => (lhs, rhs, Instruction::Less),
+            ASTNode::GreaterOrEqual(lhs, rhs) => (lhs, rhs, Instruction::GreaterOrEqual),
+            ASTNode::LessOrEqual(lhs, rhs) => (lhs, rhs, Instruction::LessOrEqual),
+            other => panic!("Cannot compile expression {:?}.", other),
+        };
+
+        self.compile_expression(lhs);
+        self.compile_expression(rhs);
+        self.emit(operation);
+    }
+
+    /// Compiles a literal, a variable, a field of the data a variable refers to, or an enum variant.
+    fn compile_value(&mut self, value: &str) {
+        if is_literal(value) {
+            return self.emit_constant(parse_literal(value));
+        }
+
+        match value.split_once('.') {
+            Some((variable_name, field_name)) => {
+                let local = self.lookup(variable_name);
+                self.emit(Instruction::LoadLocal(local));
+                let field_name = self.name(field_name);
+                self.emit(Instruction::LoadField(field_name));
+            },
+            None if self.declarations.variant_fields.contains_key(value) => {
+                self.emit_constant(Value::Variant(value.to_string(), Vec::new()));
+            },
+            None => {
+                let local = self.lookup(value);
+                self.emit(Instruction::LoadLocal(local));
+            },
+        }
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.function.code.push(instruction);
+        self.function.code.len() - 1
+    }
+
+    fn emit_constant(&mut self, value: Value) {
+        let index = match self.function.constants.iter().position(|constant| constant == &value) {
+            Some(index) => index,
+            None => {
+                self.function.constants.push(value);
+                self.function.constants.len() - 1
+            },
+        };
+
+        self.emit(Instruction::Constant(index));
+    }
+
+    /// Makes a jump emitted earlier go to the next instruction to be emitted.
+    fn patch_jump(&mut self, position: usize) {
+        let target = self.function.code.len();
+
+        self.function.code[position] = match self.function.code[position] {
+            Instruction::Jump(_) => Instruction::Jump(target),
+            Instruction::JumpIfFalse(_) => Instruction::JumpIfFalse(target),
+            Instruction::NextIteration(first_local, _) => Instruction::NextIteration(first_local, target),
+            Instruction::RunParallel(first_local, _) => Instruction::RunParallel(first_local, target),
+            other => unreachable!("{:?} is not a jump.", other),
+        };
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        match self.function.names.iter().position(|existing_name| existing_name == name) {
+            Some(index) => index,
+            None => {
+                self.function.names.push(name.to_string());
+                self.function.names.len() - 1
+            },
+        }
+    }
+
+    fn new_local(&mut self, variable_name: &str) -> usize {
+        let local = self.new_slot();
+        self.scopes.last_mut().unwrap().insert(variable_name.to_string(), local);
+        local
+    }
+
+    /// A local no variable refers to, for values the compiler keeps around.
+    fn new_slot(&mut self) -> usize {
+        self.function.local_count += 1;
+        self.function.local_count - 1
+    }
+
+    fn lookup(&self, variable_name: &str) -> usize {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(variable_name)) {
+            Some(local) => *local,
+            None => panic!("Unknown variable {} in {}.", variable_name, self.function.name),
+        }
+    }
+}
@@ -0,0 +1,625 @@
+use std::collections::HashMap;
+
+use crate::ir::{BinaryOperator, BlockId, FunctionKind, Instruction, IrFunction, IrProgram, IrTable, IrType, Operand, Register, Terminator};
+
+/// Helpers every generated program starts with: the header shared by tables, the merge join over entity columns,
+/// printing values the way run prints them, and arithmetic that stops with an error where run would.
+const PRELUDE: &str = r#"#include <inttypes.h>
+#include <math.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct {
+    size_t length;
+    size_t capacity;
+    size_t *entities;
+} dolang_Table;
+
+static size_t dolang_entity_count;
+
+static inline void *dolang_grow(void *column, size_t capacity, size_t size) {
+    void *grown = realloc(column, capacity * size);
+
+    if (grown == NULL) {
+        fputs("error: Out of memory.\n", stderr);
+        exit(1);
+    }
+
+    return grown;
+}
+
+/* Moves the rows forward until they all point to the same entity, and tells whether there is one. */
+static inline bool dolang_join_next(size_t count, dolang_Table **tables, const size_t *lengths, size_t *rows) {
+    for (;;) {
+        size_t highest_entity = 0;
+        bool same_entity = true;
+
+        for (size_t index = 0; index < count; index++) {
+            if (rows[index] >= lengths[index]) {
+                return false;
+            }
+
+            size_t entity = tables[index]->entities[rows[index]];
+
+            if (index > 0 && entity != highest_entity) {
+                same_entity = false;
+            }
+
+            if (entity > highest_entity) {
+                highest_entity = entity;
+            }
+        }
+
+        if (same_entity) {
+            return true;
+        }
+
+        for (size_t index = 0; index < count; index++) {
+            if (tables[index]->entities[rows[index]] < highest_entity) {
+                rows[index]++;
+            }
+        }
+    }
+}
+
+/* Writes the shortest decimal form that reads back as the same float, always with a fractional part. */
+static inline void dolang_print_float(FILE *file, double value) {
+    char digits[40];
+    int precision = 1;
+
+    if (isnan(value)) {
+        fputs("NaN", file);
+        return;
+    }
+
+    if (isinf(value)) {
+        fputs(value > 0 ? "inf" : "-inf", file);
+        return;
+    }
+
+    for (; precision < 17; precision++) {
+        snprintf(digits, sizeof digits, "%.*e", precision - 1, value);
+
+        if (strtod(digits, NULL) == value) {
+            break;
+        }
+    }
+
+    snprintf(digits, sizeof digits, "%.*e", precision - 1, value);
+    char *exponent_start = strchr(digits, 'e');
+    int exponent = atoi(exponent_start + 1);
+
+    if (value != 0 && (exponent < -4 || exponent >= 16)) {
+        *exponent_start = '\0';
+        fprintf(file, "%se%d", digits, exponent);
+    }
+    else {
+        int decimals = precision - 1 - exponent;
+        fprintf(file, "%.*f%s", decimals > 0 ? decimals : 0, value, decimals > 0 ? "" : ".0");
+    }
+}
+
+static inline void dolang_fail_int(const char *context, const char *message, int64_t lhs, const char *operator, int64_t rhs) {
+    fprintf(stderr, "error: In %s: ", context);
+    fprintf(stderr, message, lhs, operator, rhs);
+    fputc('\n', stderr);
+    exit(1);
+}
+
+static inline int64_t dolang_add(int64_t lhs, int64_t rhs, const char *context) {
+    if ((rhs > 0 && lhs > INT64_MAX - rhs) || (rhs < 0 && lhs < INT64_MIN - rhs)) {
+        dolang_fail_int(context, "%" PRId64 " %s %" PRId64 " overflows a 64 bit integer.", lhs, "+", rhs);
+    }
+
+    return lhs + rhs;
+}
+
+static inline int64_t dolang_subtract(int64_t lhs, int64_t rhs, const char *context) {
+    if ((rhs < 0 && lhs > INT64_MAX + rhs) || (rhs > 0 && lhs < INT64_MIN + rhs)) {
+        dolang_fail_int(context, "%" PRId64 " %s %" PRId64 " overflows a 64 bit integer.", lhs, "-", rhs);
+    }
+
+    return lhs - rhs;
+}
+
+static inline int64_t dolang_multiply(int64_t lhs, int64_t rhs, const char *context) {
+    bool overflows = lhs > 0
+        ? (rhs > 0 ? lhs > INT64_MAX / rhs : rhs < INT64_MIN / lhs)
+        : (rhs > 0 ? lhs < INT64_MIN / rhs : lhs != 0 && rhs < INT64_MAX / lhs);
+
+    if (overflows) {
+        dolang_fail_int(context, "%" PRId64 " %s %" PRId64 " overflows a 64 bit integer.", lhs, "*", rhs);
+    }
+
+    return lhs * rhs;
+}
+
+static inline int64_t dolang_divide(int64_t lhs, int64_t rhs, const char *context, const char *operator) {
+    if (rhs == 0) {
+        dolang_fail_int(context, "Division by zero in %" PRId64 " %s %" PRId64 ".", lhs, operator, rhs);
+    }
+
+    if (lhs == INT64_MIN && rhs == -1) {
+        dolang_fail_int(context, "%" PRId64 " %s %" PRId64 " overflows a 64 bit integer.", lhs, operator, rhs);
+    }
+
+    return operator[0] == '/' ? lhs / rhs : lhs % rhs;
+}
+
+static inline double dolang_divide_float(double lhs, double rhs, const char *context, const char *operator) {
+    if (rhs == 0) {
+        fprintf(stderr, "error: In %s: Division by zero in ", context);
+        dolang_print_float(stderr, lhs);
+        fprintf(stderr, " %s ", operator);
+        dolang_print_float(stderr, rhs);
+        fputs(".\n", stderr);
+        exit(1);
+    }
+
+    return operator[0] == '/' ? lhs / rhs : fmod(lhs, rhs);
+}
+
+static inline const char *dolang_concat(const char *lhs, const char *rhs) {
+    char *result = dolang_grow(NULL, strlen(lhs) + strlen(rhs) + 1, 1);
+    strcpy(result, lhs);
+    strcat(result, rhs);
+    return result;
+}
+
+static inline void dolang_fail(const char *context, const char *message) {
+    fprintf(stderr, "error: In %s: %s\n", context, message);
+    exit(1);
+}
+"#;
+
+const C_KEYWORDS: [&str; 40] = [
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum", "extern",
+    "float", "for", "goto", "if", "inline", "int", "long", "register", "restrict", "return", "short", "signed",
+    "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned", "void", "volatile", "while", "bool",
+    "true", "false", "main", "entity", "row",
+];
+
+struct CGenerator<'a> {
+    program: &'a IrProgram,
+    output: String,
+    indentation: usize,
+}
+
+/// Generates a C99 program: data become tables of one array per field, groups functions that create an entity,
+/// do actions and run blocks functions whose blocks are labels, and main runs the run blocks in order then prints
+/// the world as run does.
+pub fn generate_c(program: &IrProgram) -> String {
+    let mut generator = CGenerator {
+        program,
+        output: PRELUDE.to_string(),
+        indentation: 0,
+    };
+
+    for (enum_name, variants) in &program.enums {
+        generator.generate_enum(enum_name, variants);
+    }
+
+    for table in &program.tables {
+        generator.generate_table(table);
+    }
+
+    let mut run_count = 0;
+
+    for function in &program.functions {
+        let (qualifier, name) = match &function.kind {
+            FunctionKind::Group(group_name) => ("", format!("create_{}", c_name(group_name))),
+            FunctionKind::Do(action_name) => ("", format!("do_{}", c_name(action_name))),
+            FunctionKind::Run(_) => {
+                run_count += 1;
+                ("static ", format!("run_{}", run_count - 1))
+            },
+        };
+
+        generator.generate_function(function, &format!("{}void {}", qualifier, name));
+    }
+
+    generator.generate_main(run_count);
+    generator.output
+}
+
+impl<'a> CGenerator<'a> {
+    /// The names of the variants are only emitted when a field of the enum is printed, as nothing else reads them.
+    fn generate_enum(&mut self, name: &str, variants: &[String]) {
+        let constants: Vec<String> = variants.iter().map(|variant_name| variant_constant(name, variant_name)).collect();
+        let names: Vec<String> = variants.iter().map(|variant_name| format!("\"{}\"", variant_name)).collect();
+
+        self.line("");
+        self.line(&format!("typedef enum {{ {} }} {};", constants.join(", "), c_name(name)));
+
+        let enum_type = IrType::Enum(name.to_string());
+
+        if self.program.tables.iter().flat_map(|table| &table.fields).any(|field| field.field_type == enum_type) {
+            self.line(&format!("static const char *{}_names[] = {{ {} }};", c_name(name), names.join(", ")));
+        }
+    }
+
+    /// A table holds the shared header then one array per field, and an optional field a second array telling whether
+    /// it is set. Each table gets a function that appends a row and one that prints every row.
+    fn generate_table(&mut self, table: &IrTable) {
+        let table_name = c_name(&table.data_name);
+
+        self.line("");
+        self.line("static struct {");
+        self.indentation += 1;
+        self.line("dolang_Table rows;");
+
+        for field in &table.fields {
+            self.line(&format!("{}*{};", pointer_type(&c_type(&field.field_type)), c_name(&field.name)));
+
+            if field.optional {
+                self.line(&format!("bool *{}_is_set;", c_name(&field.name)));
+            }
+        }
+
+        self.indentation -= 1;
+        self.line(&format!("}} {};", table_name));
+
+        let mut parameters = vec!["size_t entity".to_string()];
+
+        for field in &table.fields {
+            if field.optional {
+                parameters.push(format!("bool {}_is_set", c_name(&field.name)));
+            }
+
+            parameters.push(format!("{}{}", pointer_type(&c_type(&field.field_type)), c_name(&field.name)));
+        }
+
+        self.line("");
+        self.line(&format!("void push_{}({}) {{", table_name, parameters.join(", ")));
+        self.indentation += 1;
+        self.line(&format!("if ({0}.rows.length == {0}.rows.capacity) {{", table_name));
+        self.indentation += 1;
+        self.line(&format!("{0}.rows.capacity = {0}.rows.capacity * 2 + 8;", table_name));
+        self.line(&format!("{0}.rows.entities = dolang_grow({0}.rows.entities, {0}.rows.capacity, sizeof(size_t));", table_name));
+
+        for field in &table.fields {
+            let column = format!("{}.{}", table_name, c_name(&field.name));
+            self.line(&format!("{0} = dolang_grow({0}, {1}.rows.capacity, sizeof *{0});", column, table_name));
+
+            if field.optional {
+                self.line(&format!("{0}_is_set = dolang_grow({0}_is_set, {1}.rows.capacity, sizeof(bool));", column, table_name));
+            }
+        }
+
+        self.indentation -= 1;
+        self.line("}");
+        self.line("");
+        self.line(&format!("size_t row = {}.rows.length++;", table_name));
+        self.line(&format!("{}.rows.entities[row] = entity;", table_name));
+
+        for field in &table.fields {
+            let field_name = c_name(&field.name);
+            self.line(&format!("{}.{}[row] = {};", table_name, field_name, field_name));
+
+            if field.optional {
+                self.line(&format!("{0}.{1}_is_set[row] = {1}_is_set;", table_name, field_name));
+            }
+        }
+
+        self.indentation -= 1;
+        self.line("}");
+
+        self.line("");
+        self.line(&format!("static void print_{}(void) {{", table_name));
+        self.indentation += 1;
+        self.line(&format!("printf(\"{} (%zu instances)\\n\", {}.rows.length);", table.data_name, table_name));
+        self.line("");
+        self.line(&format!("for (size_t row = 0; row < {}.rows.length; row++) {{", table_name));
+        self.indentation += 1;
+        self.line(&format!("printf(\"  entity %zu: \", {}.rows.entities[row]);", table_name));
+
+        for (index, field) in table.fields.iter().enumerate() {
+            let separator = if index == 0 { "" } else { ", " };
+            self.line(&format!("fputs(\"{}{} = \", stdout);", separator, field.name));
+
+            let value = format!("{}.{}[row]", table_name, c_name(&field.name));
+
+            match field.optional {
+                true => {
+                    self.line(&format!("if ({}.{}_is_set[row]) {{", table_name, c_name(&field.name)));
+                    self.indentation += 1;
+                    self.generate_print(&field.field_type, &value);
+                    self.indentation -= 1;
+                    self.line("}");
+                    self.line("else {");
+                    self.indentation += 1;
+                    self.line("fputs(\"none\", stdout);");
+                    self.indentation -= 1;
+                    self.line("}");
+                },
+                false => self.generate_print(&field.field_type, &value),
+            }
+        }
+
+        self.line("putchar('\\n');");
+        self.indentation -= 1;
+        self.line("}");
+        self.indentation -= 1;
+        self.line("}");
+    }
+
+    fn generate_print(&mut self, value_type: &IrType, value: &str) {
+        let statement = match value_type {
+            IrType::Int => format!("printf(\"%\" PRId64, {});", value),
+            IrType::Float => format!("dolang_print_float(stdout, {});", value),
+            IrType::Bool => format!("fputs({} ? \"true\" : \"false\", stdout);", value),
+            IrType::String => format!("printf(\"\\\"%s\\\"\", {});", value),
+            IrType::Enum(enum_name) => format!("fputs({}_names[{}], stdout);", c_name(enum_name), value),
+            IrType::Instance(data_name) => format!("printf(\"{}#%zu\", {});", data_name, value),
+            IrType::Entity => format!("printf(\"%zu\", {});", value),
+        };
+
+        self.line(&statement);
+    }
+
+    /// Registers become locals and blocks labels, and each join gets the tables it goes over, their row counts when
+    /// it starts, so that instances created in the loop are not visited, and its current rows.
+    fn generate_function(&mut self, function: &IrFunction, signature: &str) {
+        let parameters: Vec<String> = function.registers[..function.parameter_count]
+            .iter()
+            .enumerate()
+            .map(|(register, (_, register_type))| format!("{}{}", pointer_type(&c_type(register_type)), register_name(register)))
+            .collect();
+
+        let parameters = match parameters.is_empty() {
+            true  => "void".to_string(),
+            false => parameters.join(", "),
+        };
+
+        self.line("");
+        self.line(&format!("{}({}) {{", signature, parameters));
+        self.indentation += 1;
+
+        let read_registers = function.read_registers();
+
+        // A register only ever written, such as a row a join gives that the loop body never reads, is marked as
+        // used so that compilers do not warn about it.
+        for (register, (_, register_type)) in function.registers.iter().enumerate().skip(function.parameter_count) {
+            let register_c_type = c_type(register_type);
+            self.line(&format!("{}{} = {};", pointer_type(&register_c_type), register_name(register), zero_value(&register_c_type)));
+
+            if !read_registers.contains(&register) {
+                self.line(&format!("(void) {};", register_name(register)));
+            }
+        }
+
+        let joins = function.joins();
+
+        for join in 0..function.join_count {
+            let count = joins.get(&join).map_or(1, |data_names| data_names.len());
+            self.line(&format!("dolang_Table *dolang_tables_{}[{}];", join, count));
+            self.line(&format!("size_t dolang_lengths_{}[{}];", join, count));
+            self.line(&format!("size_t dolang_rows_{}[{}];", join, count));
+        }
+
+        let reachable = function.reachable_blocks();
+        let targets: Vec<BlockId> = reachable.iter().flat_map(|block| function.successors(*block)).collect();
+
+        for block in reachable {
+            if targets.contains(&block) {
+                self.indentation -= 1;
+                self.line(&format!("block{}:;", block));
+                self.indentation += 1;
+            }
+
+            for instruction in &function.blocks[block].instructions {
+                self.generate_instruction(function, instruction, &joins);
+            }
+
+            self.generate_terminator(function, &function.blocks[block].terminator, &joins);
+        }
+
+        self.indentation -= 1;
+        self.line("}");
+    }
+
+    fn generate_instruction(&mut self, function: &IrFunction, instruction: &Instruction, joins: &HashMap<usize, Vec<String>>) {
+        match instruction {
+            Instruction::Copy(target, value) => self.line(&format!("{} = {};", register_name(*target), self.operand(value))),
+            Instruction::IntToFloat(target, value) => {
+                self.line(&format!("{} = (double) {};", register_name(*target), self.operand(value)));
+            },
+            Instruction::Binary(target, operator, lhs, rhs) => {
+                let value = self.binary(function, *operator, lhs, rhs);
+                self.line(&format!("{} = {};", register_name(*target), value));
+            },
+            Instruction::LoadField(target, data_name, field_name, row) => {
+                self.line(&format!("{} = {}.{}[{}];", register_name(*target), c_name(data_name), c_name(field_name), self.operand(row)));
+            },
+            Instruction::StoreField(data_name, field_name, row, value) => {
+                let column = format!("{}.{}", c_name(data_name), c_name(field_name));
+                let row = self.operand(row);
+                self.line(&format!("{}[{}] = {};", column, row, self.operand(value)));
+
+                if self.program.table(data_name).and_then(|table| table.field(field_name)).is_some_and(|field| field.optional) {
+                    self.line(&format!("{}_is_set[{}] = true;", column, row));
+                }
+            },
+            Instruction::NewEntity(target) => self.line(&format!("{} = dolang_entity_count++;", register_name(*target))),
+            Instruction::PushRow(data_name, entity, values) => {
+                let mut arguments = vec![self.operand(entity)];
+
+                if let Some(table) = self.program.table(data_name) {
+                    for (field, value) in table.fields.iter().zip(values) {
+                        if field.optional {
+                            arguments.push(value.is_some().to_string());
+                        }
+
+                        arguments.push(match value {
+                            Some(value) => self.operand(value),
+                            None => zero_value(&c_type(&field.field_type)),
+                        });
+                    }
+                }
+
+                self.line(&format!("push_{}({});", c_name(data_name), arguments.join(", ")));
+            },
+            Instruction::Create(group_name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| self.operand(argument)).collect();
+                self.line(&format!("create_{}({});", c_name(group_name), arguments.join(", ")));
+            },
+            Instruction::Do(action_name) => self.line(&format!("do_{}();", c_name(action_name))),
+            Instruction::StartJoin(join, data_names) => {
+                for (index, data_name) in data_names.iter().enumerate() {
+                    self.line(&format!("dolang_tables_{}[{}] = &{}.rows;", join, index, c_name(data_name)));
+                    self.line(&format!("dolang_lengths_{}[{}] = {}.rows.length;", join, index, c_name(data_name)));
+                    self.line(&format!("dolang_rows_{}[{}] = 0;", join, index));
+                }
+            },
+            Instruction::AdvanceJoin(join) => {
+                let count = joins.get(join).map_or(0, |data_names| data_names.len());
+
+                for index in 0..count {
+                    self.line(&format!("dolang_rows_{}[{}]++;", join, index));
+                }
+            },
+        }
+    }
+
+    fn generate_terminator(&mut self, function: &IrFunction, terminator: &Terminator, joins: &HashMap<usize, Vec<String>>) {
+        match terminator {
+            Terminator::Jump(target) => self.line(&format!("goto block{};", target)),
+            Terminator::Branch(condition, then_block, else_block) => {
+                self.line(&format!("if ({}) goto block{};", self.operand(condition), then_block));
+                self.line(&format!("goto block{};", else_block));
+            },
+            Terminator::NextJoin(join, rows, body, exit) => {
+                let count = joins.get(join).map_or(0, |data_names| data_names.len());
+                self.line(&format!(
+                    "if (!dolang_join_next({1}, dolang_tables_{0}, dolang_lengths_{0}, dolang_rows_{0})) goto block{2};",
+                    join, count, exit
+                ));
+
+                for (index, row) in rows.iter().enumerate() {
+                    self.line(&format!("{} = dolang_rows_{}[{}];", register_name(*row), join, index));
+                }
+
+                self.line(&format!("goto block{};", body));
+            },
+            Terminator::NoMatch(_) => {
+                self.line(&format!("dolang_fail(\"{}\", \"No match arm matches the value.\");", function.context));
+            },
+            Terminator::Return => self.line("return;"),
+        }
+    }
+
+    /// Integer arithmetic stops with an error on overflow, and division by zero stops for both integers and floats.
+    fn binary(&self, function: &IrFunction, operator: BinaryOperator, lhs: &Operand, rhs: &Operand) -> String {
+        let operand_type = function.operand_type(lhs);
+        let (lhs, rhs) = (self.operand(lhs), self.operand(rhs));
+        let (context, symbol) = (&function.context, operator.symbol());
+
+        match (operator, operand_type) {
+            (BinaryOperator::Concat, _) => format!("dolang_concat({}, {})", lhs, rhs),
+            (BinaryOperator::Equal | BinaryOperator::NotEqual, IrType::String) => format!("(strcmp({}, {}) {} 0)", lhs, rhs, symbol),
+            (BinaryOperator::Add, IrType::Int) => format!("dolang_add({}, {}, \"{}\")", lhs, rhs, context),
+            (BinaryOperator::Subtract, IrType::Int) => format!("dolang_subtract({}, {}, \"{}\")", lhs, rhs, context),
+            (BinaryOperator::Multiply, IrType::Int) => format!("dolang_multiply({}, {}, \"{}\")", lhs, rhs, context),
+            (BinaryOperator::Divide | BinaryOperator::Modulo, IrType::Int) => {
+                format!("dolang_divide({}, {}, \"{}\", \"{}\")", lhs, rhs, context, symbol)
+            },
+            (BinaryOperator::Divide | BinaryOperator::Modulo, _) => {
+                format!("dolang_divide_float({}, {}, \"{}\", \"{}\")", lhs, rhs, context, symbol)
+            },
+            _ => format!("{} {} {}", lhs, symbol, rhs),
+        }
+    }
+
+    fn operand(&self, operand: &Operand) -> String {
+        match operand {
+            Operand::Register(register) => register_name(*register),
+            Operand::Int(i64::MIN) => "INT64_MIN".to_string(),
+            Operand::Int(value) if *value < 0 => format!("(INT64_C({}))", value),
+            Operand::Int(value) => format!("INT64_C({})", value),
+            Operand::Float(value) if value.is_nan() => "NAN".to_string(),
+            Operand::Float(value) if value.is_infinite() => format!("({}INFINITY)", if *value < 0.0 { "-" } else { "" }),
+            Operand::Float(value) if *value < 0.0 => format!("({:?})", value),
+            Operand::Float(value) => format!("{:?}", value),
+            Operand::Bool(value) => value.to_string(),
+            Operand::String(value) => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+            Operand::Variant(enum_name, index) => variant_constant(enum_name, &self.program.variants(enum_name)[*index]),
+        }
+    }
+
+    fn generate_main(&mut self, run_count: usize) {
+        self.line("");
+        self.line("int main(void) {");
+        self.indentation += 1;
+
+        for run in 0..run_count {
+            self.line(&format!("run_{}();", run));
+        }
+
+        self.line("printf(\"%zu entities\\n\", dolang_entity_count);");
+
+        for table in &self.program.tables {
+            self.line(&format!("print_{}();", c_name(&table.data_name)));
+        }
+
+        self.line("return 0;");
+        self.indentation -= 1;
+        self.line("}");
+    }
+
+    fn line(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.output.push_str(&"    ".repeat(self.indentation));
+            self.output.push_str(text);
+        }
+
+        self.output.push('\n');
+    }
+}
+
+fn register_name(register: Register) -> String {
+    format!("dolang_r{}", register)
+}
+
+fn c_type(value_type: &IrType) -> String {
+    match value_type {
+        IrType::Int => "int64_t".to_string(),
+        IrType::Float => "double".to_string(),
+        IrType::Bool => "bool".to_string(),
+        IrType::String => "const char *".to_string(),
+        IrType::Enum(enum_name) => c_name(enum_name),
+        IrType::Instance(_) | IrType::Entity => "size_t".to_string(),
+    }
+}
+
+/// Qualified names such as physics::Position become physics__Position, and C keywords get an underscore.
+fn c_name(name: &str) -> String {
+    let name = name.replace("::", "__");
+
+    match C_KEYWORDS.contains(&name.as_str()) {
+        true  => format!("{}_", name),
+        false => name,
+    }
+}
+
+fn variant_constant(enum_name: &str, variant_name: &str) -> String {
+    format!("{}_{}", c_name(enum_name), c_name(variant_name))
+}
+
+/// Writes a type so that a name can follow it, with no space after a pointer.
+fn pointer_type(c_type: &str) -> String {
+    match c_type.ends_with('*') {
+        true  => c_type.to_string(),
+        false => format!("{} ", c_type),
+    }
+}
+
+fn zero_value(c_type: &str) -> String {
+    match c_type {
+        "const char *" => "\"\"".to_string(),
+        "bool" => "false".to_string(),
+        _ => "0".to_string(),
+    }
+}
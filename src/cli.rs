@@ -0,0 +1,162 @@
+use crate::diagnostic::Diagnostic;
+use crate::linter::LintLevel;
+use crate::scheduler::ExecutionMode;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Check,
+    Run,
+    Build,
+}
+
+/// The language build writes the program in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    C,
+    Llvm,
+    Wasm,
+    Rust,
+}
+
+impl Target {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Target::C => "c",
+            Target::Llvm => "ll",
+            Target::Wasm => "wat",
+            Target::Rust => "rs",
+        }
+    }
+}
+
+/// What to print once the program is checked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Emit {
+    Ast,
+    Bytecode,
+    Ir,
+}
+
+/// How run executes the program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    Bytecode,
+    TreeWalking,
+}
+
+pub struct Options {
+    pub command: Command,
+    pub source_path: String,
+    pub lint_levels: Vec<(String, LintLevel)>,
+    pub execution_mode: ExecutionMode,
+    pub emit: Option<Emit>,
+    pub engine: Engine,
+    pub target: Target,
+    pub output_path: Option<String>,
+    pub verbose: bool,
+}
+
+/// Reads an optional command, the source file to compile and the -A, -W and -D lint flags, as in run -D unused_let game.do.
+/// Without a command the program is only checked and its syntax tree printed, unless --emit asks for something else.
+/// Run executes bytecode, or walks the syntax tree with --interpret, and --sequential makes it run actions one at a time.
+/// Build writes the program in the language given by --target, next to the source file unless -o gives another path.
+/// --verbose reports the definitions build leaves out as no run block reaches them.
+pub fn parse_options(args: &[String]) -> Result<Options, Diagnostic> {
+    let mut command = Command::Check;
+    let mut source_path = None;
+    let mut lint_levels = Vec::new();
+    let mut execution_mode = ExecutionMode::Parallel;
+    let mut emit = None;
+    let mut engine = Engine::Bytecode;
+    let mut target = Target::C;
+    let mut output_path = None;
+    let mut verbose = false;
+    let mut arguments = args.iter().skip(1).peekable();
+
+    match arguments.peek().map(|argument| argument.as_str()) {
+        Some("run") => command = Command::Run,
+        Some("build") => command = Command::Build,
+        _ => (),
+    }
+
+    if command != Command::Check {
+        arguments.next();
+    }
+
+    while let Some(argument) = arguments.next() {
+        let level = match argument.as_str() {
+            "-A" => LintLevel::Allow,
+            "-W" => LintLevel::Warn,
+            "-D" => LintLevel::Deny,
+            "--sequential" => {
+                execution_mode = ExecutionMode::Sequential;
+                continue;
+            },
+            "--interpret" => {
+                engine = Engine::TreeWalking;
+                continue;
+            },
+            "--verbose" => {
+                verbose = true;
+                continue;
+            },
+            flag if flag.starts_with("--emit=") => {
+                emit = Some(match &flag["--emit=".len()..] {
+                    "ast" => Emit::Ast,
+                    "bytecode" => Emit::Bytecode,
+                    "ir" => Emit::Ir,
+                    other => return Err(Diagnostic::error(format!("Unknown output {} for --emit.", other))
+                        .with_note("expected ast, bytecode or ir".to_string())),
+                });
+                continue;
+            },
+            "--target" => {
+                target = match arguments.next().map(|argument| argument.as_str()) {
+                    Some("c") => Target::C,
+                    Some("llvm") => Target::Llvm,
+                    Some("wasm") => Target::Wasm,
+                    Some("rust") => Target::Rust,
+                    Some(other) => return Err(Diagnostic::error(format!("Unknown target {}.", other))
+                        .with_note("expected c, llvm, wasm or rust".to_string())),
+                    None => return Err(Diagnostic::error("Expected a target after --target.".to_string())),
+                };
+                continue;
+            },
+            "-o" => {
+                match arguments.next() {
+                    Some(path) => output_path = Some(path.clone()),
+                    None => return Err(Diagnostic::error("Expected an output path after -o.".to_string())),
+                }
+                continue;
+            },
+            flag if flag.starts_with('-') => return Err(Diagnostic::error(format!("Unknown option {}.", flag))),
+            _ => {
+                if source_path.replace(argument.clone()).is_some() {
+                    return Err(Diagnostic::error(format!("Unexpected argument {}, only one source file can be given.", argument)));
+                }
+                continue;
+            },
+        };
+
+        match arguments.next() {
+            Some(lint_name) => lint_levels.push((lint_name.clone(), level)),
+            None => return Err(Diagnostic::error(format!("Expected a lint name after {}.", argument))),
+        }
+    }
+
+    match source_path {
+        Some(source_path) => Ok(Options {
+            command,
+            source_path,
+            lint_levels,
+            execution_mode,
+            emit,
+            engine,
+            target,
+            output_path,
+            verbose,
+        }),
+        None => Err(Diagnostic::error("Expected a source file to compile.".to_string())
+            .with_note("usage: dolang-compiler [run|build] [--sequential] [--interpret] [--verbose] [--emit=ast|bytecode|ir] [--target c|llvm|wasm|rust] [-o path] [-A lint] [-W lint] [-D lint] <file.do>".to_string())),
+    }
+}
@@ -0,0 +1,324 @@
+use std::cmp::Ordering;
+
+use crate::ast::ASTNode;
+use crate::diagnostic::Diagnostic;
+use crate::token::Location;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Constant {
+    Int(i64),
+    Float(f64),
+}
+
+struct ConstantFolder {
+    context: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Replaces arithmetic and comparisons between literals by their result and removes x + 0, x - 0, x * 1 and x / 1.
+/// Division and modulo by a constant zero are reported instead of folded.
+pub fn fold_constants(program: &mut ASTNode) -> Vec<Diagnostic> {
+    let mut folder = ConstantFolder {
+        context: "".to_string(),
+        diagnostics: Vec::new(),
+    };
+
+    if let ASTNode::Program(statements) = program {
+        for statement in statements {
+            folder.context = match statement.as_ref() {
+                ASTNode::Data(name, _, _) => format!("data {}", name),
+                ASTNode::Group(name, _, _, _) => format!("group {}", name),
+                ASTNode::Do(name, _, _) => format!("do {}", name),
+                ASTNode::Run(actions_to_do, _) => format!("run ({})", actions_to_do.join(", ")),
+                _ => continue,
+            };
+
+            folder.fold(statement);
+        }
+    }
+
+    folder.diagnostics
+}
+
+impl ConstantFolder {
+    fn fold(&mut self, node: &mut ASTNode) {
+        // Errors point to the expression as written, before its operands are folded.
+        let source_expression = match node {
+            ASTNode::Division(..) | ASTNode::Modulo(..) => describe_expression(node),
+            _ => "".to_string(),
+        };
+
+        for child in node.children_mut() {
+            self.fold(child);
+        }
+
+        let folded_node = match node {
+            ASTNode::Sum(lhs, rhs) => match (constant_of(lhs), constant_of(rhs)) {
+                (Some(lhs), Some(rhs)) => self.fold_arithmetic(lhs, rhs, i64::checked_add, |lhs, rhs| lhs + rhs, "+"),
+                (Some(Constant::Int(0)), None) => Some(take(rhs)),
+                (None, Some(Constant::Int(0))) => Some(take(lhs)),
+                _ => None,
+            },
+            ASTNode::Substraction(lhs, rhs) => match (constant_of(lhs), constant_of(rhs)) {
+                (Some(lhs), Some(rhs)) => self.fold_arithmetic(lhs, rhs, i64::checked_sub, |lhs, rhs| lhs - rhs, "-"),
+                (None, Some(Constant::Int(0))) => Some(take(lhs)),
+                _ => None,
+            },
+            ASTNode::Multiplication(lhs, rhs) => match (constant_of(lhs), constant_of(rhs)) {
+                (Some(lhs), Some(rhs)) => self.fold_arithmetic(lhs, rhs, i64::checked_mul, |lhs, rhs| lhs * rhs, "*"),
+                (Some(Constant::Int(1)), None) => Some(take(rhs)),
+                (None, Some(Constant::Int(1))) => Some(take(lhs)),
+                _ => None,
+            },
+            ASTNode::Division(_, rhs, location) | ASTNode::Modulo(_, rhs, location) if constant_of(rhs).is_some_and(Constant::is_zero) => {
+                let location = *location;
+                let operation = if matches!(node, ASTNode::Division(..)) { "Division" } else { "Modulo" };
+                self.error(format!("{} by zero.", operation), source_expression, location);
+                None
+            },
+            ASTNode::Division(lhs, rhs, _) => match (constant_of(lhs), constant_of(rhs)) {
+                (Some(lhs), Some(rhs)) => self.fold_arithmetic(lhs, rhs, i64::checked_div, |lhs, rhs| lhs / rhs, "/"),
+                (None, Some(Constant::Int(1))) => Some(take(lhs)),
+                _ => None,
+            },
+            ASTNode::Modulo(lhs, rhs, _) => match (constant_of(lhs), constant_of(rhs)) {
+                (Some(lhs), Some(rhs)) => self.fold_arithmetic(lhs, rhs, i64::checked_rem, |lhs, rhs| lhs % rhs, "%"),
+                _ => None,
+            },
+            ASTNode::Equality(lhs, rhs) => fold_equality(lhs, rhs, true),
+            ASTNode::Inequality(lhs, rhs) => fold_equality(lhs, rhs, false),
+            ASTNode::GreaterThan(lhs, rhs) => fold_comparison(lhs, rhs, Ordering::is_gt),
+            ASTNode::LessThan(lhs, rhs) => fold_comparison(lhs, rhs, Ordering::is_lt),
+            ASTNode::GreaterOrEqual(lhs, rhs) => fold_comparison(lhs, rhs, Ordering::is_ge),
+            ASTNode::LessOrEqual(lhs, rhs) => fold_comparison(lhs, rhs, Ordering::is_le),
+            _ => None,
+        };
+
+        if let Some(folded_node) = folded_node {
+            *node = folded_node;
+        }
+    }
+
+    /// Integers stay integers unless the operation overflows, any float operand makes the result a float. A float
+    /// result that is not finite has no literal, so the expression is left as written.
+    fn fold_arithmetic(
+        &mut self,
+        lhs: Constant,
+        rhs: Constant,
+        int_operation: fn(i64, i64) -> Option<i64>,
+        float_operation: fn(f64, f64) -> f64,
+        operator: &str
+    ) -> Option<ASTNode> {
+        match (lhs, rhs) {
+            (Constant::Int(lhs), Constant::Int(rhs)) => match int_operation(lhs, rhs) {
+                Some(result) => Some(ASTNode::new_value(result.to_string())),
+                None => {
+                    self.diagnostics.push(Diagnostic::error(format!(
+                        "In {}: {} {} {} overflows a 64 bit integer.",
+                        self.context,
+                        lhs,
+                        operator,
+                        rhs
+                    )));
+                    None
+                },
+            },
+            _ => match float_operation(lhs.as_float(), rhs.as_float()) {
+                result if result.is_finite() => Some(ASTNode::new_value(format!("{:?}", result))),
+                _ => None,
+            },
+        }
+    }
+
+    fn error(&mut self, message: String, source_expression: String, location: Location) {
+        self.diagnostics.push(
+            Diagnostic::error(format!("In {}: {}", self.context, message))
+                .with_note(format!("in expression {} at {}", source_expression, location))
+        );
+    }
+}
+
+impl Constant {
+    fn is_zero(self) -> bool {
+        self.as_float() == 0.0
+    }
+
+    fn as_float(self) -> f64 {
+        match self {
+            Constant::Int(value) => value as f64,
+            Constant::Float(value) => value,
+        }
+    }
+}
+
+fn constant_of(node: &ASTNode) -> Option<Constant> {
+    match node {
+        ASTNode::Value(value) => match value.parse::<i64>() {
+            Ok(value) => Some(Constant::Int(value)),
+            Err(_) if value.trim_start_matches('-').starts_with(|character: char| character.is_ascii_digit()) => {
+                value.parse::<f64>().ok().map(Constant::Float)
+            },
+            Err(_) => None,
+        },
+        _ => None,
+    }
+}
+
+fn fold_comparison(lhs: &ASTNode, rhs: &ASTNode, holds: fn(Ordering) -> bool) -> Option<ASTNode> {
+    let ordering = match (constant_of(lhs)?, constant_of(rhs)?) {
+        (Constant::Int(lhs), Constant::Int(rhs)) => lhs.cmp(&rhs),
+        (lhs, rhs) => lhs.as_float().partial_cmp(&rhs.as_float())?,
+    };
+
+    Some(ASTNode::new_value(holds(ordering).to_string()))
+}
+
+/// Literals of the same kind are compared by value, numbers across int and float.
+fn fold_equality(lhs: &ASTNode, rhs: &ASTNode, equal: bool) -> Option<ASTNode> {
+    let are_equal = match (constant_of(lhs), constant_of(rhs)) {
+        (Some(Constant::Int(lhs)), Some(Constant::Int(rhs))) => lhs == rhs,
+        (Some(lhs), Some(rhs)) => lhs.as_float() == rhs.as_float(),
+        _ => match (lhs, rhs) {
+            (ASTNode::Value(lhs), ASTNode::Value(rhs)) if is_text_or_bool(lhs) && is_text_or_bool(rhs) => lhs == rhs,
+            _ => return None,
+        },
+    };
+
+    Some(ASTNode::new_value((are_equal == equal).to_string()))
+}
+
+fn is_text_or_bool(value: &str) -> bool {
+    value.starts_with('"') || value == "true" || value == "false"
+}
+
+fn take(node: &mut ASTNode) -> ASTNode {
+    std::mem::replace(node, ASTNode::new_value("".to_string()))
+}
+
+/// Writes an expression back in source form, as a pointer to where an error is.
+fn describe_expression(node: &ASTNode) -> String {
+    let (lhs, operator, rhs) = match node {
+        ASTNode::Value(value) => return value.clone(),
+        ASTNode::Sum(lhs, rhs) => (lhs, "+", rhs),
+        ASTNode::Substraction(lhs, rhs) => (lhs, "-", rhs),
+        ASTNode::Multiplication(lhs, rhs) => (lhs, "*", rhs),
+        ASTNode::Division(lhs, rhs, _) => (lhs, "/", rhs),
+        ASTNode::Modulo(lhs, rhs, _) => (lhs, "%", rhs),
+        ASTNode::Equality(lhs, rhs) => (lhs, "==", rhs),
+        ASTNode::Inequality(lhs, rhs) => (lhs, "!=", rhs),
+        ASTNode::GreaterThan(lhs, rhs) => (lhs, ">", rhs),
+        ASTNode::LessThan(lhs, rhs) => (lhs, "<", rhs),
+        ASTNode::GreaterOrEqual(lhs, rhs) => (lhs, ">=", rhs),
+        ASTNode::LessOrEqual(lhs, rhs) => (lhs, "<=", rhs),
+        ASTNode::ListLiteral(elements) => {
+            let elements: Vec<String> = elements.iter().map(|element| describe_expression(element)).collect();
+            return format!("[{}]", elements.join(", "));
+        },
+        _ => return "...".to_string(),
+    };
+
+    let describe_operand = |operand: &ASTNode| match operand {
+        ASTNode::Value(_) => describe_expression(operand),
+        _ => format!("({})", describe_expression(operand)),
+    };
+
+    format!("{} {} {}", describe_operand(lhs), operator, describe_operand(rhs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast_parser::parse_ast;
+    use crate::tokenizer::tokenize;
+
+    /// Folds the let statements of a do action, and gives the value of each as written back and the diagnostics.
+    fn fold(statements: &str) -> (Vec<String>, Vec<Diagnostic>) {
+        let mut program = parse_ast(tokenize(format!("do D {{\n{}\n}}", statements)));
+        let diagnostics = fold_constants(&mut program);
+
+        let values = match &program {
+            ASTNode::Program(statements) => match statements[0].as_ref() {
+                ASTNode::Do(_, instructions, _) => instructions
+                    .iter()
+                    .filter_map(|instruction| match instruction.as_ref() {
+                        ASTNode::Declaration(_, value) => Some(describe_expression(value)),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        (values, diagnostics)
+    }
+
+    #[test]
+    fn folds_int_and_float_arithmetic() {
+        let (values, diagnostics) = fold(
+            "let a = 1 + 2 * 3\nlet b = 7 / 2\nlet c = 7 % 3\nlet d = 1.5 + 1\nlet e = 7.0 / 2\nlet f = 2 * 0.5"
+        );
+
+        assert_eq!(values, vec!["7", "3", "1", "2.5", "3.5", "1.0"]);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn removes_neutral_operands() {
+        let (values, _) = fold(
+            "let x = 2.5\nlet a = x + 0\nlet b = 0 + x\nlet c = x - 0\nlet d = x * 1\nlet e = 1 * x\nlet f = x / 1\nlet g = 0 - x"
+        );
+
+        assert_eq!(values, vec!["2.5", "x", "x", "x", "x", "x", "x", "0 - x"]);
+    }
+
+    #[test]
+    fn multiplying_by_one_keeps_the_float_type() {
+        let (values, _) = fold("let n = 3\nlet a = 2.0 * 1\nlet b = n * 1.0\nlet c = n + 0.0\nlet d = n / 1.0");
+
+        assert_eq!(values, vec!["3", "2.0", "n * 1.0", "n + 0.0", "n / 1.0"]);
+    }
+
+    #[test]
+    fn reports_integer_overflow_instead_of_wrapping() {
+        let (values, diagnostics) = fold("let a = 9223372036854775807 + 1\nlet b = 0 - 9223372036854775807 - 2");
+
+        assert_eq!(values, vec!["9223372036854775807 + 1", "-9223372036854775807 - 2"]);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "In do D: 9223372036854775807 + 1 overflows a 64 bit integer.");
+        assert_eq!(diagnostics[1].message, "In do D: -9223372036854775807 - 2 overflows a 64 bit integer.");
+    }
+
+    #[test]
+    fn leaves_infinite_and_nan_results_unfolded() {
+        let (values, diagnostics) = fold(&format!("let a = {:.1} * 10.0", f64::MAX));
+
+        assert_eq!(values, vec![format!("{:.1} * 10.0", f64::MAX)]);
+        assert!(diagnostics.is_empty());
+
+        let mut folder = ConstantFolder { context: "do D".to_string(), diagnostics: Vec::new() };
+
+        let not_a_number = folder.fold_arithmetic(
+            Constant::Float(0.0),
+            Constant::Float(f64::INFINITY),
+            i64::checked_mul,
+            |lhs, rhs| lhs * rhs,
+            "*"
+        );
+
+        assert!(not_a_number.is_none());
+        assert!(folder.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn points_division_and_modulo_by_zero_at_the_operator() {
+        let (values, diagnostics) = fold("let a = 1 / 0\n  let b = 5 % (2 - 2)");
+
+        assert_eq!(values, vec!["1 / 0", "5 % 0"]);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "In do D: Division by zero.");
+        assert_eq!(diagnostics[0].notes, vec!["in expression 1 / 0 at line 2, column 11"]);
+        assert_eq!(diagnostics[1].message, "In do D: Modulo by zero.");
+        assert_eq!(diagnostics[1].notes, vec!["in expression 5 % (2 - 2) at line 3, column 13"]);
+    }
+}
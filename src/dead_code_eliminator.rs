@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+
+use crate::ir::{FunctionKind, Instruction, IrFunction, IrProgram, IrType};
+
+/// Removes the do actions and groups no run block reaches, and the data nothing that remains uses, as their tables
+/// would stay empty. The world is then printed without them. Gives what was removed, such as do Move or data
+/// Position, in the order it was declared.
+pub fn eliminate_dead_code(program: &mut IrProgram) -> Vec<String> {
+    let mut reached: Vec<bool> = program.functions.iter().map(|function| matches!(function.kind, FunctionKind::Run(_))).collect();
+    let mut worklist: Vec<usize> = (0..program.functions.len()).filter(|index| reached[*index]).collect();
+
+    while let Some(index) = worklist.pop() {
+        for callee in called_functions(program, &program.functions[index]) {
+            if !reached[callee] {
+                reached[callee] = true;
+                worklist.push(callee);
+            }
+        }
+    }
+
+    let mut used_data = HashSet::new();
+    let mut data_worklist: Vec<String> = program
+        .functions
+        .iter()
+        .zip(&reached)
+        .filter(|(_, reached)| **reached)
+        .flat_map(|(function, _)| used_data_names(function))
+        .collect();
+
+    while let Some(data_name) = data_worklist.pop() {
+        if used_data.insert(data_name.clone()) {
+            if let Some(table) = program.table(&data_name) {
+                data_worklist.extend(table.fields.iter().filter_map(|field| instance_data(&field.field_type)));
+            }
+        }
+    }
+
+    let mut removed: Vec<String> = program
+        .functions
+        .iter()
+        .zip(&reached)
+        .filter(|(_, reached)| !**reached)
+        .map(|(function, _)| function.context.clone())
+        .collect();
+
+    removed.extend(
+        program
+            .tables
+            .iter()
+            .filter(|table| !used_data.contains(&table.data_name))
+            .map(|table| format!("data {}", table.data_name))
+    );
+
+    removed.sort_by_key(|removed| program.declarations.iter().position(|declaration| declaration == removed));
+
+    let mut reached = reached.into_iter();
+    program.functions.retain(|_| reached.next().unwrap_or(true));
+    program.tables.retain(|table| used_data.contains(&table.data_name));
+
+    removed
+}
+
+/// The functions of the groups a function creates and of the actions it does.
+fn called_functions(program: &IrProgram, function: &IrFunction) -> Vec<usize> {
+    function
+        .blocks
+        .iter()
+        .flat_map(|block| &block.instructions)
+        .filter_map(|instruction| {
+            let callee = match instruction {
+                Instruction::Create(group_name, _) => FunctionKind::Group(group_name.clone()),
+                Instruction::Do(action_name) => FunctionKind::Do(action_name.clone()),
+                _ => return None,
+            };
+
+            program.functions.iter().position(|function| function.kind == callee)
+        })
+        .collect()
+}
+
+/// The data whose tables a function reads or writes, or whose instances its registers hold.
+fn used_data_names(function: &IrFunction) -> Vec<String> {
+    let mut data_names: Vec<String> = function.registers.iter().filter_map(|(_, register_type)| instance_data(register_type)).collect();
+
+    for instruction in function.blocks.iter().flat_map(|block| &block.instructions) {
+        match instruction {
+            Instruction::LoadField(_, data_name, _, _)
+            | Instruction::StoreField(data_name, _, _, _)
+            | Instruction::PushRow(data_name, _, _) => data_names.push(data_name.clone()),
+            Instruction::StartJoin(_, joined_data_names) => data_names.extend(joined_data_names.iter().cloned()),
+            _ => (),
+        }
+    }
+
+    data_names
+}
+
+fn instance_data(value_type: &IrType) -> Option<String> {
+    match value_type {
+        IrType::Instance(data_name) => Some(data_name.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast_parser::parse_ast;
+    use crate::ir_builder::lower_program;
+    use crate::tokenizer::tokenize;
+
+    /// Lowers a program and eliminates its dead code, giving what was removed and what remains.
+    fn eliminate(source: &str) -> (Vec<String>, IrProgram) {
+        let (mut program, diagnostics) = lower_program(&parse_ast(tokenize(source.to_string())));
+        assert!(diagnostics.is_empty());
+
+        let removed = eliminate_dead_code(&mut program);
+        (removed, program)
+    }
+
+    fn function_contexts(program: &IrProgram) -> Vec<&str> {
+        program.functions.iter().map(|function| function.context.as_str()).collect()
+    }
+
+    fn table_names(program: &IrProgram) -> Vec<&str> {
+        program.tables.iter().map(|table| table.data_name.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_a_group_only_an_action_creates() {
+        let (removed, program) = eliminate(r#"
+            data Position { x: float }
+            group Spawner(x: float) { Position(x = x) }
+            do Spawn { create Spawner(1.0) }
+            run (Spawn) { }
+        "#);
+
+        assert!(removed.is_empty());
+        assert_eq!(function_contexts(&program), vec!["group Spawner", "do Spawn", "run (Spawn)"]);
+        assert_eq!(table_names(&program), vec!["Position"]);
+    }
+
+    #[test]
+    fn keeps_a_data_only_a_field_type_uses() {
+        let (removed, program) = eliminate(r#"
+            data Position { x: float }
+            data Target { at?: Position, n: int }
+            group Seeker(n: int) { Target(n = n) }
+            run () { create Seeker(1) }
+        "#);
+
+        assert!(removed.is_empty());
+        assert_eq!(table_names(&program), vec!["Position", "Target"]);
+    }
+
+    #[test]
+    fn removes_a_do_no_run_block_lists() {
+        let (removed, program) = eliminate(r#"
+            data Health { hp: int }
+            group Unit(hp: int) { Health(hp = hp) }
+            do Damage { foreach h in Health { h.hp = h.hp - 1 } }
+            do Heal { foreach h in Health { h.hp = h.hp + 1 } }
+            run (Damage) { create Unit(10) }
+        "#);
+
+        assert_eq!(removed, vec!["do Heal"]);
+        assert_eq!(function_contexts(&program), vec!["group Unit", "do Damage", "run (Damage)"]);
+    }
+
+    #[test]
+    fn gives_what_was_removed_in_declaration_order() {
+        let (removed, _) = eliminate(r#"
+            data Unused { u: int }
+            data Position { x: float }
+            do Idle { foreach u in Unused { u.u = u.u + 1 } }
+            group Marker(x: float) { Position(x = x) }
+            data Health { hp: int }
+            group Unit(hp: int) { Health(hp = hp) }
+            run () { create Unit(10) }
+        "#);
+
+        assert_eq!(removed, vec!["data Unused", "data Position", "do Idle", "group Marker"]);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::ast::ASTNode;
+use crate::diagnostic::Diagnostic;
+use crate::token::Location;
+
+struct DeclarationCollector<'a> {
+    globals: HashMap<String, (String, usize, Location)>,
+    module_files: &'a HashMap<String, PathBuf>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Reports every name declared twice in the same namespace, pointing to its first definition.
+/// Module files name the file of an imported module, whose declarations are named module::Name.
+pub fn collect_declarations(program: &ASTNode, module_files: &HashMap<String, PathBuf>) -> Vec<Diagnostic> {
+    let mut collector = DeclarationCollector {
+        globals: HashMap::new(),
+        module_files,
+        diagnostics: Vec::new(),
+    };
+
+    if let ASTNode::Program(statements) = program {
+        for (index, statement) in statements.iter().enumerate() {
+            collector.collect_statement(statement, index + 1);
+        }
+    }
+
+    collector.diagnostics
+}
+
+impl DeclarationCollector<'_> {
+    fn collect_statement(&mut self, statement: &ASTNode, position: usize) {
+        match statement {
+            ASTNode::Data(name, fields, location) => {
+                self.declare_global(name, format!("data {}", name), position, *location);
+                self.check_unique_names(fields, "field", name, &format!("data {}", name));
+            },
+            ASTNode::Group(name, parameters, _, location) => {
+                self.declare_global(name, format!("group {}", name), position, *location);
+                self.check_unique_names(parameters, "parameter", name, &format!("group {}", name));
+            },
+            ASTNode::Do(name, _, location) => self.declare_global(name, format!("do {}", name), position, *location),
+            ASTNode::Enum(name, variants, location) => {
+                self.declare_global(name, format!("enum {}", name), position, *location);
+                self.check_unique_names(variants, "variant", name, &format!("enum {}", name));
+
+                for variant in variants {
+                    if let ASTNode::Variant(variant_name, fields, variant_location) = variant.as_ref() {
+                        self.declare_global(
+                            variant_name,
+                            format!("variant {} of enum {}", variant_name, name),
+                            position,
+                            *variant_location
+                        );
+                        self.check_unique_names(fields, "field", variant_name, &format!("variant {}", variant_name));
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn declare_global(&mut self, name: &str, description: String, position: usize, location: Location) {
+        match self.globals.get(name) {
+            // A variant repeated within its enum is already reported as a duplicate variant.
+            Some((first_description, first_position, _)) if first_description == &description && first_position == &position => (),
+            Some((first_description, _, first_location)) => {
+                self.diagnostics.push(Diagnostic::error(format!(
+                    "{} is defined more than once.",
+                    capitalize(&description)
+                )).with_note(format!("first defined as {} at {}", first_description, self.locate(*first_location, name))));
+            },
+            None => {
+                self.globals.insert(name.to_string(), (description, position, location));
+            },
+        }
+    }
+
+    fn check_unique_names(&mut self, nodes: &[Box<ASTNode>], kind: &str, owner_name: &str, owner: &str) {
+        let mut first_locations: HashMap<&str, (usize, Location)> = HashMap::new();
+
+        for (index, node) in nodes.iter().enumerate() {
+            let (name, location) = match node.as_ref() {
+                ASTNode::Field(name, _, _, location)
+                | ASTNode::Parameter(name, _, location)
+                | ASTNode::Variant(name, _, location) => (name, *location),
+                _ => continue,
+            };
+
+            match first_locations.get(name.as_str()) {
+                Some((first_position, first_location)) => {
+                    self.diagnostics.push(Diagnostic::error(format!(
+                        "{} {} of {} is declared more than once.",
+                        capitalize(kind),
+                        name,
+                        owner
+                    )).with_note(format!(
+                        "first declared as {} {} of {} at {}",
+                        kind,
+                        first_position,
+                        owner,
+                        self.locate(*first_location, owner_name)
+                    )));
+                },
+                None => {
+                    first_locations.insert(name, (index + 1, location));
+                },
+            }
+        }
+    }
+
+    /// Names the file along with the location when the name belongs to an imported module.
+    fn locate(&self, location: Location, name: &str) -> String {
+        let module_file = name.rsplit_once("::").and_then(|(module_name, _)| self.module_files.get(module_name));
+
+        match module_file {
+            Some(file) => format!("{} of {}", location, file.display()),
+            None => location.to_string(),
+        }
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut characters = text.chars();
+
+    match characters.next() {
+        Some(first) => first.to_uppercase().chain(characters).collect(),
+        None => "".to_string(),
+    }
+}
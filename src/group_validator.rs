@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use crate::ast::ASTNode;
+use crate::diagnostic::Diagnostic;
+use crate::resolver::closest_name;
+use crate::type_checker::{Type, TypeChecker};
+
+struct GroupValidator {
+    type_checker: TypeChecker,
+    fields_with_default: HashMap<String, Vec<String>>,
+    context: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Checks every data instanciation of a group against its data declaration
+/// and every create instruction against the parameters of its group.
+/// Fields with a default value or an optional type may be left out.
+pub fn validate_groups(program: &ASTNode) -> Vec<Diagnostic> {
+    let mut validator = GroupValidator {
+        type_checker: TypeChecker::new(program),
+        fields_with_default: HashMap::new(),
+        context: "".to_string(),
+        diagnostics: Vec::new(),
+    };
+
+    // Declarations were already checked by the type checker.
+    validator.type_checker.diagnostics.clear();
+
+    if let ASTNode::Program(statements) = program {
+        for statement in statements {
+            if let ASTNode::Data(data_name, fields, _) = statement.as_ref() {
+                let fields_with_default = fields
+                    .iter()
+                    .filter_map(|field| match field.as_ref() {
+                        ASTNode::Field(field_name, _, Some(_), _) => Some(field_name.clone()),
+                        _ => None,
+                    })
+                    .collect();
+
+                validator.fields_with_default.insert(data_name.clone(), fields_with_default);
+            }
+        }
+
+        for statement in statements {
+            validator.validate_statement(statement);
+        }
+    }
+
+    validator.diagnostics.append(&mut validator.type_checker.diagnostics);
+    validator.diagnostics
+}
+
+impl GroupValidator {
+    fn validate_statement(&mut self, statement: &ASTNode) {
+        match statement {
+            ASTNode::Group(name, _, data_instanciations, _) => {
+                self.set_context(format!("group {}", name));
+
+                let parameters = self.type_checker.group_parameters.get(name).cloned().unwrap_or_default();
+                self.type_checker.enter_scope(parameters.into_iter().collect::<HashMap<_, _>>());
+
+                for data_instanciation in data_instanciations {
+                    if let ASTNode::DataInstanciation(data_name, field_values) = data_instanciation.as_ref() {
+                        self.validate_data_instanciation(data_name, field_values);
+                    }
+                }
+
+                self.type_checker.exit_scope();
+            },
+            ASTNode::Do(name, instructions, _) => {
+                self.set_context(format!("do {}", name));
+                self.validate_instructions(instructions);
+            },
+            ASTNode::Run(actions_to_do, instructions) => {
+                self.set_context(format!("run ({})", actions_to_do.join(", ")));
+                self.validate_instructions(instructions);
+            },
+            _ => (),
+        }
+    }
+
+    fn validate_instructions(&mut self, instructions: &[Box<ASTNode>]) {
+        for instruction in instructions {
+            match instruction.as_ref() {
+                ASTNode::CreateInstruction(group_name, parameter_values) => {
+                    self.validate_create_instruction(group_name, parameter_values);
+                },
+                ASTNode::If(_, body) | ASTNode::Foreach(_, _, body, _) | ASTNode::For(_, _, _, body) => {
+                    self.validate_instructions(body);
+                },
+                ASTNode::Match(_, arms) => {
+                    for arm in arms {
+                        if let ASTNode::MatchArm(_, body) = arm.as_ref() {
+                            self.validate_instructions(body);
+                        }
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+
+    fn validate_data_instanciation(&mut self, data_name: &str, field_values: &[Box<ASTNode>]) {
+        let fields = match self.type_checker.data_fields.get(data_name) {
+            Some(fields) => fields.clone(),
+            None => return,
+        };
+
+        let mut set_fields: Vec<&str> = Vec::new();
+
+        for field_value in field_values {
+            if let ASTNode::FieldValue(field_name, value) = field_value.as_ref() {
+                if set_fields.contains(&field_name.as_str()) {
+                    self.error(format!("Field {} of data {} is set more than once.", field_name, data_name));
+                    continue;
+                }
+
+                set_fields.push(field_name);
+
+                match fields.iter().find(|(name, _)| name == field_name) {
+                    Some((_, field_type)) => {
+                        let value_type = self.type_checker.infer_value(value);
+
+                        if !field_type.accepts(&value_type) {
+                            self.error(format!(
+                                "Field {} of data {} expects {} but got {} ({}).",
+                                field_name,
+                                data_name,
+                                field_type,
+                                value_type,
+                                value
+                            ));
+                        }
+                    },
+                    None => {
+                        let suggestion = closest_name(field_name, fields.iter().map(|(name, _)| name.clone()));
+                        self.unknown_name(format!("Data {} has no field named {}.", data_name, field_name), suggestion);
+                    },
+                }
+            }
+        }
+
+        let fields_with_default = self.fields_with_default.get(data_name).cloned().unwrap_or_default();
+
+        let missing_fields: Vec<String> = fields
+            .iter()
+            .filter(|(name, _)| !set_fields.contains(&name.as_str()))
+            .filter(|(name, field_type)| !fields_with_default.contains(name) && !matches!(field_type, Type::Optional(_)))
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        if !missing_fields.is_empty() {
+            self.error(format!(
+                "Missing field(s) {} in instanciation of data {}.",
+                missing_fields.join(", "),
+                data_name
+            ));
+        }
+    }
+
+    fn validate_create_instruction(&mut self, group_name: &str, parameter_values: &[Box<ASTNode>]) {
+        let parameters = match self.type_checker.group_parameters.get(group_name) {
+            Some(parameters) => parameters.clone(),
+            None => return,
+        };
+
+        let mut given_parameters: Vec<&str> = Vec::new();
+
+        for (index, parameter_value) in parameter_values.iter().enumerate() {
+            match parameter_value.as_ref() {
+                ASTNode::NamedArgument(parameter_name, _) => {
+                    if !parameters.iter().any(|(name, _)| name == parameter_name) {
+                        let suggestion = closest_name(parameter_name, parameters.iter().map(|(name, _)| name.clone()));
+                        self.unknown_name(format!("Group {} has no parameter named {}.", group_name, parameter_name), suggestion);
+                    }
+                    else if given_parameters.contains(&parameter_name.as_str()) {
+                        self.error(format!("Parameter {} of group {} is given more than once.", parameter_name, group_name));
+                    }
+                    else {
+                        given_parameters.push(parameter_name);
+                    }
+                },
+                _ => match parameters.get(index) {
+                    Some((parameter_name, _)) => given_parameters.push(parameter_name),
+                    None => {
+                        self.error(format!(
+                            "Group {} takes {} parameter(s) but {} were given.",
+                            group_name,
+                            parameters.len(),
+                            parameter_values.len()
+                        ));
+                        return;
+                    },
+                },
+            }
+        }
+
+        let missing_parameters: Vec<String> = parameters
+            .iter()
+            .filter(|(name, _)| !given_parameters.contains(&name.as_str()))
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        if !missing_parameters.is_empty() {
+            self.error(format!(
+                "Missing argument(s) for parameter(s) {} in creation of group {}.",
+                missing_parameters.join(", "),
+                group_name
+            ));
+        }
+    }
+
+    fn set_context(&mut self, context: String) {
+        self.type_checker.context = context.clone();
+        self.context = context;
+    }
+
+    fn unknown_name(&mut self, message: String, suggestion: Option<String>) {
+        let mut diagnostic = Diagnostic::error(format!("In {}: {}", self.context, message));
+
+        if let Some(suggestion) = suggestion {
+            diagnostic = diagnostic.with_note(format!("did you mean {}?", suggestion));
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn error(&mut self, message: String) {
+        self.diagnostics.push(Diagnostic::error(format!("In {}: {}", self.context, message)));
+    }
+}
@@ -0,0 +1,486 @@
+use std::collections::HashMap;
+
+use crate::access_analysis::analyze_accesses;
+use crate::ast::{is_literal, ASTNode};
+use crate::diagnostic::Diagnostic;
+use crate::runtime::{
+    apply_arithmetic, compare, new_list, parse_literal, values_equal,
+    ColumnType, Storage, Value, World
+};
+use crate::scheduler::{execute_in_chunks, execute_stage, schedule, ExecutionMode};
+
+/// Parameters of a group with the type of column they would be stored in, and the data it instanciates.
+struct GroupDeclaration<'a> {
+    parameters: Vec<(String, ColumnType)>,
+    data_instanciations: &'a [Box<ASTNode>],
+}
+
+/// Declarations the interpreter needs, shared by every action of a stage.
+#[derive(Default)]
+struct Declarations<'a> {
+    data_defaults: HashMap<String, Vec<Option<&'a ASTNode>>>,
+    groups: HashMap<String, GroupDeclaration<'a>>,
+    actions: HashMap<String, &'a [Box<ASTNode>]>,
+    variant_fields: HashMap<String, Vec<ColumnType>>,
+}
+
+struct Interpreter<'a, 's> {
+    declarations: &'s Declarations<'a>,
+    world: &'s mut dyn Storage,
+    scopes: Vec<HashMap<String, Value>>,
+    context: String,
+}
+
+/// Executes every run block in order: its own instructions first, then each listed do action in turn.
+/// In parallel mode, actions that do not conflict over data run at the same time, which gives the same world.
+/// The program must have passed every check, so only errors that depend on values are reported.
+pub fn interpret(program: &ASTNode, mode: ExecutionMode) -> Result<World, Diagnostic> {
+    let statements = match program {
+        ASTNode::Program(statements) => statements,
+        _ => unreachable!(),
+    };
+
+    let mut declarations = Declarations::default();
+    let mut world = World::default();
+
+    for statement in statements {
+        declarations.collect(statement, &mut world);
+    }
+
+    let accesses = analyze_accesses(program);
+
+    for statement in statements {
+        if let ASTNode::Run(actions_to_do, instructions) = statement.as_ref() {
+            let context = format!("run ({})", actions_to_do.join(", "));
+            Interpreter::new(&declarations, &mut world, context).execute_block(instructions, HashMap::new())?;
+
+            for stage in schedule(actions_to_do, &accesses, mode) {
+                execute_stage(&mut world, &stage, &accesses, &|action_to_do, storage| {
+                    let context = format!("do {}", action_to_do);
+                    Interpreter::new(&declarations, storage, context).execute_block(declarations.actions[action_to_do], HashMap::new())
+                })?;
+            }
+        }
+    }
+
+    Ok(world)
+}
+
+impl<'a> Declarations<'a> {
+    fn collect(&mut self, statement: &'a ASTNode, world: &mut World) {
+        match statement {
+            ASTNode::Data(name, fields, _) => {
+                let mut columns = Vec::new();
+                let mut defaults = Vec::new();
+
+                for field in fields {
+                    if let ASTNode::Field(field_name, field_type, default_value, _) = field.as_ref() {
+                        columns.push((field_name.clone(), ColumnType::of(field_type)));
+                        defaults.push(default_value.as_deref());
+                    }
+                }
+
+                world.add_table(name, columns);
+                self.data_defaults.insert(name.clone(), defaults);
+            },
+            ASTNode::Group(name, parameters, data_instanciations, _) => {
+                let parameters = parameters
+                    .iter()
+                    .filter_map(|parameter| match parameter.as_ref() {
+                        ASTNode::Parameter(parameter_name, parameter_type, _) => {
+                            Some((parameter_name.clone(), ColumnType::of(parameter_type)))
+                        },
+                        _ => None,
+                    })
+                    .collect();
+
+                self.groups.insert(name.clone(), GroupDeclaration { parameters, data_instanciations });
+            },
+            ASTNode::Do(name, instructions, _) => {
+                self.actions.insert(name.clone(), instructions);
+            },
+            ASTNode::Enum(_, variants, _) => {
+                for variant in variants {
+                    if let ASTNode::Variant(variant_name, fields, _) = variant.as_ref() {
+                        let field_types = fields
+                            .iter()
+                            .filter_map(|field| match field.as_ref() {
+                                ASTNode::Field(_, field_type, _, _) => Some(ColumnType::of(field_type)),
+                                _ => None,
+                            })
+                            .collect();
+
+                        self.variant_fields.insert(variant_name.clone(), field_types);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+impl<'a, 's> Interpreter<'a, 's> {
+    fn new(declarations: &'s Declarations<'a>, world: &'s mut dyn Storage, context: String) -> Interpreter<'a, 's> {
+        Interpreter {
+            declarations,
+            world,
+            scopes: Vec::new(),
+            context,
+        }
+    }
+
+    fn execute_block(&mut self, instructions: &'a [Box<ASTNode>], bindings: HashMap<String, Value>) -> Result<(), Diagnostic> {
+        self.scopes.push(bindings);
+
+        let result = instructions.iter().try_for_each(|instruction| self.execute(instruction));
+
+        self.scopes.pop();
+        result
+    }
+
+    fn execute(&mut self, instruction: &'a ASTNode) -> Result<(), Diagnostic> {
+        match instruction {
+            ASTNode::Declaration(variable_name, value) => {
+                let value = self.evaluate(value)?;
+                self.scopes.last_mut().unwrap().insert(variable_name.clone(), value);
+            },
+            ASTNode::Assignment(target, value) => {
+                let value = self.evaluate(value)?;
+                self.assign(target, value)?;
+            },
+            ASTNode::If(condition, instructions) => {
+                if self.evaluate_condition(condition, "if")? {
+                    self.execute_block(instructions, HashMap::new())?;
+                }
+            },
+            ASTNode::Foreach(values, collections, instructions, parallel) => {
+                let data_names: Vec<&str> = collections
+                    .iter()
+                    .filter_map(|collection| match collection.as_ref() {
+                        ASTNode::Value(name) if self.declarations.data_defaults.contains_key(name) => Some(name.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+
+                let iterations = match data_names.len() == collections.len() {
+                    true  => self.join_iterations(values, &data_names),
+                    false => self.zip_iterations(values, collections)?,
+                };
+
+                match parallel {
+                    true  => self.execute_in_parallel(iterations, instructions)?,
+                    false => {
+                        for bindings in iterations {
+                            self.execute_block(instructions, bindings)?;
+                        }
+                    },
+                }
+            },
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                self.scopes.push(HashMap::new());
+                let result = self.execute_for(declaration, condition, progression, instructions);
+                self.scopes.pop();
+                result?;
+            },
+            ASTNode::Match(value, arms) => {
+                let value = self.evaluate(value)?;
+
+                for arm in arms {
+                    if let ASTNode::MatchArm(pattern, instructions) = arm.as_ref() {
+                        let mut bindings = HashMap::new();
+
+                        if self.matches(pattern, &value, &mut bindings) {
+                            return self.execute_block(instructions, bindings);
+                        }
+                    }
+                }
+
+                return Err(self.error(format!("No match arm matches {}.", value)));
+            },
+            ASTNode::CreateInstruction(group_name, parameter_values) => self.create(group_name, parameter_values)?,
+            node => {
+                self.evaluate(node)?;
+            },
+        }
+
+        Ok(())
+    }
+
+    fn execute_for(
+        &mut self,
+        declaration: &'a ASTNode,
+        condition: &'a ASTNode,
+        progression: &'a ASTNode,
+        instructions: &'a [Box<ASTNode>]
+    ) -> Result<(), Diagnostic> {
+        self.execute(declaration)?;
+
+        while self.evaluate_condition(condition, "for")? {
+            self.execute_block(instructions, HashMap::new())?;
+            self.execute(progression)?;
+        }
+
+        Ok(())
+    }
+
+    /// Binds each name to its instance, once per entity that has an instance of every data.
+    fn join_iterations(&self, values: &[String], data_names: &[&str]) -> Vec<HashMap<String, Value>> {
+        self.world
+            .join(data_names)
+            .into_iter()
+            .map(|rows| {
+                values
+                    .iter()
+                    .zip(data_names)
+                    .zip(rows)
+                    .map(|((value, data_name), row)| (value.clone(), Value::Instance(data_name.to_string(), row)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Binds each name to the element of its list, once per position in lists of the same length.
+    fn zip_iterations(&mut self, values: &[String], collections: &'a [Box<ASTNode>]) -> Result<Vec<HashMap<String, Value>>, Diagnostic> {
+        let mut lists = Vec::new();
+
+        for collection in collections {
+            match self.evaluate(collection)? {
+                Value::List(elements) => lists.push(elements),
+                other => return Err(self.error(format!("Cannot iterate over {} in foreach.", other))),
+            }
+        }
+
+        let length = lists.first().map_or(0, Vec::len);
+
+        if let Some(other_list) = lists.iter().find(|list| list.len() != length) {
+            return Err(self.error(format!("Foreach zips lists of different lengths, {} and {}.", length, other_list.len())));
+        }
+
+        Ok((0..length)
+            .map(|index| {
+                values
+                    .iter()
+                    .zip(&lists)
+                    .map(|(value, list)| (value.clone(), list[index].clone()))
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn execute_in_parallel(&mut self, iterations: Vec<HashMap<String, Value>>, instructions: &'a [Box<ASTNode>]) -> Result<(), Diagnostic> {
+        let declarations = self.declarations;
+        let scopes = &self.scopes;
+        let context = &self.context;
+
+        execute_in_chunks(self.world, &iterations, &|storage, chunk| {
+            let mut interpreter = Interpreter::new(declarations, storage, context.clone());
+            interpreter.scopes = scopes.clone();
+
+            chunk.iter().try_for_each(|bindings| interpreter.execute_block(instructions, bindings.clone()))
+        })
+    }
+
+    fn create(&mut self, group_name: &str, parameter_values: &'a [Box<ASTNode>]) -> Result<(), Diagnostic> {
+        let group = &self.declarations.groups[group_name];
+        let mut parameters = HashMap::new();
+
+        for (index, parameter_value) in parameter_values.iter().enumerate() {
+            let (parameter_name, value) = match parameter_value.as_ref() {
+                ASTNode::NamedArgument(parameter_name, value) => (parameter_name, value.as_ref()),
+                value => (&group.parameters[index].0, value),
+            };
+
+            let column_type = group.parameters.iter().find(|(name, _)| name == parameter_name).unwrap().1;
+            let value = self.evaluate(value)?;
+            parameters.insert(parameter_name.clone(), column_type.convert(value));
+        }
+
+        let entity = self.world.new_entity();
+
+        // Field values of a group only see its parameters, and defaults see nothing at all.
+        let enclosing_scopes = std::mem::replace(&mut self.scopes, vec![parameters]);
+        let result = self.instanciate_all(entity, group.data_instanciations);
+        self.scopes = enclosing_scopes;
+
+        result
+    }
+
+    fn instanciate_all(&mut self, entity: usize, data_instanciations: &'a [Box<ASTNode>]) -> Result<(), Diagnostic> {
+        for data_instanciation in data_instanciations {
+            if let ASTNode::DataInstanciation(data_name, field_values) = data_instanciation.as_ref() {
+                let mut row = Vec::new();
+
+                for (field_index, field_name) in self.world.table(data_name).field_names.clone().iter().enumerate() {
+                    let field_value = field_values.iter().find_map(|field_value| match field_value.as_ref() {
+                        ASTNode::FieldValue(name, value) if name == field_name => Some(value),
+                        _ => None,
+                    });
+
+                    let value = match (field_value, self.declarations.data_defaults[data_name][field_index]) {
+                        (Some(value), _) => self.evaluate_value(value)?,
+                        (None, Some(default_value)) => self.evaluate(default_value)?,
+                        (None, None) => Value::None,
+                    };
+
+                    row.push(value);
+                }
+
+                self.world.table_mut(data_name).push_row(entity, row);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn assign(&mut self, target: &str, value: Value) -> Result<(), Diagnostic> {
+        match target.split_once('.') {
+            Some((variable_name, field_name)) => match self.lookup(variable_name)? {
+                Value::Instance(data_name, row) => {
+                    let field_index = self.field_index(&data_name, field_name)?;
+                    self.world.set_field(&data_name, row, field_index, value);
+                    Ok(())
+                },
+                other => Err(self.error(format!("Cannot assign field {} of {}.", field_name, other))),
+            },
+            None => match self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(target)) {
+                Some(variable) => {
+                    *variable = value;
+                    Ok(())
+                },
+                None => Err(self.error(format!("Unknown variable {}.", target))),
+            },
+        }
+    }
+
+    fn evaluate_condition(&mut self, condition: &'a ASTNode, description: &str) -> Result<bool, Diagnostic> {
+        match self.evaluate(condition)? {
+            Value::Bool(value) => Ok(value),
+            other => Err(self.error(format!("The {} condition evaluated to {} instead of a bool.", description, other))),
+        }
+    }
+
+    fn evaluate(&mut self, node: &'a ASTNode) -> Result<Value, Diagnostic> {
+        match node {
+            ASTNode::Value(value) => self.evaluate_value(value),
+            ASTNode::Sum(lhs, rhs) => self.evaluate_arithmetic("+", lhs, rhs),
+            ASTNode::Substraction(lhs, rhs) => self.evaluate_arithmetic("-", lhs, rhs),
+            ASTNode::Multiplication(lhs, rhs) => self.evaluate_arithmetic("*", lhs, rhs),
+            ASTNode::Division(lhs, rhs, _) => self.evaluate_arithmetic("/", lhs, rhs),
+            ASTNode::Modulo(lhs, rhs, _) => self.evaluate_arithmetic("%", lhs, rhs),
+            ASTNode::Equality(lhs, rhs) => Ok(Value::Bool(self.evaluate_equality(lhs, rhs)?)),
+            ASTNode::Inequality(lhs, rhs) => Ok(Value::Bool(!self.evaluate_equality(lhs, rhs)?)),
+            ASTNode::GreaterThan(lhs, rhs) => self.evaluate_comparison(">", lhs, rhs),
+            ASTNode::LessThan(lhs, rhs) => self.evaluate_comparison("<", lhs, rhs),
+            ASTNode::GreaterOrEqual(lhs, rhs) => self.evaluate_comparison(">=", lhs, rhs),
+            ASTNode::LessOrEqual(lhs, rhs) => self.evaluate_comparison("<=", lhs, rhs),
+            ASTNode::ListLiteral(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.evaluate(element))
+                    .collect::<Result<Vec<Value>, Diagnostic>>()?;
+
+                Ok(new_list(elements))
+            },
+            ASTNode::VariantConstructor(variant_name, field_values) => {
+                let declarations = self.declarations;
+                let fields = field_values
+                    .iter()
+                    .zip(&declarations.variant_fields[variant_name])
+                    .map(|(field_value, field_type)| Ok(field_type.convert(self.evaluate(field_value)?)))
+                    .collect::<Result<Vec<Value>, Diagnostic>>()?;
+
+                Ok(Value::Variant(variant_name.clone(), fields))
+            },
+            other => Err(self.error(format!("Cannot evaluate {:?}.", other))),
+        }
+    }
+
+    /// Evaluates a literal, a variable, a field of the data a variable refers to, or an enum variant.
+    fn evaluate_value(&self, value: &str) -> Result<Value, Diagnostic> {
+        if is_literal(value) {
+            return Ok(parse_literal(value));
+        }
+
+        match value.split_once('.') {
+            Some((variable_name, field_name)) => match self.lookup(variable_name)? {
+                Value::Instance(data_name, row) => {
+                    let field_index = self.field_index(&data_name, field_name)?;
+                    Ok(self.world.get_field(&data_name, row, field_index))
+                },
+                other => Err(self.error(format!("Cannot read field {} of {}.", field_name, other))),
+            },
+            None if self.declarations.variant_fields.contains_key(value) => {
+                Ok(Value::Variant(value.to_string(), Vec::new()))
+            },
+            None => self.lookup(value),
+        }
+    }
+
+    fn evaluate_arithmetic(&mut self, operator: &str, lhs: &'a ASTNode, rhs: &'a ASTNode) -> Result<Value, Diagnostic> {
+        let lhs = self.evaluate(lhs)?;
+        let rhs = self.evaluate(rhs)?;
+
+        apply_arithmetic(operator, &lhs, &rhs).map_err(|message| self.error(message))
+    }
+
+    fn evaluate_equality(&mut self, lhs: &'a ASTNode, rhs: &'a ASTNode) -> Result<bool, Diagnostic> {
+        let lhs = self.evaluate(lhs)?;
+        let rhs = self.evaluate(rhs)?;
+
+        Ok(values_equal(&lhs, &rhs))
+    }
+
+    fn evaluate_comparison(&mut self, operator: &str, lhs: &'a ASTNode, rhs: &'a ASTNode) -> Result<Value, Diagnostic> {
+        let lhs = self.evaluate(lhs)?;
+        let rhs = self.evaluate(rhs)?;
+
+        compare(operator, &lhs, &rhs).map(Value::Bool).map_err(|message| self.error(message))
+    }
+
+    fn matches(&self, pattern: &ASTNode, value: &Value, bindings: &mut HashMap<String, Value>) -> bool {
+        match (pattern, value) {
+            (ASTNode::WildcardPattern, _) => true,
+            (ASTNode::BindingPattern(name), value) => {
+                bindings.insert(name.clone(), value.clone());
+                true
+            },
+            (ASTNode::LiteralPattern(literal), value) => &parse_literal(literal) == value,
+            (ASTNode::VariantPattern(name, field_patterns), Value::Variant(variant_name, fields)) => {
+                name == variant_name && field_patterns
+                    .iter()
+                    .zip(fields)
+                    .all(|(field_pattern, field)| self.matches(field_pattern, field, bindings))
+            },
+            (ASTNode::VariantPattern(name, _), Value::Instance(data_name, _)) => name == data_name,
+            (ASTNode::DataPattern(name, field_patterns), Value::Instance(data_name, row)) => {
+                name == data_name && field_patterns.iter().all(|field_pattern| match field_pattern.as_ref() {
+                    ASTNode::FieldPattern(field_name, sub_pattern) => {
+                        let field_index = self.world.table(data_name).field_index(field_name).unwrap();
+                        let field = self.world.get_field(data_name, *row, field_index);
+                        self.matches(sub_pattern, &field, bindings)
+                    },
+                    _ => false,
+                })
+            },
+            _ => false,
+        }
+    }
+
+    fn lookup(&self, variable_name: &str) -> Result<Value, Diagnostic> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(variable_name)) {
+            Some(value) => Ok(value.clone()),
+            None => Err(self.error(format!("Unknown variable {}.", variable_name))),
+        }
+    }
+
+    fn field_index(&self, data_name: &str, field_name: &str) -> Result<usize, Diagnostic> {
+        match self.world.table(data_name).field_index(field_name) {
+            Some(field_index) => Ok(field_index),
+            None => Err(self.error(format!("Data {} has no field named {}.", data_name, field_name))),
+        }
+    }
+
+    fn error(&self, message: String) -> Diagnostic {
+        Diagnostic::error(format!("In {}: {}", self.context, message))
+    }
+}
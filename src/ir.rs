@@ -0,0 +1,415 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// The type of a register or of a constant. An instance is the row of an instance in the table of its data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrType {
+    Int,
+    Float,
+    Bool,
+    String,
+    Enum(String),
+    Instance(String),
+    Entity,
+}
+
+/// A register is written by instructions and read through operands. Unlike SSA values, a register may be written
+/// several times, as the variable it holds is assigned.
+pub type Register = usize;
+
+pub type BlockId = usize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Register(Register),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// A variant of the named enum, by its position.
+    Variant(String, usize),
+}
+
+/// Both operands have the same type. Arithmetic on integers fails on overflow, and dividing by zero fails for both
+/// integers and floats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Concat,
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+    GreaterOrEqual,
+    LessOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Copy(Register, Operand),
+    IntToFloat(Register, Operand),
+    Binary(Register, BinaryOperator, Operand, Operand),
+    /// Reads a field of the instance at a row of the named data. An unset optional field reads as the default value
+    /// of its type.
+    LoadField(Register, String, String, Operand),
+    /// Writes a field of the instance at a row of the named data, which sets an optional field.
+    StoreField(String, String, Operand, Operand),
+    NewEntity(Register),
+    /// Adds an instance of the named data for an entity, with a value per field, none leaving an optional field unset.
+    PushRow(String, Operand, Vec<Option<Operand>>),
+    /// Calls the function of the named group with a value per parameter.
+    Create(String, Vec<Operand>),
+    /// Calls the function of the named do action.
+    Do(String),
+    /// Starts the join of that number over the tables of the named data. Instances added later are not visited.
+    StartJoin(usize, Vec<String>),
+    /// Moves the join past the rows it gave last.
+    AdvanceJoin(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(BlockId),
+    /// Goes to the first block when the bool is true, and to the second otherwise.
+    Branch(Operand, BlockId, BlockId),
+    /// Moves the join to the next entity with an instance in each of its tables and goes to the first block with
+    /// their rows in the registers, or goes to the second block when there is none.
+    NextJoin(usize, Vec<Register>, BlockId, BlockId),
+    /// Stops with an error as no match arm matches the value.
+    NoMatch(Operand),
+    Return,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub instructions: Vec<Instruction>,
+    pub terminator: Terminator,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FunctionKind {
+    /// Creates an entity with an instance of each data of the group, taking the parameters of the group.
+    Group(String),
+    Do(String),
+    /// A run block, which ends by calling the actions it lists.
+    Run(Vec<String>),
+}
+
+/// The parameters are the first registers. The first block is the entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrFunction {
+    pub kind: FunctionKind,
+    /// Where errors happen, as run reports it, such as do Move.
+    pub context: String,
+    pub parameter_count: usize,
+    /// The name of the variable each register holds, or the empty string, and its type.
+    pub registers: Vec<(String, IrType)>,
+    pub join_count: usize,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrField {
+    pub name: String,
+    pub field_type: IrType,
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrTable {
+    pub data_name: String,
+    pub fields: Vec<IrField>,
+}
+
+/// A whole program once lowered: its enums, a table per data, then the functions of its groups, its do actions and
+/// its run blocks, each in the order they are declared. Running it runs the run blocks in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrProgram {
+    pub enums: Vec<(String, Vec<String>)>,
+    pub tables: Vec<IrTable>,
+    pub functions: Vec<IrFunction>,
+    /// The data, groups and do actions as they are declared together, such as data Position then do Move.
+    pub declarations: Vec<String>,
+}
+
+impl IrProgram {
+    pub fn table(&self, data_name: &str) -> Option<&IrTable> {
+        self.tables.iter().find(|table| table.data_name == data_name)
+    }
+
+    pub fn variants(&self, enum_name: &str) -> &[String] {
+        self.enums.iter().find(|(name, _)| name == enum_name).map_or(&[], |(_, variants)| variants)
+    }
+
+    pub fn group(&self, group_name: &str) -> Option<&IrFunction> {
+        self.functions.iter().find(|function| matches!(&function.kind, FunctionKind::Group(name) if name == group_name))
+    }
+
+    pub fn action(&self, action_name: &str) -> Option<&IrFunction> {
+        self.functions.iter().find(|function| matches!(&function.kind, FunctionKind::Do(name) if name == action_name))
+    }
+}
+
+impl IrTable {
+    pub fn field(&self, field_name: &str) -> Option<&IrField> {
+        self.fields.iter().find(|field| field.name == field_name)
+    }
+}
+
+impl IrFunction {
+    pub fn operand_type(&self, operand: &Operand) -> IrType {
+        match operand {
+            Operand::Register(register) => self.registers[*register].1.clone(),
+            Operand::Int(_) => IrType::Int,
+            Operand::Float(_) => IrType::Float,
+            Operand::Bool(_) => IrType::Bool,
+            Operand::String(_) => IrType::String,
+            Operand::Variant(enum_name, _) => IrType::Enum(enum_name.clone()),
+        }
+    }
+
+    /// The data each join goes over, from the instruction starting it.
+    pub fn joins(&self) -> HashMap<usize, Vec<String>> {
+        self.blocks
+            .iter()
+            .flat_map(|block| &block.instructions)
+            .filter_map(|instruction| match instruction {
+                Instruction::StartJoin(join, data_names) => Some((*join, data_names.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The blocks some path from the entry reaches, in order.
+    pub fn reachable_blocks(&self) -> Vec<BlockId> {
+        let mut reached = vec![false; self.blocks.len()];
+        let mut worklist = vec![0];
+
+        while let Some(block) = worklist.pop() {
+            if !reached[block] {
+                reached[block] = true;
+                worklist.extend(self.successors(block));
+            }
+        }
+
+        (0..self.blocks.len()).filter(|block| reached[*block]).collect()
+    }
+
+    /// The blocks a block may go to next.
+    pub fn successors(&self, block: BlockId) -> Vec<BlockId> {
+        match &self.blocks[block].terminator {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch(_, then_block, else_block) | Terminator::NextJoin(_, _, then_block, else_block) => {
+                vec![*then_block, *else_block]
+            },
+            Terminator::NoMatch(_) | Terminator::Return => Vec::new(),
+        }
+    }
+
+    /// The registers that some instruction or terminator of a reachable block reads.
+    pub fn read_registers(&self) -> HashSet<Register> {
+        self.reachable_blocks()
+            .into_iter()
+            .flat_map(|block| {
+                let block = &self.blocks[block];
+                block.instructions.iter().flat_map(|instruction| instruction.uses().0).chain(block.terminator.reads())
+            })
+            .collect()
+    }
+}
+
+impl Operand {
+    pub fn register(&self) -> Option<Register> {
+        match self {
+            Operand::Register(register) => Some(*register),
+            _ => None,
+        }
+    }
+}
+
+impl Instruction {
+    /// The registers the instruction reads, those it writes, and the join it uses.
+    pub fn uses(&self) -> (Vec<Register>, Vec<Register>, Option<usize>) {
+        let registers = |operands: &[&Operand]| operands.iter().filter_map(|operand| operand.register()).collect();
+
+        match self {
+            Instruction::Copy(target, value) | Instruction::IntToFloat(target, value) => (registers(&[value]), vec![*target], None),
+            Instruction::Binary(target, _, lhs, rhs) => (registers(&[lhs, rhs]), vec![*target], None),
+            Instruction::LoadField(target, _, _, row) => (registers(&[row]), vec![*target], None),
+            Instruction::StoreField(_, _, row, value) => (registers(&[row, value]), Vec::new(), None),
+            Instruction::NewEntity(target) => (Vec::new(), vec![*target], None),
+            Instruction::PushRow(_, entity, values) => {
+                let mut operands = vec![entity];
+                operands.extend(values.iter().flatten());
+                (registers(&operands), Vec::new(), None)
+            },
+            Instruction::Create(_, arguments) => (registers(&arguments.iter().collect::<Vec<_>>()), Vec::new(), None),
+            Instruction::Do(_) => (Vec::new(), Vec::new(), None),
+            Instruction::StartJoin(join, _) | Instruction::AdvanceJoin(join) => (Vec::new(), Vec::new(), Some(*join)),
+        }
+    }
+}
+
+impl Terminator {
+    /// The registers the terminator reads. The rows a join gives are written, not read.
+    pub fn reads(&self) -> Vec<Register> {
+        match self {
+            Terminator::Branch(operand, _, _) | Terminator::NoMatch(operand) => operand.register().into_iter().collect(),
+            Terminator::Jump(_) | Terminator::NextJoin(..) | Terminator::Return => Vec::new(),
+        }
+    }
+}
+
+impl BinaryOperator {
+    pub fn is_comparison(&self) -> bool {
+        !matches!(
+            self,
+            BinaryOperator::Add
+                | BinaryOperator::Subtract
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Modulo
+                | BinaryOperator::Concat
+        )
+    }
+
+    /// The do-lang operator, as errors show it.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Add | BinaryOperator::Concat => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::Less => "<",
+            BinaryOperator::GreaterOrEqual => ">=",
+            BinaryOperator::LessOrEqual => "<=",
+        }
+    }
+}
+
+impl fmt::Display for IrType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IrType::Int => write!(f, "int"),
+            IrType::Float => write!(f, "float"),
+            IrType::Bool => write!(f, "bool"),
+            IrType::String => write!(f, "string"),
+            IrType::Enum(name) | IrType::Instance(name) => write!(f, "{}", name),
+            IrType::Entity => write!(f, "entity"),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Register(register) => write!(f, "%{}", register),
+            Operand::Int(value) => write!(f, "{}", value),
+            Operand::Float(value) => write!(f, "{:?}", value),
+            Operand::Bool(value) => write!(f, "{}", value),
+            Operand::String(value) => write!(f, "{:?}", value),
+            Operand::Variant(enum_name, index) => write!(f, "{}#{}", enum_name, index),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::Copy(target, value) => write!(f, "%{} = {}", target, value),
+            Instruction::IntToFloat(target, value) => write!(f, "%{} = int_to_float {}", target, value),
+            Instruction::Binary(target, operator, lhs, rhs) => {
+                write!(f, "%{} = {} {}, {}", target, format!("{:?}", operator).to_lowercase(), lhs, rhs)
+            },
+            Instruction::LoadField(target, data_name, field_name, row) => {
+                write!(f, "%{} = load {}.{} {}", target, data_name, field_name, row)
+            },
+            Instruction::StoreField(data_name, field_name, row, value) => {
+                write!(f, "store {}.{} {}, {}", data_name, field_name, row, value)
+            },
+            Instruction::NewEntity(target) => write!(f, "%{} = new_entity", target),
+            Instruction::PushRow(data_name, entity, values) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|value| value.as_ref().map_or("unset".to_string(), |value| value.to_string()))
+                    .collect();
+
+                write!(f, "push {} {} ({})", data_name, entity, values.join(", "))
+            },
+            Instruction::Create(group_name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+                write!(f, "create {}({})", group_name, arguments.join(", "))
+            },
+            Instruction::Do(action_name) => write!(f, "do {}", action_name),
+            Instruction::StartJoin(join, data_names) => write!(f, "start_join #{} ({})", join, data_names.join(", ")),
+            Instruction::AdvanceJoin(join) => write!(f, "advance_join #{}", join),
+        }
+    }
+}
+
+impl fmt::Display for Terminator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Terminator::Jump(target) => write!(f, "jump block{}", target),
+            Terminator::Branch(condition, then_block, else_block) => {
+                write!(f, "branch {}, block{}, block{}", condition, then_block, else_block)
+            },
+            Terminator::NextJoin(join, rows, body, exit) => {
+                let rows: Vec<String> = rows.iter().map(|row| format!("%{}", row)).collect();
+                write!(f, "next_join #{} ({}), block{}, block{}", join, rows.join(", "), body, exit)
+            },
+            Terminator::NoMatch(value) => write!(f, "no_match {}", value),
+            Terminator::Return => write!(f, "return"),
+        }
+    }
+}
+
+impl fmt::Display for IrProgram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (enum_name, variants) in &self.enums {
+            writeln!(f, "enum {} ({})", enum_name, variants.join(", "))?;
+        }
+
+        for table in &self.tables {
+            let fields: Vec<String> = table
+                .fields
+                .iter()
+                .map(|field| format!("{}: {}{}", field.name, field.field_type, if field.optional { "?" } else { "" }))
+                .collect();
+
+            writeln!(f, "table {} ({})", table.data_name, fields.join(", "))?;
+        }
+
+        for function in &self.functions {
+            let parameters: Vec<String> = (0..function.parameter_count).map(|register| format!("%{}", register)).collect();
+            writeln!(f, "\nfunction {} ({})", function.context, parameters.join(", "))?;
+
+            for (register, (name, register_type)) in function.registers.iter().enumerate() {
+                match name.is_empty() {
+                    true  => writeln!(f, "  %{}: {}", register, register_type)?,
+                    false => writeln!(f, "  %{}: {} ({})", register, register_type, name)?,
+                }
+            }
+
+            for (index, block) in function.blocks.iter().enumerate() {
+                writeln!(f, "block{}:", index)?;
+
+                for instruction in &block.instructions {
+                    writeln!(f, "  {}", instruction)?;
+                }
+
+                writeln!(f, "  {}", block.terminator)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,675 @@
+use std::collections::HashMap;
+
+use crate::ast::{is_literal, ASTNode};
+use crate::diagnostic::Diagnostic;
+use crate::ir::{
+    BinaryOperator, Block, BlockId, FunctionKind, Instruction, IrField, IrFunction, IrProgram, IrTable, IrType, Operand,
+    Register, Terminator,
+};
+use crate::runtime::{parse_literal, Value};
+use crate::type_checker::{Type, TypeChecker};
+
+struct IrBuilder<'a> {
+    types: TypeChecker,
+    enums: Vec<(String, Vec<String>)>,
+    tables: Vec<IrTable>,
+    /// The default value of each field of each data, in the order of the fields.
+    defaults: HashMap<String, Vec<Option<&'a ASTNode>>>,
+    function: IrFunction,
+    /// The block instructions are added to.
+    current: BlockId,
+    /// The register each variable in scope is held in.
+    scopes: Vec<HashMap<String, Register>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Lowers a program that passed every check. Lists, maps and variants with fields cannot be lowered yet, and are
+/// reported.
+pub fn lower_program(program: &ASTNode) -> (IrProgram, Vec<Diagnostic>) {
+    let statements = match program {
+        ASTNode::Program(statements) => statements,
+        _ => unreachable!(),
+    };
+
+    let mut builder = IrBuilder {
+        types: TypeChecker::new(program),
+        enums: Vec::new(),
+        tables: Vec::new(),
+        defaults: HashMap::new(),
+        function: new_function(FunctionKind::Do(String::new()), String::new()),
+        current: 0,
+        scopes: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    for statement in statements {
+        match statement.as_ref() {
+            ASTNode::Enum(name, variants, _) => builder.lower_enum(name, variants),
+            ASTNode::Data(name, fields, _) => builder.lower_table(name, fields),
+            _ => (),
+        }
+    }
+
+    let mut functions = Vec::new();
+
+    for statement in statements {
+        if let ASTNode::Group(name, _, data_instanciations, _) = statement.as_ref() {
+            functions.push(builder.lower_group(name, data_instanciations));
+        }
+    }
+
+    for statement in statements {
+        let (kind, context, instructions) = match statement.as_ref() {
+            ASTNode::Do(name, instructions, _) => (FunctionKind::Do(name.clone()), format!("do {}", name), instructions),
+            ASTNode::Run(actions_to_do, instructions) => {
+                (FunctionKind::Run(actions_to_do.clone()), format!("run ({})", actions_to_do.join(", ")), instructions)
+            },
+            _ => continue,
+        };
+
+        builder.start_function(kind, context, Vec::new());
+        builder.lower_block(instructions);
+
+        if let FunctionKind::Run(actions_to_do) = &builder.function.kind {
+            for action_to_do in actions_to_do.clone() {
+                builder.emit(Instruction::Do(action_to_do));
+            }
+        }
+
+        functions.push(builder.finish_function());
+    }
+
+    let declarations = statements
+        .iter()
+        .filter_map(|statement| match statement.as_ref() {
+            ASTNode::Data(name, _, _) => Some(format!("data {}", name)),
+            ASTNode::Group(name, _, _, _) => Some(format!("group {}", name)),
+            ASTNode::Do(name, _, _) => Some(format!("do {}", name)),
+            _ => None,
+        })
+        .collect();
+
+    let program = IrProgram {
+        enums: builder.enums,
+        tables: builder.tables,
+        functions,
+        declarations,
+    };
+
+    (program, builder.diagnostics)
+}
+
+fn new_function(kind: FunctionKind, context: String) -> IrFunction {
+    IrFunction {
+        kind,
+        context,
+        parameter_count: 0,
+        registers: Vec::new(),
+        join_count: 0,
+        blocks: vec![Block { instructions: Vec::new(), terminator: Terminator::Return }],
+    }
+}
+
+impl<'a> IrBuilder<'a> {
+    fn lower_enum(&mut self, name: &str, variants: &[Box<ASTNode>]) {
+        let mut variant_names = Vec::new();
+
+        for variant in variants {
+            if let ASTNode::Variant(variant_name, fields, _) = variant.as_ref() {
+                if !fields.is_empty() {
+                    self.function.context = format!("enum {}", name);
+                    self.unsupported("variants with fields");
+                }
+
+                variant_names.push(variant_name.clone());
+            }
+        }
+
+        self.enums.push((name.to_string(), variant_names));
+    }
+
+    fn lower_table(&mut self, name: &str, fields: &'a [Box<ASTNode>]) {
+        self.function.context = format!("data {}", name);
+
+        let mut ir_fields = Vec::new();
+        let mut defaults = Vec::new();
+
+        for field in fields {
+            if let ASTNode::Field(field_name, _, default_value, _) = field.as_ref() {
+                let field_type = self.types.field_type(name, field_name).unwrap_or(Type::Unknown);
+
+                ir_fields.push(IrField {
+                    name: field_name.clone(),
+                    field_type: self.ir_type(&field_type),
+                    optional: matches!(field_type, Type::Optional(_)),
+                });
+                defaults.push(default_value.as_deref());
+            }
+        }
+
+        self.tables.push(IrTable { data_name: name.to_string(), fields: ir_fields });
+        self.defaults.insert(name.to_string(), defaults);
+    }
+
+    /// A field gets the value the group gives it, or else its default value, or else is left unset.
+    fn lower_group(&mut self, name: &str, data_instanciations: &[Box<ASTNode>]) -> IrFunction {
+        let parameters = self.types.group_parameters.get(name).cloned().unwrap_or_default();
+        self.start_function(FunctionKind::Group(name.to_string()), format!("group {}", name), parameters.clone());
+        self.types.enter_scope(parameters.into_iter().collect());
+
+        let entity = self.register("entity", IrType::Entity);
+        self.emit(Instruction::NewEntity(entity));
+
+        for data_instanciation in data_instanciations {
+            if let ASTNode::DataInstanciation(data_name, field_values) = data_instanciation.as_ref() {
+                let table = match self.tables.iter().find(|table| table.data_name == *data_name) {
+                    Some(table) => table.clone(),
+                    None => continue,
+                };
+
+                let defaults = self.defaults[data_name].clone();
+                let mut values = Vec::new();
+
+                for (field, default_value) in table.fields.iter().zip(defaults) {
+                    let field_value = field_values.iter().find_map(|field_value| match field_value.as_ref() {
+                        ASTNode::FieldValue(name, value) if *name == field.name => Some(ASTNode::new_value(value.clone())),
+                        _ => None,
+                    });
+
+                    let value = match (&field_value, default_value) {
+                        (Some(value), _) | (None, Some(value)) => {
+                            let value = self.expression(value);
+                            Some(self.convert(value, &field.field_type))
+                        },
+                        (None, None) => None,
+                    };
+
+                    values.push(value);
+                }
+
+                self.emit(Instruction::PushRow(data_name.clone(), Operand::Register(entity), values));
+            }
+        }
+
+        self.types.exit_scope();
+        self.finish_function()
+    }
+
+    fn lower_block(&mut self, instructions: &[Box<ASTNode>]) {
+        self.lower_scoped_block(instructions, HashMap::new());
+    }
+
+    /// Opens a scope with the given variables already held in registers.
+    fn lower_scoped_block(&mut self, instructions: &[Box<ASTNode>], variables: HashMap<String, Register>) {
+        let variable_types = variables
+            .iter()
+            .map(|(name, register)| (name.clone(), self.source_type(&self.function.registers[*register].1)))
+            .collect();
+
+        self.types.enter_scope(variable_types);
+        self.scopes.push(variables);
+
+        for instruction in instructions {
+            self.lower_instruction(instruction);
+        }
+
+        self.scopes.pop();
+        self.types.exit_scope();
+    }
+
+    fn lower_instruction(&mut self, instruction: &ASTNode) {
+        match instruction {
+            ASTNode::Declaration(variable_name, value) => {
+                let value_type = self.types.infer_type(value);
+                let value = self.expression(value);
+                let register_type = self.function.operand_type(&value);
+                let register = self.register(variable_name, register_type);
+                self.emit(Instruction::Copy(register, value));
+
+                self.types.declare(variable_name, value_type);
+                self.scopes.last_mut().unwrap().insert(variable_name.clone(), register);
+            },
+            ASTNode::Assignment(target, value) => match target.split_once('.') {
+                Some((variable_name, field_name)) => {
+                    let data_name = match self.types.infer_value(variable_name) {
+                        Type::Data(data_name) => data_name,
+                        _ => return self.unsupported("assigning fields of values that are not data"),
+                    };
+
+                    let field_type = self.field(&data_name, field_name).field_type;
+                    let row = self.variable(variable_name);
+                    let value = self.expression(value);
+                    let value = self.convert(value, &field_type);
+                    self.emit(Instruction::StoreField(data_name, field_name.to_string(), Operand::Register(row), value));
+                },
+                None => {
+                    let register = self.variable(target);
+                    let value = self.expression(value);
+
+                    match (self.function.operand_type(&value), &self.function.registers[register].1) {
+                        (IrType::Int, IrType::Float) => self.emit(Instruction::IntToFloat(register, value)),
+                        _ => self.emit(Instruction::Copy(register, value)),
+                    }
+                },
+            },
+            ASTNode::If(condition, instructions) => {
+                let condition = self.expression(condition);
+                let then_block = self.new_block();
+                let after_block = self.new_block();
+                self.terminate(Terminator::Branch(condition, then_block, after_block));
+
+                self.current = then_block;
+                self.lower_block(instructions);
+                self.terminate(Terminator::Jump(after_block));
+                self.current = after_block;
+            },
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                self.types.enter_scope(HashMap::new());
+                self.scopes.push(HashMap::new());
+                self.lower_instruction(declaration);
+
+                let condition_block = self.new_block();
+                let body_block = self.new_block();
+                let exit_block = self.new_block();
+                self.terminate(Terminator::Jump(condition_block));
+
+                self.current = condition_block;
+                let condition = self.expression(condition);
+                self.terminate(Terminator::Branch(condition, body_block, exit_block));
+
+                self.current = body_block;
+                self.lower_block(instructions);
+                self.lower_instruction(progression);
+                self.terminate(Terminator::Jump(condition_block));
+                self.current = exit_block;
+
+                self.scopes.pop();
+                self.types.exit_scope();
+            },
+            ASTNode::Foreach(values, collections, instructions, _) => self.lower_foreach(values, collections, instructions),
+            ASTNode::Match(value, arms) => self.lower_match(value, arms),
+            ASTNode::CreateInstruction(group_name, parameter_values) => {
+                let parameters = self.types.group_parameters.get(group_name).cloned().unwrap_or_default();
+                let mut arguments = Vec::new();
+
+                for (index, (parameter_name, parameter_type)) in parameters.iter().enumerate() {
+                    let parameter_value = parameter_values
+                        .iter()
+                        .enumerate()
+                        .find_map(|(position, parameter_value)| match parameter_value.as_ref() {
+                            ASTNode::NamedArgument(name, value) if name == parameter_name => Some(value.as_ref()),
+                            ASTNode::NamedArgument(..) => None,
+                            value if position == index => Some(value),
+                            _ => None,
+                        });
+
+                    if let Some(value) = parameter_value {
+                        let parameter_type = self.ir_type(parameter_type);
+                        let value = self.expression(value);
+                        arguments.push(self.convert(value, &parameter_type));
+                    }
+                }
+
+                self.emit(Instruction::Create(group_name.clone(), arguments));
+            },
+            node => {
+                self.expression(node);
+            },
+        }
+    }
+
+    /// Runs the body for each entity that has an instance of every data. A parallel foreach is lowered the same
+    /// way, as its iterations give the same result in any order.
+    fn lower_foreach(&mut self, values: &[String], collections: &[Box<ASTNode>], instructions: &[Box<ASTNode>]) {
+        let binding_types = self.types.check_foreach(values, collections);
+
+        let data_names: Vec<String> = values
+            .iter()
+            .filter_map(|value| match binding_types.get(value) {
+                Some(Type::Data(data_name)) => Some(data_name.clone()),
+                _ => None,
+            })
+            .collect();
+
+        if data_names.len() != collections.len() || data_names.len() != values.len() {
+            return self.unsupported("foreach over lists");
+        }
+
+        let join = self.function.join_count;
+        self.function.join_count += 1;
+        self.emit(Instruction::StartJoin(join, data_names.clone()));
+
+        let rows: Vec<Register> = values
+            .iter()
+            .zip(&data_names)
+            .map(|(value, data_name)| self.register(value, IrType::Instance(data_name.clone())))
+            .collect();
+
+        let next_block = self.new_block();
+        let body_block = self.new_block();
+        let exit_block = self.new_block();
+        self.terminate(Terminator::Jump(next_block));
+
+        self.current = next_block;
+        self.terminate(Terminator::NextJoin(join, rows.clone(), body_block, exit_block));
+
+        self.current = body_block;
+        self.lower_scoped_block(instructions, values.iter().cloned().zip(rows).collect());
+        self.emit(Instruction::AdvanceJoin(join));
+        self.terminate(Terminator::Jump(next_block));
+        self.current = exit_block;
+    }
+
+    /// Tests the arms in order, each pattern going to the next arm as soon as a part of it does not match.
+    fn lower_match(&mut self, value: &ASTNode, arms: &[Box<ASTNode>]) {
+        let matched = self.expression(value);
+        let end_block = self.new_block();
+
+        for arm in arms {
+            if let ASTNode::MatchArm(pattern, instructions) = arm.as_ref() {
+                let next_arm_block = self.new_block();
+                let mut bindings = Vec::new();
+                self.pattern(pattern, matched.clone(), next_arm_block, &mut bindings);
+
+                let mut variables = HashMap::new();
+
+                for (name, value) in bindings {
+                    let register = self.register(&name, self.function.operand_type(&value));
+                    self.emit(Instruction::Copy(register, value));
+                    variables.insert(name, register);
+                }
+
+                self.lower_scoped_block(instructions, variables);
+                self.terminate(Terminator::Jump(end_block));
+                self.current = next_arm_block;
+            }
+        }
+
+        self.terminate(Terminator::NoMatch(matched));
+        self.current = end_block;
+    }
+
+    /// Goes to the next arm when a part of the pattern does not match the value, and gives the values to bind.
+    fn pattern(&mut self, pattern: &ASTNode, matched: Operand, next_arm_block: BlockId, bindings: &mut Vec<(String, Operand)>) {
+        let matched_type = self.function.operand_type(&matched);
+
+        match pattern {
+            ASTNode::BindingPattern(name) => bindings.push((name.clone(), matched)),
+            ASTNode::LiteralPattern(literal) => {
+                let literal = self.expression(&ASTNode::new_value(literal.clone()));
+                let is_equal = self.binary(BinaryOperator::Equal, matched, literal);
+                self.branch_or(is_equal, next_arm_block);
+            },
+            ASTNode::VariantPattern(name, field_patterns) => {
+                if !field_patterns.is_empty() {
+                    return self.unsupported("patterns on variant fields");
+                }
+
+                match &matched_type {
+                    IrType::Enum(enum_name) => {
+                        let variant = Operand::Variant(enum_name.clone(), self.variant_index(enum_name, name));
+                        let is_variant = self.binary(BinaryOperator::Equal, matched, variant);
+                        self.branch_or(is_variant, next_arm_block);
+                    },
+                    IrType::Instance(data_name) if data_name == name => (),
+                    _ => self.jump_away(next_arm_block),
+                }
+            },
+            ASTNode::DataPattern(name, field_patterns) => {
+                if matched_type != IrType::Instance(name.clone()) {
+                    return self.jump_away(next_arm_block);
+                }
+
+                for field_pattern in field_patterns {
+                    if let ASTNode::FieldPattern(field_name, sub_pattern) = field_pattern.as_ref() {
+                        let field = self.load_field(name, field_name, matched.clone());
+                        self.pattern(sub_pattern, field, next_arm_block, bindings);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Goes on in a new block when the bool is true, and to the given block otherwise.
+    fn branch_or(&mut self, condition: Operand, else_block: BlockId) {
+        let then_block = self.new_block();
+        self.terminate(Terminator::Branch(condition, then_block, else_block));
+        self.current = then_block;
+    }
+
+    /// Goes to the given block, leaving the code that follows in a block nothing goes to.
+    fn jump_away(&mut self, target: BlockId) {
+        self.terminate(Terminator::Jump(target));
+        self.current = self.new_block();
+    }
+
+    fn expression(&mut self, node: &ASTNode) -> Operand {
+        let (lhs, rhs, operator) = match node {
+            ASTNode::Value(value) => return self.value(value),
+            ASTNode::Sum(lhs, rhs) => (lhs, rhs, BinaryOperator::Add),
+            ASTNode::Substraction(lhs, rhs) => (lhs, rhs, BinaryOperator::Subtract),
+            ASTNode::Multiplication(lhs, rhs) => (lhs, rhs, BinaryOperator::Multiply),
+            ASTNode::Division(lhs, rhs, _) => (lhs, rhs, BinaryOperator::Divide),
+            ASTNode::Modulo(lhs, rhs, _) => (lhs, rhs, BinaryOperator::Modulo),
+            ASTNode::Equality(lhs, rhs) => (lhs, rhs, BinaryOperator::Equal),
+            ASTNode::Inequality(lhs, rhs) => (lhs, rhs, BinaryOperator::NotEqual),
+            ASTNode::GreaterThan(lhs, rhs) => (lhs, rhs, BinaryOperator::Greater),
+            ASTNode::LessThan(lhs, rhs) => (lhs, rhs, BinaryOperator::Less),
+            ASTNode::GreaterOrEqual(lhs, rhs) => (lhs, rhs, BinaryOperator::GreaterOrEqual),
+            ASTNode::LessOrEqual(lhs, rhs) => (lhs, rhs, BinaryOperator::LessOrEqual),
+            ASTNode::VariantConstructor(_, _) => {
+                self.unsupported("variants with fields");
+                return Operand::Int(0);
+            },
+            _ => {
+                self.unsupported("lists");
+                return Operand::Int(0);
+            },
+        };
+
+        let lhs = self.expression(lhs);
+        let rhs = self.expression(rhs);
+        self.binary(operator, lhs, rhs)
+    }
+
+    /// Gives the result of an operator in a new register. Strings are concatenated, and an integer meeting a float
+    /// is widened first.
+    fn binary(&mut self, operator: BinaryOperator, lhs: Operand, rhs: Operand) -> Operand {
+        let (lhs_type, rhs_type) = (self.function.operand_type(&lhs), self.function.operand_type(&rhs));
+
+        let (operator, lhs, rhs) = match (&lhs_type, &rhs_type) {
+            (IrType::String, _) if operator == BinaryOperator::Add => (BinaryOperator::Concat, lhs, rhs),
+            (IrType::Int, IrType::Float) => (operator, self.convert(lhs, &IrType::Float), rhs),
+            (IrType::Float, IrType::Int) => (operator, lhs, self.convert(rhs, &IrType::Float)),
+            _ => (operator, lhs, rhs),
+        };
+
+        let result_type = match operator.is_comparison() {
+            true  => IrType::Bool,
+            false => self.function.operand_type(&lhs),
+        };
+
+        let result = self.register("", result_type);
+        self.emit(Instruction::Binary(result, operator, lhs, rhs));
+        Operand::Register(result)
+    }
+
+    /// Gives a literal, a variant, a variable, or a field of the instance a variable refers to.
+    fn value(&mut self, value: &str) -> Operand {
+        if is_literal(value) {
+            return match parse_literal(value) {
+                Value::Int(int) => Operand::Int(int),
+                Value::Float(float) => Operand::Float(float),
+                Value::Bool(bool) => Operand::Bool(bool),
+                Value::String(string) => Operand::String(string),
+                _ => unreachable!(),
+            };
+        }
+
+        match value.split_once('.') {
+            Some((variable_name, field_name)) => match self.types.infer_value(variable_name) {
+                Type::Data(data_name) => {
+                    let row = self.variable(variable_name);
+                    self.load_field(&data_name, field_name, Operand::Register(row))
+                },
+                _ => {
+                    self.unsupported("fields of values that are not data");
+                    Operand::Int(0)
+                },
+            },
+            None => match self.scopes.iter().rev().find_map(|scope| scope.get(value)) {
+                Some(register) => Operand::Register(*register),
+                None => match self.types.infer_value(value) {
+                    Type::Enum(enum_name) => {
+                        let variant = self.variant_index(&enum_name, value);
+                        Operand::Variant(enum_name, variant)
+                    },
+                    _ => {
+                        self.unsupported("iterating over data outside of foreach");
+                        Operand::Int(0)
+                    },
+                },
+            },
+        }
+    }
+
+    fn load_field(&mut self, data_name: &str, field_name: &str, row: Operand) -> Operand {
+        let field_type = self.field(data_name, field_name).field_type;
+        let register = self.register("", field_type);
+        self.emit(Instruction::LoadField(register, data_name.to_string(), field_name.to_string(), row));
+        Operand::Register(register)
+    }
+
+    /// Widens an integer where a float is expected, at once for a constant.
+    fn convert(&mut self, value: Operand, to: &IrType) -> Operand {
+        match (self.function.operand_type(&value), to, &value) {
+            (IrType::Int, IrType::Float, Operand::Int(int)) => Operand::Float(*int as f64),
+            (IrType::Int, IrType::Float, _) => {
+                let register = self.register("", IrType::Float);
+                self.emit(Instruction::IntToFloat(register, value));
+                Operand::Register(register)
+            },
+            _ => value,
+        }
+    }
+
+    fn ir_type(&mut self, value_type: &Type) -> IrType {
+        match value_type {
+            Type::Int => IrType::Int,
+            Type::Float => IrType::Float,
+            Type::Bool => IrType::Bool,
+            Type::String => IrType::String,
+            Type::Enum(enum_name) => IrType::Enum(enum_name.clone()),
+            Type::Data(data_name) => IrType::Instance(data_name.clone()),
+            Type::Optional(inner_type) => self.ir_type(inner_type),
+            Type::List(_) | Type::Array(..) => {
+                self.unsupported("lists");
+                IrType::Int
+            },
+            Type::Map(..) => {
+                self.unsupported("maps");
+                IrType::Int
+            },
+            _ => {
+                self.unsupported("values whose type is unknown");
+                IrType::Int
+            },
+        }
+    }
+
+    /// The type the type checker knows a register by.
+    fn source_type(&self, register_type: &IrType) -> Type {
+        match register_type {
+            IrType::Int | IrType::Entity => Type::Int,
+            IrType::Float => Type::Float,
+            IrType::Bool => Type::Bool,
+            IrType::String => Type::String,
+            IrType::Enum(enum_name) => Type::Enum(enum_name.clone()),
+            IrType::Instance(data_name) => Type::Data(data_name.clone()),
+        }
+    }
+
+    fn field(&mut self, data_name: &str, field_name: &str) -> IrField {
+        let field = self
+            .tables
+            .iter()
+            .find(|table| table.data_name == data_name)
+            .and_then(|table| table.field(field_name))
+            .cloned();
+
+        match field {
+            Some(field) => field,
+            None => {
+                self.unsupported(&format!("field {} of {}", field_name, data_name));
+                IrField { name: field_name.to_string(), field_type: IrType::Int, optional: false }
+            },
+        }
+    }
+
+    fn variant_index(&self, enum_name: &str, variant_name: &str) -> usize {
+        self.enums
+            .iter()
+            .find(|(name, _)| name == enum_name)
+            .and_then(|(_, variants)| variants.iter().position(|variant| variant == variant_name))
+            .unwrap_or(0)
+    }
+
+    fn variable(&mut self, variable_name: &str) -> Register {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(variable_name)) {
+            Some(register) => *register,
+            None => {
+                self.unsupported(&format!("the name {}", variable_name));
+                self.register(variable_name, IrType::Int)
+            },
+        }
+    }
+
+    /// Starts a function whose parameters are its first registers and in scope.
+    fn start_function(&mut self, kind: FunctionKind, context: String, parameters: Vec<(String, Type)>) {
+        self.function = new_function(kind, context);
+        self.current = 0;
+
+        let mut variables = HashMap::new();
+
+        for (parameter_name, parameter_type) in parameters {
+            let parameter_type = self.ir_type(&parameter_type);
+            variables.insert(parameter_name.clone(), self.register(&parameter_name, parameter_type));
+        }
+
+        self.function.parameter_count = variables.len();
+        self.scopes = vec![variables];
+    }
+
+    fn finish_function(&mut self) -> IrFunction {
+        self.terminate(Terminator::Return);
+        self.scopes.clear();
+        std::mem::replace(&mut self.function, new_function(FunctionKind::Do(String::new()), String::new()))
+    }
+
+    fn register(&mut self, name: &str, register_type: IrType) -> Register {
+        self.function.registers.push((name.to_string(), register_type));
+        self.function.registers.len() - 1
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.function.blocks.push(Block { instructions: Vec::new(), terminator: Terminator::Return });
+        self.function.blocks.len() - 1
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.function.blocks[self.current].instructions.push(instruction);
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        self.function.blocks[self.current].terminator = terminator;
+    }
+
+    fn unsupported(&mut self, construct: &str) {
+        let message = format!("In {}: Build does not support {}.", self.function.context, construct);
+
+        if !self.diagnostics.iter().any(|diagnostic| diagnostic.message == message) {
+            self.diagnostics.push(Diagnostic::error(message));
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+use crate::diagnostic::Diagnostic;
+use crate::ir::{BinaryOperator, BlockId, FunctionKind, Instruction, IrFunction, IrProgram, IrType, Operand, Register, Terminator};
+
+struct FunctionValidator<'a> {
+    program: &'a IrProgram,
+    function: &'a IrFunction,
+    /// The data each join goes over, from the instruction starting it.
+    joins: HashMap<usize, Vec<String>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Checks that a lowered program is well formed: the blocks, registers, tables, groups and actions it names exist,
+/// every instruction gets operands of the types it expects, and no register or join is used before it is set on
+/// some path. A program lowered from a checked syntax tree always passes, so any error is a compiler bug.
+pub fn validate_ir(program: &IrProgram) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for table in &program.tables {
+        for field in &table.fields {
+            if !type_exists(program, &field.field_type) {
+                diagnostics.push(Diagnostic::error(format!(
+                    "In data {}: Field {} has type {}, which does not exist.",
+                    table.data_name, field.name, field.field_type
+                )));
+            }
+        }
+    }
+
+    for function in &program.functions {
+        let mut validator = FunctionValidator { program, function, joins: function.joins(), diagnostics: Vec::new() };
+        validator.validate();
+        diagnostics.append(&mut validator.diagnostics);
+    }
+
+    diagnostics
+}
+
+fn type_exists(program: &IrProgram, ir_type: &IrType) -> bool {
+    match ir_type {
+        IrType::Enum(enum_name) => program.enums.iter().any(|(name, _)| name == enum_name),
+        IrType::Instance(data_name) => program.table(data_name).is_some(),
+        _ => true,
+    }
+}
+
+impl<'a> FunctionValidator<'a> {
+    fn validate(&mut self) {
+        let function = self.function;
+
+        if function.blocks.is_empty() {
+            return self.error("The function has no block.".to_string());
+        }
+
+        if function.parameter_count > function.registers.len() {
+            self.error(format!("The function has {} parameters but only {} registers.", function.parameter_count, function.registers.len()));
+        }
+
+        for (register, (_, register_type)) in function.registers.iter().enumerate() {
+            if !type_exists(self.program, register_type) {
+                self.error(format!("%{} has type {}, which does not exist.", register, register_type));
+            }
+        }
+
+        if let FunctionKind::Run(actions_to_do) = &function.kind {
+            for action_to_do in actions_to_do {
+                if self.program.action(action_to_do).is_none() {
+                    self.error(format!("The run block does action {}, which does not exist.", action_to_do));
+                }
+            }
+        }
+
+        for (block, contents) in function.blocks.iter().enumerate() {
+            for instruction in &contents.instructions {
+                self.validate_instruction(block, instruction);
+            }
+
+            self.validate_terminator(block, &contents.terminator);
+        }
+
+        if self.diagnostics.is_empty() {
+            self.check_definite_assignment();
+        }
+    }
+
+    fn validate_instruction(&mut self, block: BlockId, instruction: &Instruction) {
+        match instruction {
+            Instruction::Copy(target, value) => {
+                if let Some(value_type) = self.operand(block, value) {
+                    self.expect_register(block, *target, &value_type);
+                }
+            },
+            Instruction::IntToFloat(target, value) => {
+                self.expect_operand(block, value, &IrType::Int);
+                self.expect_register(block, *target, &IrType::Float);
+            },
+            Instruction::Binary(target, operator, lhs, rhs) => {
+                let (lhs_type, rhs_type) = match (self.operand(block, lhs), self.operand(block, rhs)) {
+                    (Some(lhs_type), Some(rhs_type)) => (lhs_type, rhs_type),
+                    _ => return,
+                };
+
+                let accepted = match operator {
+                    BinaryOperator::Concat => lhs_type == IrType::String,
+                    BinaryOperator::Equal | BinaryOperator::NotEqual => lhs_type != IrType::Entity,
+                    _ => matches!(lhs_type, IrType::Int | IrType::Float),
+                };
+
+                if !accepted || lhs_type != rhs_type {
+                    return self.error_at(block, format!("{} {} {} is not a valid operation.", lhs_type, operator.symbol(), rhs_type));
+                }
+
+                let result_type = match operator.is_comparison() {
+                    true  => IrType::Bool,
+                    false => lhs_type,
+                };
+
+                self.expect_register(block, *target, &result_type);
+            },
+            Instruction::LoadField(target, data_name, field_name, row) => {
+                if let Some(field_type) = self.field_type(block, data_name, field_name) {
+                    self.expect_operand(block, row, &IrType::Instance(data_name.clone()));
+                    self.expect_register(block, *target, &field_type);
+                }
+            },
+            Instruction::StoreField(data_name, field_name, row, value) => {
+                if let Some(field_type) = self.field_type(block, data_name, field_name) {
+                    self.expect_operand(block, row, &IrType::Instance(data_name.clone()));
+                    self.expect_operand(block, value, &field_type);
+                }
+            },
+            Instruction::NewEntity(target) => self.expect_register(block, *target, &IrType::Entity),
+            Instruction::PushRow(data_name, entity, values) => {
+                self.expect_operand(block, entity, &IrType::Entity);
+
+                let table = match self.program.table(data_name) {
+                    Some(table) => table,
+                    None => return self.error_at(block, format!("Data {} does not exist.", data_name)),
+                };
+
+                if values.len() != table.fields.len() {
+                    return self.error_at(block, format!("{} values are pushed for the {} fields of {}.", values.len(), table.fields.len(), data_name));
+                }
+
+                for (field, value) in table.fields.iter().zip(values) {
+                    match value {
+                        Some(value) => self.expect_operand(block, value, &field.field_type),
+                        None if field.optional => (),
+                        None => self.error_at(block, format!("Field {} of {} is left unset but is not optional.", field.name, data_name)),
+                    }
+                }
+            },
+            Instruction::Create(group_name, arguments) => {
+                let group = match self.program.group(group_name) {
+                    Some(group) => group,
+                    None => return self.error_at(block, format!("Group {} does not exist.", group_name)),
+                };
+
+                if arguments.len() != group.parameter_count {
+                    return self.error_at(block, format!("Group {} takes {} arguments but gets {}.", group_name, group.parameter_count, arguments.len()));
+                }
+
+                for (argument, (_, parameter_type)) in arguments.iter().zip(&group.registers) {
+                    self.expect_operand(block, argument, parameter_type);
+                }
+            },
+            Instruction::Do(action_name) => {
+                if self.program.action(action_name).is_none() {
+                    self.error_at(block, format!("Action {} does not exist.", action_name));
+                }
+            },
+            Instruction::StartJoin(join, data_names) => {
+                self.expect_join(block, *join);
+
+                for data_name in data_names {
+                    if self.program.table(data_name).is_none() {
+                        self.error_at(block, format!("Data {} does not exist.", data_name));
+                    }
+                }
+            },
+            Instruction::AdvanceJoin(join) => self.expect_join(block, *join),
+        }
+    }
+
+    fn validate_terminator(&mut self, block: BlockId, terminator: &Terminator) {
+        for target in self.function.successors(block) {
+            if target >= self.function.blocks.len() {
+                self.error_at(block, format!("block{} does not exist.", target));
+            }
+        }
+
+        match terminator {
+            Terminator::Branch(condition, _, _) => self.expect_operand(block, condition, &IrType::Bool),
+            Terminator::NextJoin(join, rows, _, _) => {
+                self.expect_join(block, *join);
+
+                let data_names = self.joins.get(join).cloned().unwrap_or_default();
+
+                if rows.len() != data_names.len() {
+                    return self.error_at(block, format!("Join #{} gives {} rows, not {}.", join, data_names.len(), rows.len()));
+                }
+
+                for (row, data_name) in rows.iter().zip(data_names) {
+                    self.expect_register(block, *row, &IrType::Instance(data_name));
+                }
+            },
+            Terminator::NoMatch(value) => {
+                self.operand(block, value);
+            },
+            Terminator::Jump(_) | Terminator::Return => (),
+        }
+    }
+
+    /// Follows every path from the entry, where only the parameters are set, and reports the registers and joins a
+    /// block uses that some path into it leaves unset. Blocks no path reaches are not checked.
+    fn check_definite_assignment(&mut self) {
+        let function = self.function;
+        let join_slot = |join: usize| function.registers.len() + join;
+        let slot_count = function.registers.len() + function.join_count;
+
+        let mut entry_sets: Vec<Option<Vec<bool>>> = vec![None; function.blocks.len()];
+        let mut entry_set = vec![false; slot_count];
+        entry_set[..function.parameter_count].iter_mut().for_each(|slot| *slot = true);
+        entry_sets[0] = Some(entry_set);
+
+        let mut worklist = vec![0];
+
+        while let Some(block) = worklist.pop() {
+            let mut set = entry_sets[block].clone().unwrap();
+            let contents = &function.blocks[block];
+
+            for instruction in &contents.instructions {
+                let (reads, writes, join) = instruction.uses();
+
+                if let Some(join) = join {
+                    if !matches!(instruction, Instruction::StartJoin(..)) && !set[join_slot(join)] {
+                        self.error_at(block, format!("Join #{} is advanced before it is started.", join));
+                    }
+
+                    set[join_slot(join)] = true;
+                }
+
+                self.expect_set(block, &set, reads);
+                writes.into_iter().for_each(|register| set[register] = true);
+            }
+
+            let mut edges = Vec::new();
+
+            match &contents.terminator {
+                Terminator::Jump(target) => edges.push((*target, set)),
+                Terminator::Branch(condition, then_block, else_block) => {
+                    self.expect_set(block, &set, condition.register().into_iter().collect());
+                    edges.push((*then_block, set.clone()));
+                    edges.push((*else_block, set));
+                },
+                Terminator::NextJoin(join, rows, body, exit) => {
+                    if !set[join_slot(*join)] {
+                        self.error_at(block, format!("Join #{} is advanced before it is started.", join));
+                    }
+
+                    let mut body_set = set.clone();
+                    rows.iter().for_each(|row| body_set[*row] = true);
+                    edges.push((*body, body_set));
+                    edges.push((*exit, set));
+                },
+                Terminator::NoMatch(value) => self.expect_set(block, &set, value.register().into_iter().collect()),
+                Terminator::Return => (),
+            }
+
+            for (target, set) in edges {
+                let merged = match &entry_sets[target] {
+                    Some(previous) => previous.iter().zip(&set).map(|(previous, new)| *previous && *new).collect(),
+                    None => set,
+                };
+
+                if entry_sets[target].as_ref() != Some(&merged) {
+                    entry_sets[target] = Some(merged);
+                    worklist.push(target);
+                }
+            }
+
+            if !self.diagnostics.is_empty() {
+                return;
+            }
+        }
+    }
+
+    fn expect_set(&mut self, block: BlockId, set: &[bool], registers: Vec<Register>) {
+        for register in registers {
+            if !set[register] {
+                self.error_at(block, format!("%{} may be read before it is written.", register));
+            }
+        }
+    }
+
+    fn operand(&mut self, block: BlockId, operand: &Operand) -> Option<IrType> {
+        match operand {
+            Operand::Register(register) if *register >= self.function.registers.len() => {
+                self.error_at(block, format!("%{} does not exist.", register));
+                None
+            },
+            Operand::Variant(enum_name, index) if *index >= self.program.variants(enum_name).len() => {
+                self.error_at(block, format!("Enum {} has no variant #{}.", enum_name, index));
+                None
+            },
+            _ => Some(self.function.operand_type(operand)),
+        }
+    }
+
+    fn expect_operand(&mut self, block: BlockId, operand: &Operand, expected: &IrType) {
+        if let Some(actual) = self.operand(block, operand) {
+            if actual != *expected {
+                self.error_at(block, format!("{} has type {} where {} is expected.", operand, actual, expected));
+            }
+        }
+    }
+
+    fn expect_register(&mut self, block: BlockId, register: Register, expected: &IrType) {
+        self.expect_operand(block, &Operand::Register(register), expected);
+    }
+
+    fn expect_join(&mut self, block: BlockId, join: usize) {
+        if join >= self.function.join_count {
+            self.error_at(block, format!("Join #{} does not exist.", join));
+        }
+    }
+
+    fn field_type(&mut self, block: BlockId, data_name: &str, field_name: &str) -> Option<IrType> {
+        match self.program.table(data_name).and_then(|table| table.field(field_name)) {
+            Some(field) => Some(field.field_type.clone()),
+            None => {
+                self.error_at(block, format!("Field {} of {} does not exist.", field_name, data_name));
+                None
+            },
+        }
+    }
+
+    fn error_at(&mut self, block: BlockId, message: String) {
+        self.error(format!("In block{}: {}", block, message));
+    }
+
+    fn error(&mut self, message: String) {
+        self.diagnostics.push(Diagnostic::error(format!("In {}: {}", self.function.context, message)));
+    }
+}
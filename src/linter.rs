@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ast::ASTNode;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::lints::all_lints;
+use crate::resolver::closest_name;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl LintLevel {
+    pub fn from_name(name: &str) -> Option<LintLevel> {
+        match name {
+            "allow" => Some(LintLevel::Allow),
+            "warn"  => Some(LintLevel::Warn),
+            "deny"  => Some(LintLevel::Deny),
+            _       => None,
+        }
+    }
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintLevel::Allow => write!(f, "allow"),
+            LintLevel::Warn  => write!(f, "warn"),
+            LintLevel::Deny  => write!(f, "deny"),
+        }
+    }
+}
+
+/// A lint inspects the whole program and reports each finding against the top-level statement it is about,
+/// so that attributes placed on that statement can change its level.
+pub trait Lint {
+    fn name(&self) -> &'static str;
+
+    fn default_level(&self) -> LintLevel {
+        LintLevel::Warn
+    }
+
+    fn check(&self, statements: &[Box<ASTNode>]) -> Vec<(usize, Diagnostic)>;
+}
+
+/// Runs every lint and turns its findings into warnings or errors.
+/// Levels given on the command line override the defaults, and attributes override both for their declaration.
+pub fn run_lints(program: &ASTNode, command_line_levels: &[(String, LintLevel)]) -> Vec<Diagnostic> {
+    let lints = all_lints();
+    let lint_names: Vec<String> = lints.iter().map(|lint| lint.name().to_string()).collect();
+    let mut diagnostics = Vec::new();
+
+    let mut levels: HashMap<&str, (LintLevel, &str)> = lints
+        .iter()
+        .map(|lint| (lint.name(), (lint.default_level(), "on by default")))
+        .collect();
+
+    for (lint_name, level) in command_line_levels {
+        match lint_names.iter().find(|name| name == &lint_name) {
+            Some(name) => {
+                levels.insert(name, (*level, "set on the command line"));
+            },
+            None => diagnostics.push(unknown_lint(lint_name, &lint_names, "on the command line".to_string())),
+        }
+    }
+
+    let statements = match program {
+        ASTNode::Program(statements) => statements,
+        _ => return diagnostics,
+    };
+
+    let mut attribute_levels: HashMap<usize, Vec<(String, LintLevel)>> = HashMap::new();
+    let mut pending_levels: Vec<(String, LintLevel)> = Vec::new();
+
+    for (index, statement) in statements.iter().enumerate() {
+        match statement.as_ref() {
+            ASTNode::Attribute(level_name, attribute_lint_names) => {
+                let level = match LintLevel::from_name(level_name) {
+                    Some(level) => level,
+                    None => {
+                        diagnostics.push(Diagnostic::error(format!("Unknown attribute #[{}].", level_name))
+                            .with_note("expected allow, warn or deny".to_string()));
+                        continue;
+                    },
+                };
+
+                for lint_name in attribute_lint_names {
+                    match lint_names.contains(lint_name) {
+                        true  => pending_levels.push((lint_name.clone(), level)),
+                        false => diagnostics.push(unknown_lint(lint_name, &lint_names, format!("in #[{}]", level_name))),
+                    }
+                }
+            },
+            _ if !pending_levels.is_empty() => {
+                attribute_levels.insert(index, std::mem::take(&mut pending_levels));
+            },
+            _ => (),
+        }
+    }
+
+    if !pending_levels.is_empty() {
+        diagnostics.push(Diagnostic::error("Attribute at the end of the program is not followed by a declaration.".to_string()));
+    }
+
+    for lint in &lints {
+        for (statement_index, diagnostic) in lint.check(statements) {
+            let attribute_level = attribute_levels
+                .get(&statement_index)
+                .and_then(|statement_levels| statement_levels.iter().rev().find(|(name, _)| name == lint.name()));
+
+            let (level, origin) = match attribute_level {
+                Some((_, level)) => (*level, "set by an attribute"),
+                None => levels[lint.name()],
+            };
+
+            let severity = match level {
+                LintLevel::Allow => continue,
+                LintLevel::Warn  => Severity::Warning,
+                LintLevel::Deny  => Severity::Error,
+            };
+
+            let mut diagnostic = diagnostic.with_note(format!("#[{}({})] is {}", level, lint.name(), origin));
+            diagnostic.severity = severity;
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    diagnostics
+}
+
+fn unknown_lint(lint_name: &str, lint_names: &[String], location: String) -> Diagnostic {
+    let diagnostic = Diagnostic::error(format!("Unknown lint {} {}.", lint_name, location));
+
+    match closest_name(lint_name, lint_names.iter().cloned()) {
+        Some(suggestion) => diagnostic.with_note(format!("did you mean {}?", suggestion)),
+        None => diagnostic,
+    }
+}
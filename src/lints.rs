@@ -0,0 +1,547 @@
+use std::collections::HashSet;
+
+use crate::ast::{is_literal, ASTNode, TypeExpr};
+use crate::diagnostic::Diagnostic;
+use crate::linter::Lint;
+
+pub fn all_lints() -> Vec<Box<dyn Lint>> {
+    vec![
+        Box::new(UnusedData),
+        Box::new(UnusedDo),
+        Box::new(UnusedLet),
+        Box::new(UnusedGroupParameter),
+        Box::new(EmptyBody),
+        Box::new(NonPascalCaseData),
+        Box::new(NonSnakeCaseField),
+        Box::new(Shadowing),
+    ]
+}
+
+/// Data that is never instanciated, iterated, matched or used as a type.
+struct UnusedData;
+
+impl Lint for UnusedData {
+    fn name(&self) -> &'static str {
+        "unused_data"
+    }
+
+    fn check(&self, statements: &[Box<ASTNode>]) -> Vec<(usize, Diagnostic)> {
+        let mut used_names = HashSet::new();
+
+        for statement in statements {
+            collect_used_names(statement, &mut used_names);
+        }
+
+        statements
+            .iter()
+            .enumerate()
+            .filter_map(|(index, statement)| match statement.as_ref() {
+                ASTNode::Data(name, _, _) if !used_names.contains(name) => {
+                    Some((index, Diagnostic::warning(format!("Data {} is never used.", name))))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Do actions that no run block lists.
+struct UnusedDo;
+
+impl Lint for UnusedDo {
+    fn name(&self) -> &'static str {
+        "unused_do"
+    }
+
+    fn check(&self, statements: &[Box<ASTNode>]) -> Vec<(usize, Diagnostic)> {
+        let run_actions: HashSet<&String> = statements
+            .iter()
+            .filter_map(|statement| match statement.as_ref() {
+                ASTNode::Run(actions_to_do, _) => Some(actions_to_do),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+
+        statements
+            .iter()
+            .enumerate()
+            .filter_map(|(index, statement)| match statement.as_ref() {
+                ASTNode::Do(name, _, _) if !run_actions.contains(name) => {
+                    Some((index, Diagnostic::warning(format!("Do {} is never listed in a run block.", name))))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Let bindings that are never read. Names starting with _ are not reported.
+struct UnusedLet;
+
+impl Lint for UnusedLet {
+    fn name(&self) -> &'static str {
+        "unused_let"
+    }
+
+    fn check(&self, statements: &[Box<ASTNode>]) -> Vec<(usize, Diagnostic)> {
+        let mut findings = Vec::new();
+
+        for (index, statement) in statements.iter().enumerate() {
+            if let Some((context, instructions)) = action_body(statement) {
+                let mut walker = LetUsage { scopes: Vec::new(), unused_lets: Vec::new() };
+                walker.walk_block(instructions, Vec::new());
+
+                for variable_name in walker.unused_lets {
+                    findings.push((index, Diagnostic::warning(format!("In {}: let {} is never used.", context, variable_name))));
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+/// Each scope holds its variables, whether they were declared by a let, and whether they were read.
+struct LetUsage {
+    scopes: Vec<Vec<(String, bool, bool)>>,
+    unused_lets: Vec<String>,
+}
+
+impl LetUsage {
+    fn walk_block(&mut self, instructions: &[Box<ASTNode>], bindings: Vec<String>) {
+        self.scopes.push(bindings.into_iter().map(|binding| (binding, false, false)).collect());
+
+        for instruction in instructions {
+            self.walk(instruction);
+        }
+
+        self.exit_scope();
+    }
+
+    fn walk(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Declaration(variable_name, value) => {
+                self.walk(value);
+                self.scopes.last_mut().unwrap().push((variable_name.clone(), true, false));
+            },
+            ASTNode::Value(value) | ASTNode::FieldValue(_, value) => self.use_value(value),
+            ASTNode::If(condition, instructions) => {
+                self.walk(condition);
+                self.walk_block(instructions, Vec::new());
+            },
+            ASTNode::Foreach(values, collections, instructions, _) => {
+                for collection in collections {
+                    self.walk(collection);
+                }
+                self.walk_block(instructions, values.clone());
+            },
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                self.scopes.push(Vec::new());
+                self.walk(declaration);
+                self.walk(condition);
+                self.walk(progression);
+                self.walk_block(instructions, Vec::new());
+                self.exit_scope();
+            },
+            ASTNode::MatchArm(pattern, instructions) => {
+                let mut bindings = Vec::new();
+                collect_pattern_bindings(pattern, &mut bindings);
+                self.walk_block(instructions, bindings);
+            },
+            _ => {
+                for child in node.children() {
+                    self.walk(child);
+                }
+            },
+        }
+    }
+
+    fn use_value(&mut self, value: &str) {
+        let name = value.split('.').next().unwrap();
+
+        let variable = self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.iter_mut().rev().find(|(variable_name, _, _)| variable_name == name));
+
+        if let Some((_, _, used)) = variable {
+            *used = true;
+        }
+    }
+
+    fn exit_scope(&mut self) {
+        for (variable_name, is_let, used) in self.scopes.pop().unwrap() {
+            if is_let && !used && !variable_name.starts_with('_') {
+                self.unused_lets.push(variable_name);
+            }
+        }
+    }
+}
+
+/// Group parameters that no data instanciation of the group reads.
+struct UnusedGroupParameter;
+
+impl Lint for UnusedGroupParameter {
+    fn name(&self) -> &'static str {
+        "unused_group_parameter"
+    }
+
+    fn check(&self, statements: &[Box<ASTNode>]) -> Vec<(usize, Diagnostic)> {
+        let mut findings = Vec::new();
+
+        for (index, statement) in statements.iter().enumerate() {
+            if let ASTNode::Group(group_name, parameters, data_instanciations, _) = statement.as_ref() {
+                let mut used_names = HashSet::new();
+
+                for data_instanciation in data_instanciations {
+                    collect_used_names(data_instanciation, &mut used_names);
+                }
+
+                for parameter in parameters {
+                    if let ASTNode::Parameter(parameter_name, _, _) = parameter.as_ref() {
+                        if !used_names.contains(parameter_name) && !parameter_name.starts_with('_') {
+                            findings.push((index, Diagnostic::warning(format!(
+                                "In group {}: parameter {} is never used.",
+                                group_name,
+                                parameter_name
+                            ))));
+                        }
+                    }
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+/// Declarations and blocks without anything inside.
+/// Data without fields are tags and empty match arms ignore a case on purpose, so neither is reported.
+struct EmptyBody;
+
+impl Lint for EmptyBody {
+    fn name(&self) -> &'static str {
+        "empty_body"
+    }
+
+    fn check(&self, statements: &[Box<ASTNode>]) -> Vec<(usize, Diagnostic)> {
+        let mut findings = Vec::new();
+
+        for (index, statement) in statements.iter().enumerate() {
+            let description = match statement.as_ref() {
+                ASTNode::Group(name, _, data_instanciations, _) if data_instanciations.is_empty() => format!("Group {}", name),
+                ASTNode::Do(name, instructions, _) if instructions.is_empty() => format!("Do {}", name),
+                ASTNode::Enum(name, variants, _) if variants.is_empty() => format!("Enum {}", name),
+                ASTNode::Run(actions_to_do, instructions) if actions_to_do.is_empty() && instructions.is_empty() => {
+                    "Run block".to_string()
+                },
+                _ => "".to_string(),
+            };
+
+            if !description.is_empty() {
+                findings.push((index, Diagnostic::warning(format!("{} has an empty body.", description))));
+            }
+
+            if let Some((context, instructions)) = action_body(statement) {
+                let mut empty_blocks = Vec::new();
+                collect_empty_blocks(instructions, &mut empty_blocks);
+
+                for empty_block in empty_blocks {
+                    findings.push((index, Diagnostic::warning(format!("In {}: {} has an empty body.", context, empty_block))));
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+fn collect_empty_blocks(instructions: &[Box<ASTNode>], empty_blocks: &mut Vec<&'static str>) {
+    for instruction in instructions {
+        let (description, body) = match instruction.as_ref() {
+            ASTNode::If(_, body) => ("if", body),
+            ASTNode::Foreach(_, _, body, _) => ("foreach", body),
+            ASTNode::For(_, _, _, body) => ("for", body),
+            ASTNode::Match(_, arms) => {
+                for arm in arms {
+                    if let ASTNode::MatchArm(_, body) = arm.as_ref() {
+                        collect_empty_blocks(body, empty_blocks);
+                    }
+                }
+                continue;
+            },
+            _ => continue,
+        };
+
+        if body.is_empty() {
+            empty_blocks.push(description);
+        }
+
+        collect_empty_blocks(body, empty_blocks);
+    }
+}
+
+/// Data names not written in PascalCase, such as rigid_body for RigidBody.
+struct NonPascalCaseData;
+
+impl Lint for NonPascalCaseData {
+    fn name(&self) -> &'static str {
+        "non_pascal_case_data"
+    }
+
+    fn check(&self, statements: &[Box<ASTNode>]) -> Vec<(usize, Diagnostic)> {
+        statements
+            .iter()
+            .enumerate()
+            .filter_map(|(index, statement)| match statement.as_ref() {
+                ASTNode::Data(name, _, _) => {
+                    let short_name = name.rsplit("::").next().unwrap();
+                    let pascal_case_name = to_pascal_case(short_name);
+
+                    (short_name != pascal_case_name).then(|| (index, Diagnostic::warning(format!(
+                        "Data {} should have a PascalCase name.",
+                        name
+                    )).with_note(format!("rename it to {}", pascal_case_name))))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Data field names not written in snake_case, such as maxSpeed for max_speed.
+struct NonSnakeCaseField;
+
+impl Lint for NonSnakeCaseField {
+    fn name(&self) -> &'static str {
+        "non_snake_case_field"
+    }
+
+    fn check(&self, statements: &[Box<ASTNode>]) -> Vec<(usize, Diagnostic)> {
+        let mut findings = Vec::new();
+
+        for (index, statement) in statements.iter().enumerate() {
+            if let ASTNode::Data(data_name, fields, _) = statement.as_ref() {
+                for field in fields {
+                    if let ASTNode::Field(field_name, _, _, _) = field.as_ref() {
+                        let snake_case_name = to_snake_case(field_name);
+
+                        if field_name != &snake_case_name {
+                            findings.push((index, Diagnostic::warning(format!(
+                                "Field {} of data {} should have a snake_case name.",
+                                field_name,
+                                data_name
+                            )).with_note(format!("rename it to {}", snake_case_name))));
+                        }
+                    }
+                }
+            }
+        }
+
+        findings
+    }
+}
+
+/// Variables declared in a nested body, by a let, a for, a foreach or a match arm, that hide a variable of an
+/// enclosing body.
+struct Shadowing;
+
+impl Lint for Shadowing {
+    fn name(&self) -> &'static str {
+        "shadowing"
+    }
+
+    fn check(&self, statements: &[Box<ASTNode>]) -> Vec<(usize, Diagnostic)> {
+        let mut findings = Vec::new();
+
+        for (index, statement) in statements.iter().enumerate() {
+            if let Some((context, instructions)) = action_body(statement) {
+                let mut diagnostics = Vec::new();
+                check_shadowing(instructions, &mut vec![Vec::new()], &context, &mut diagnostics);
+                findings.extend(diagnostics.into_iter().map(|diagnostic| (index, diagnostic)));
+            }
+        }
+
+        findings
+    }
+}
+
+/// Each scope holds the variables it declares along with the construct that declared them.
+fn check_shadowing(
+    instructions: &[Box<ASTNode>],
+    scopes: &mut Vec<Vec<(String, String)>>,
+    context: &str,
+    diagnostics: &mut Vec<Diagnostic>
+) {
+    for instruction in instructions {
+        match instruction.as_ref() {
+            ASTNode::Declaration(variable_name, _) => declare_local(variable_name, "let", scopes, context, diagnostics),
+            ASTNode::If(_, body) => check_nested_shadowing(body, Vec::new(), scopes, context, diagnostics),
+            ASTNode::Foreach(values, _, body, _) => {
+                let bindings = values.iter().map(|value| (value.clone(), "foreach".to_string())).collect();
+                check_nested_shadowing(body, bindings, scopes, context, diagnostics);
+            },
+            ASTNode::For(declaration, _, _, body) => {
+                scopes.push(Vec::new());
+
+                if let ASTNode::Declaration(variable_name, _) = declaration.as_ref() {
+                    declare_local(variable_name, "for", scopes, context, diagnostics);
+                }
+
+                check_nested_shadowing(body, Vec::new(), scopes, context, diagnostics);
+                scopes.pop();
+            },
+            ASTNode::Match(_, arms) => {
+                for arm in arms {
+                    if let ASTNode::MatchArm(pattern, body) = arm.as_ref() {
+                        let mut bindings = Vec::new();
+                        collect_pattern_bindings(pattern, &mut bindings);
+
+                        let bindings = bindings.into_iter().map(|binding| (binding, "match".to_string())).collect();
+                        check_nested_shadowing(body, bindings, scopes, context, diagnostics);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+fn check_nested_shadowing(
+    body: &[Box<ASTNode>],
+    bindings: Vec<(String, String)>,
+    scopes: &mut Vec<Vec<(String, String)>>,
+    context: &str,
+    diagnostics: &mut Vec<Diagnostic>
+) {
+    scopes.push(Vec::new());
+
+    for (variable_name, declared_by) in bindings {
+        declare_local(&variable_name, &declared_by, scopes, context, diagnostics);
+    }
+
+    check_shadowing(body, scopes, context, diagnostics);
+    scopes.pop();
+}
+
+fn declare_local(
+    variable_name: &str,
+    declared_by: &str,
+    scopes: &mut [Vec<(String, String)>],
+    context: &str,
+    diagnostics: &mut Vec<Diagnostic>
+) {
+    let (current_scope, enclosing_scopes) = scopes.split_last_mut().unwrap();
+
+    let shadowed = enclosing_scopes
+        .iter()
+        .rev()
+        .find_map(|scope| scope.iter().find(|(name, _)| name == variable_name));
+
+    if let Some((_, shadowed_by)) = shadowed {
+        diagnostics.push(Diagnostic::warning(format!(
+            "In {}: {} declared by a {} shadows a variable of an enclosing body.",
+            context,
+            variable_name,
+            declared_by
+        )).with_note(format!("{} is first declared by a {} in {}", variable_name, shadowed_by, context)));
+    }
+
+    current_scope.push((variable_name.to_string(), declared_by.to_string()));
+}
+
+/// Gives the instructions of a do or run block along with a description of it.
+fn action_body(statement: &ASTNode) -> Option<(String, &[Box<ASTNode>])> {
+    match statement {
+        ASTNode::Do(name, instructions, _) => Some((format!("do {}", name), instructions)),
+        ASTNode::Run(actions_to_do, instructions) => Some((format!("run ({})", actions_to_do.join(", ")), instructions)),
+        _ => None,
+    }
+}
+
+/// Collects every name a node refers to: value heads, instanciated data, patterns and types.
+fn collect_used_names(node: &ASTNode, used_names: &mut HashSet<String>) {
+    match node {
+        ASTNode::Value(value) | ASTNode::FieldValue(_, value) if !is_literal(value) => {
+            used_names.insert(value.split('.').next().unwrap().to_string());
+        },
+        ASTNode::DataInstanciation(name, _)
+        | ASTNode::VariantConstructor(name, _)
+        | ASTNode::VariantPattern(name, _)
+        | ASTNode::DataPattern(name, _) => {
+            used_names.insert(name.clone());
+        },
+        ASTNode::Parameter(_, node_type, _) | ASTNode::Field(_, node_type, _, _) => collect_type_names(node_type, used_names),
+        _ => (),
+    }
+
+    for child in node.children() {
+        collect_used_names(child, used_names);
+    }
+}
+
+fn collect_type_names(type_expr: &TypeExpr, used_names: &mut HashSet<String>) {
+    match type_expr {
+        TypeExpr::Named(name) => {
+            used_names.insert(name.clone());
+        },
+        TypeExpr::Generic(_, arguments) => {
+            for argument in arguments {
+                collect_type_names(argument, used_names);
+            }
+        },
+        TypeExpr::Array(inner_type, _) | TypeExpr::Optional(inner_type) => collect_type_names(inner_type, used_names),
+    }
+}
+
+fn collect_pattern_bindings(pattern: &ASTNode, bindings: &mut Vec<String>) {
+    match pattern {
+        ASTNode::BindingPattern(name) => bindings.push(name.clone()),
+        _ => {
+            for child in pattern.children() {
+                collect_pattern_bindings(child, bindings);
+            }
+        },
+    }
+}
+
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut characters = word.chars();
+            let first = characters.next().unwrap();
+            first.to_uppercase().chain(characters).collect::<String>()
+        })
+        .collect()
+}
+
+/// A run of capitals is one word, so maxHP becomes max_hp and HPMax becomes hp_max.
+fn to_snake_case(name: &str) -> String {
+    let characters: Vec<char> = name.chars().collect();
+    let mut snake_case_name = String::new();
+
+    for (index, character) in characters.iter().enumerate() {
+        if character.is_uppercase() {
+            let previous = index.checked_sub(1).map(|previous| characters[previous]);
+            let next = characters.get(index + 1);
+
+            let starts_word = match previous {
+                Some(previous) if previous.is_uppercase() => next.is_some_and(|next| next.is_lowercase()),
+                Some(previous) => previous != '_',
+                None => false,
+            };
+
+            if starts_word {
+                snake_case_name.push('_');
+            }
+            snake_case_name.extend(character.to_lowercase());
+        }
+        else {
+            snake_case_name.push(*character);
+        }
+    }
+
+    snake_case_name
+}
@@ -0,0 +1,981 @@
+use std::collections::HashMap;
+
+use crate::ir::{BinaryOperator, FunctionKind, Instruction, IrFunction, IrProgram, IrTable, IrType, Operand, Register, Terminator};
+
+/// Declarations of the C library functions the module calls, and helpers every module starts with: the merge join
+/// over entity columns, printing floats the way run prints them, and reporting errors. A $name stands for a pointer
+/// to the string of that name in PRELUDE_STRINGS.
+const PRELUDE: &str = r#"declare i32 @dprintf(i32, i8*, ...)
+declare i32 @snprintf(i8*, i64, i8*, ...)
+declare double @strtod(i8*, i8**)
+declare i8* @strchr(i8*, i32)
+declare i32 @atoi(i8*)
+declare void @exit(i32) noreturn
+declare i8* @realloc(i8*, i64)
+declare i64 @strlen(i8*)
+declare i8* @strcpy(i8*, i8*)
+declare i8* @strcat(i8*, i8*)
+declare i32 @strcmp(i8*, i8*)
+declare { i64, i1 } @llvm.sadd.with.overflow.i64(i64, i64)
+declare { i64, i1 } @llvm.ssub.with.overflow.i64(i64, i64)
+declare { i64, i1 } @llvm.smul.with.overflow.i64(i64, i64)
+
+@dolang.entity_count = internal global i64 0
+
+define internal i8* @dolang_grow(i8* %column, i64 %count, i64 %size) {
+entry:
+  %bytes = mul i64 %count, %size
+  %grown = call i8* @realloc(i8* %column, i64 %bytes)
+  %failed = icmp eq i8* %grown, null
+  br i1 %failed, label %fail, label %done
+fail:
+  call i32 (i32, i8*, ...) @dprintf(i32 2, i8* $out_of_memory)
+  call void @exit(i32 1)
+  unreachable
+done:
+  ret i8* %grown
+}
+
+; Moves the rows forward until they all point to the same entity, and tells whether there is one.
+define internal i1 @dolang_join_next(i64 %count, i64*** %tables, i64* %lengths, i64* %rows) {
+entry:
+  br label %scan_start
+scan_start:
+  br label %scan
+scan:
+  %index = phi i64 [ 0, %scan_start ], [ %next_index, %scan_entity ]
+  %highest = phi i64 [ 0, %scan_start ], [ %new_highest, %scan_entity ]
+  %same = phi i1 [ true, %scan_start ], [ %new_same, %scan_entity ]
+  %scanned = icmp eq i64 %index, %count
+  br i1 %scanned, label %compare, label %scan_row
+scan_row:
+  %row_pointer = getelementptr inbounds i64, i64* %rows, i64 %index
+  %row = load i64, i64* %row_pointer
+  %length_pointer = getelementptr inbounds i64, i64* %lengths, i64 %index
+  %length = load i64, i64* %length_pointer
+  %exhausted = icmp uge i64 %row, %length
+  br i1 %exhausted, label %not_found, label %scan_entity
+scan_entity:
+  %table_pointer = getelementptr inbounds i64**, i64*** %tables, i64 %index
+  %table = load i64**, i64*** %table_pointer
+  %entities = load i64*, i64** %table
+  %entity_pointer = getelementptr inbounds i64, i64* %entities, i64 %row
+  %entity = load i64, i64* %entity_pointer
+  %is_first = icmp eq i64 %index, 0
+  %is_highest = icmp eq i64 %entity, %highest
+  %agrees = or i1 %is_first, %is_highest
+  %new_same = and i1 %same, %agrees
+  %is_higher = icmp ugt i64 %entity, %highest
+  %new_highest = select i1 %is_higher, i64 %entity, i64 %highest
+  %next_index = add i64 %index, 1
+  br label %scan
+compare:
+  br i1 %same, label %found, label %advance_start
+found:
+  ret i1 true
+not_found:
+  ret i1 false
+advance_start:
+  br label %advance
+advance:
+  %advance_index = phi i64 [ 0, %advance_start ], [ %next_advance_index, %advance_next ]
+  %advanced = icmp eq i64 %advance_index, %count
+  br i1 %advanced, label %scan_start, label %advance_row
+advance_row:
+  %advance_row_pointer = getelementptr inbounds i64, i64* %rows, i64 %advance_index
+  %advance_row_value = load i64, i64* %advance_row_pointer
+  %advance_table_pointer = getelementptr inbounds i64**, i64*** %tables, i64 %advance_index
+  %advance_table = load i64**, i64*** %advance_table_pointer
+  %advance_entities = load i64*, i64** %advance_table
+  %advance_entity_pointer = getelementptr inbounds i64, i64* %advance_entities, i64 %advance_row_value
+  %advance_entity = load i64, i64* %advance_entity_pointer
+  %behind = icmp ult i64 %advance_entity, %highest
+  br i1 %behind, label %increment, label %advance_next
+increment:
+  %incremented_row = add i64 %advance_row_value, 1
+  store i64 %incremented_row, i64* %advance_row_pointer
+  br label %advance_next
+advance_next:
+  %next_advance_index = add i64 %advance_index, 1
+  br label %advance
+}
+
+; Writes the shortest decimal form that reads back as the same float, always with a fractional part.
+define internal void @dolang_print_float(i32 %file, double %value) {
+entry:
+  %digits = alloca [40 x i8]
+  %buffer = getelementptr inbounds [40 x i8], [40 x i8]* %digits, i64 0, i64 0
+  %is_nan = fcmp uno double %value, %value
+  br i1 %is_nan, label %print_nan, label %check_infinity
+print_nan:
+  call i32 (i32, i8*, ...) @dprintf(i32 %file, i8* $nan)
+  ret void
+check_infinity:
+  %is_infinity = fcmp oeq double %value, 0x7FF0000000000000
+  br i1 %is_infinity, label %print_infinity, label %check_negative_infinity
+print_infinity:
+  call i32 (i32, i8*, ...) @dprintf(i32 %file, i8* $infinity)
+  ret void
+check_negative_infinity:
+  %is_negative_infinity = fcmp oeq double %value, 0xFFF0000000000000
+  br i1 %is_negative_infinity, label %print_negative_infinity, label %search
+print_negative_infinity:
+  call i32 (i32, i8*, ...) @dprintf(i32 %file, i8* $negative_infinity)
+  ret void
+search:
+  %precision = phi i32 [ 1, %check_negative_infinity ], [ %next_precision, %retry ]
+  %exponent_decimals = sub i32 %precision, 1
+  call i32 (i8*, i64, i8*, ...) @snprintf(i8* %buffer, i64 40, i8* $exponent_format, i32 %exponent_decimals, double %value)
+  %read_back = call double @strtod(i8* %buffer, i8** null)
+  %is_exact = fcmp oeq double %read_back, %value
+  %is_longest = icmp sge i32 %precision, 17
+  %is_found = or i1 %is_exact, %is_longest
+  br i1 %is_found, label %print, label %retry
+retry:
+  %next_precision = add i32 %precision, 1
+  br label %search
+print:
+  %exponent_start = call i8* @strchr(i8* %buffer, i32 101)
+  %exponent_digits = getelementptr inbounds i8, i8* %exponent_start, i64 1
+  %exponent = call i32 @atoi(i8* %exponent_digits)
+  %is_zero = fcmp oeq double %value, 0.0
+  %is_small = icmp slt i32 %exponent, -4
+  %is_large = icmp sge i32 %exponent, 16
+  %is_out_of_range = or i1 %is_small, %is_large
+  %is_scientific = select i1 %is_zero, i1 false, i1 %is_out_of_range
+  br i1 %is_scientific, label %print_scientific, label %print_fixed
+print_scientific:
+  store i8 0, i8* %exponent_start
+  call i32 (i32, i8*, ...) @dprintf(i32 %file, i8* $scientific_format, i8* %buffer, i32 %exponent)
+  ret void
+print_fixed:
+  %decimals = sub i32 %exponent_decimals, %exponent
+  %has_decimals = icmp sgt i32 %decimals, 0
+  br i1 %has_decimals, label %print_decimals, label %print_whole
+print_decimals:
+  call i32 (i32, i8*, ...) @dprintf(i32 %file, i8* $fixed_format, i32 %decimals, double %value)
+  ret void
+print_whole:
+  call i32 (i32, i8*, ...) @dprintf(i32 %file, i8* $whole_format, double %value)
+  ret void
+}
+
+define internal i8* @dolang_concat(i8* %lhs, i8* %rhs) {
+entry:
+  %lhs_length = call i64 @strlen(i8* %lhs)
+  %rhs_length = call i64 @strlen(i8* %rhs)
+  %length = add i64 %lhs_length, %rhs_length
+  %size = add i64 %length, 1
+  %result = call i8* @dolang_grow(i8* null, i64 %size, i64 1)
+  call i8* @strcpy(i8* %result, i8* %lhs)
+  call i8* @strcat(i8* %result, i8* %rhs)
+  ret i8* %result
+}
+
+define internal void @dolang_fail(i8* %context, i8* %message) noreturn {
+entry:
+  call i32 (i32, i8*, ...) @dprintf(i32 2, i8* $message_format, i8* %context, i8* %message)
+  call void @exit(i32 1)
+  unreachable
+}
+
+define internal void @dolang_fail_int(i8* %context, i8* %message, i64 %lhs, i8* %operator, i64 %rhs) noreturn {
+entry:
+  call i32 (i32, i8*, ...) @dprintf(i32 2, i8* $error_format, i8* %context)
+  call i32 (i32, i8*, ...) @dprintf(i32 2, i8* %message, i64 %lhs, i8* %operator, i64 %rhs)
+  call i32 (i32, i8*, ...) @dprintf(i32 2, i8* $newline)
+  call void @exit(i32 1)
+  unreachable
+}
+
+define internal void @dolang_fail_float_division(i8* %context, double %lhs, i8* %operator, double %rhs) noreturn {
+entry:
+  call i32 (i32, i8*, ...) @dprintf(i32 2, i8* $error_format, i8* %context)
+  call i32 (i32, i8*, ...) @dprintf(i32 2, i8* $division_by_zero)
+  call void @dolang_print_float(i32 2, double %lhs)
+  call i32 (i32, i8*, ...) @dprintf(i32 2, i8* $operator_format, i8* %operator)
+  call void @dolang_print_float(i32 2, double %rhs)
+  call i32 (i32, i8*, ...) @dprintf(i32 2, i8* $period)
+  call void @exit(i32 1)
+  unreachable
+}
+"#;
+
+const PRELUDE_STRINGS: [(&str, &str); 14] = [
+    ("out_of_memory", "error: Out of memory.\n"),
+    ("nan", "NaN"),
+    ("infinity", "inf"),
+    ("negative_infinity", "-inf"),
+    ("exponent_format", "%.*e"),
+    ("scientific_format", "%se%d"),
+    ("fixed_format", "%.*f"),
+    ("whole_format", "%.0f.0"),
+    ("message_format", "error: In %s: %s\n"),
+    ("error_format", "error: In %s: "),
+    ("newline", "\n"),
+    ("division_by_zero", "Division by zero in "),
+    ("operator_format", " %s "),
+    ("period", ".\n"),
+];
+
+const OVERFLOW_MESSAGE: &str = "%ld %s %ld overflows a 64 bit integer.";
+const DIVISION_BY_ZERO_MESSAGE: &str = "Division by zero in %ld %s %ld.";
+
+struct LlvmGenerator<'a> {
+    program: &'a IrProgram,
+    /// Type definitions, tables and string constants.
+    globals: String,
+    /// The functions written so far.
+    functions: String,
+    /// The allocas of the function being written, all placed in its entry block.
+    entry: String,
+    body: String,
+    strings: HashMap<String, String>,
+    /// The alloca holding each register of the function being written.
+    registers: Vec<String>,
+    /// The alloca of the tables, the lengths and the rows of each join of the function being written.
+    joins: Vec<(String, String, String)>,
+    register_count: usize,
+    context: String,
+}
+
+/// Generates a textual LLVM IR module: data become struct types stored in growable arrays, groups functions that
+/// create an entity, do actions and run blocks functions with a basic block per block, and main runs the run blocks in
+/// order then prints the world as run does. Registers live in allocas, so control flow needs no phi.
+pub fn generate_llvm(program: &IrProgram) -> String {
+    let mut generator = LlvmGenerator {
+        program,
+        globals: String::new(),
+        functions: String::new(),
+        entry: String::new(),
+        body: String::new(),
+        strings: HashMap::new(),
+        registers: Vec::new(),
+        joins: Vec::new(),
+        register_count: 0,
+        context: "".to_string(),
+    };
+
+    for (enum_name, variants) in &program.enums {
+        generator.generate_enum(enum_name, variants);
+    }
+
+    for table in &program.tables {
+        generator.generate_table(table);
+    }
+
+    let mut run_count = 0;
+
+    for function in &program.functions {
+        let signature = match &function.kind {
+            FunctionKind::Group(group_name) => format!("define void {}", symbol('@', &format!("create.{}", group_name))),
+            FunctionKind::Do(action_name) => format!("define void {}", symbol('@', &format!("do.{}", action_name))),
+            FunctionKind::Run(_) => {
+                run_count += 1;
+                format!("define internal void @run.{}", run_count - 1)
+            },
+        };
+
+        generator.generate_function(function, &signature);
+    }
+
+    generator.generate_main(run_count);
+
+    let mut prelude = PRELUDE.to_string();
+
+    for (name, text) in PRELUDE_STRINGS {
+        let pointer = generator.string(text);
+        prelude = prelude.replace(&format!("${}", name), &pointer);
+    }
+
+    format!("{}\n{}{}", prelude, generator.globals, generator.functions)
+}
+
+impl<'a> LlvmGenerator<'a> {
+    fn generate_enum(&mut self, name: &str, variants: &[String]) {
+        let names: Vec<String> = variants.iter().map(|variant_name| format!("i8* {}", self.string(variant_name))).collect();
+
+        self.globals.push_str(&format!(
+            "{} = private unnamed_addr constant [{} x i8*] [{}]\n",
+            symbol('@', &format!("{}.names", name)),
+            names.len(),
+            names.join(", ")
+        ));
+    }
+
+    /// A data is a struct type with one member per field, and an optional field a second member telling whether it
+    /// is set. Its instances are kept in an array next to the entity of each, and each table gets a function that
+    /// appends an instance and one that prints every instance.
+    fn generate_table(&mut self, table: &IrTable) {
+        let mut members = Vec::new();
+
+        for field in &table.fields {
+            members.push(llvm_type(&field.field_type).to_string());
+
+            if field.optional {
+                members.push("i1".to_string());
+            }
+        }
+
+        let name = &table.data_name;
+        let struct_type = symbol('%', name);
+
+        self.globals.push_str(&format!("\n{} = type {{ {} }}\n", struct_type, members.join(", ")));
+        self.globals.push_str(&format!("{} = internal global i64 0\n", symbol('@', &format!("{}.length", name))));
+        self.globals.push_str(&format!("{} = internal global i64 0\n", symbol('@', &format!("{}.capacity", name))));
+        self.globals.push_str(&format!("{} = internal global i64* null\n", symbol('@', &format!("{}.entities", name))));
+        self.globals.push_str(&format!("{} = internal global {}* null\n", symbol('@', &format!("{}.instances", name)), struct_type));
+
+        self.generate_push(table);
+        self.generate_print(table);
+    }
+
+    /// Each join gets the tables it goes over, their lengths when it starts, so that instances created in the loop
+    /// are not visited, and its current rows.
+    fn generate_function(&mut self, function: &IrFunction, signature: &str) {
+        self.context = function.context.clone();
+        self.registers.clear();
+        self.joins.clear();
+
+        let mut parameters = Vec::new();
+
+        for (register, (name, register_type)) in function.registers.iter().enumerate() {
+            let name = if name.is_empty() { "r" } else { name };
+            let pointer = self.alloca(llvm_type(register_type), name);
+
+            if register < function.parameter_count {
+                let argument = format!("%parameter.{}", register);
+                parameters.push(format!("{} {}", llvm_type(register_type), argument));
+                self.emit(&format!("store {0} {1}, {0}* {2}", llvm_type(register_type), argument, pointer));
+            }
+
+            self.registers.push(pointer);
+        }
+
+        let joins = function.joins();
+
+        for join in 0..function.join_count {
+            let count = joins.get(&join).map_or(0, Vec::len);
+            let tables = self.alloca(&format!("[{} x i64**]", count), "join.tables");
+            let lengths = self.alloca(&format!("[{} x i64]", count), "join.lengths");
+            let rows = self.alloca(&format!("[{} x i64]", count), "join.rows");
+            self.joins.push((tables, lengths, rows));
+        }
+
+        self.emit("br label %block0");
+
+        for block in function.reachable_blocks() {
+            self.start_block(&format!("block{}", block));
+
+            for instruction in &function.blocks[block].instructions {
+                self.generate_instruction(function, instruction, &joins);
+            }
+
+            self.generate_terminator(function, &function.blocks[block].terminator, &joins);
+        }
+
+        self.end_function(&format!("{}({})", signature, parameters.join(", ")));
+    }
+
+    fn generate_instruction(&mut self, function: &IrFunction, instruction: &Instruction, joins: &HashMap<usize, Vec<String>>) {
+        match instruction {
+            Instruction::Copy(target, value) => {
+                let value = self.operand(function, value);
+                self.store_register(function, *target, &value);
+            },
+            Instruction::IntToFloat(target, value) => {
+                let value = self.operand(function, value);
+                let converted = self.instruction(&format!("sitofp i64 {} to double", value));
+                self.store_register(function, *target, &converted);
+            },
+            Instruction::Binary(target, operator, lhs, rhs) => {
+                let operand_type = function.operand_type(lhs);
+                let (lhs, rhs) = (self.operand(function, lhs), self.operand(function, rhs));
+
+                let result = match (operator, &operand_type) {
+                    (BinaryOperator::Concat, _) => self.instruction(&format!("call i8* @dolang_concat(i8* {}, i8* {})", lhs, rhs)),
+                    (operator, _) if operator.is_comparison() => self.comparison(*operator, &operand_type, &lhs, &rhs),
+                    (operator, IrType::Int) => self.int_arithmetic(operator.symbol(), &lhs, &rhs),
+                    (operator, _) => self.float_arithmetic(operator.symbol(), &lhs, &rhs),
+                };
+
+                self.store_register(function, *target, &result);
+            },
+            Instruction::LoadField(target, data_name, field_name, row) => {
+                let row = self.operand(function, row);
+                let (value_pointer, _) = self.field_pointers(data_name, field_name, &row);
+                let value = self.load(llvm_type(&function.registers[*target].1), &value_pointer);
+                self.store_register(function, *target, &value);
+            },
+            Instruction::StoreField(data_name, field_name, row, value) => {
+                let value_type = function.operand_type(value);
+                let (row, value) = (self.operand(function, row), self.operand(function, value));
+                let (value_pointer, flag_pointer) = self.field_pointers(data_name, field_name, &row);
+                self.emit(&format!("store {0} {1}, {0}* {2}", llvm_type(&value_type), value, value_pointer));
+
+                if let Some(flag_pointer) = flag_pointer {
+                    self.emit(&format!("store i1 true, i1* {}", flag_pointer));
+                }
+            },
+            Instruction::NewEntity(target) => {
+                let entity = self.load("i64", "@dolang.entity_count");
+                let next_entity = self.instruction(&format!("add i64 {}, 1", entity));
+                self.emit(&format!("store i64 {}, i64* @dolang.entity_count", next_entity));
+                self.store_register(function, *target, &entity);
+            },
+            Instruction::PushRow(data_name, entity, values) => {
+                let mut arguments = vec![format!("i64 {}", self.operand(function, entity))];
+                let fields = self.program.table(data_name).map_or(Vec::new(), |table| table.fields.clone());
+
+                for (field, value) in fields.iter().zip(values) {
+                    let argument = match value {
+                        Some(value) => self.operand(function, value),
+                        None => self.zero_value(&field.field_type),
+                    };
+
+                    arguments.push(format!("{} {}", llvm_type(&field.field_type), argument));
+
+                    if field.optional {
+                        arguments.push(format!("i1 {}", value.is_some()));
+                    }
+                }
+
+                self.emit(&format!("call void {}({})", symbol('@', &format!("push.{}", data_name)), arguments.join(", ")));
+            },
+            Instruction::Create(group_name, arguments) => {
+                let arguments: Vec<String> = arguments
+                    .iter()
+                    .map(|argument| {
+                        let argument_type = function.operand_type(argument);
+                        format!("{} {}", llvm_type(&argument_type), self.operand(function, argument))
+                    })
+                    .collect();
+
+                self.emit(&format!("call void {}({})", symbol('@', &format!("create.{}", group_name)), arguments.join(", ")));
+            },
+            Instruction::Do(action_name) => self.emit(&format!("call void {}()", symbol('@', &format!("do.{}", action_name)))),
+            Instruction::StartJoin(join, data_names) => {
+                let (tables, lengths, rows) = self.joins[*join].clone();
+                let count = data_names.len();
+
+                for (index, data_name) in data_names.iter().enumerate() {
+                    let table_pointer = self.element_pointer(&format!("[{} x i64**]", count), &tables, index);
+                    self.emit(&format!("store i64** {}, i64*** {}", symbol('@', &format!("{}.entities", data_name)), table_pointer));
+
+                    let length = self.load("i64", &symbol('@', &format!("{}.length", data_name)));
+                    let length_pointer = self.element_pointer(&format!("[{} x i64]", count), &lengths, index);
+                    self.emit(&format!("store i64 {}, i64* {}", length, length_pointer));
+
+                    let row_pointer = self.element_pointer(&format!("[{} x i64]", count), &rows, index);
+                    self.emit(&format!("store i64 0, i64* {}", row_pointer));
+                }
+            },
+            Instruction::AdvanceJoin(join) => {
+                let rows = self.joins[*join].2.clone();
+                let count = joins.get(join).map_or(0, Vec::len);
+
+                for index in 0..count {
+                    let row_pointer = self.element_pointer(&format!("[{} x i64]", count), &rows, index);
+                    let row = self.load("i64", &row_pointer);
+                    let next_row = self.instruction(&format!("add i64 {}, 1", row));
+                    self.emit(&format!("store i64 {}, i64* {}", next_row, row_pointer));
+                }
+            },
+        }
+    }
+
+    fn generate_terminator(&mut self, function: &IrFunction, terminator: &Terminator, joins: &HashMap<usize, Vec<String>>) {
+        match terminator {
+            Terminator::Jump(target) => self.emit(&format!("br label %block{}", target)),
+            Terminator::Branch(condition, then_block, else_block) => {
+                let condition = self.operand(function, condition);
+                self.emit(&format!("br i1 {}, label %block{}, label %block{}", condition, then_block, else_block));
+            },
+            Terminator::NextJoin(join, row_registers, body, exit) => {
+                let (tables, lengths, rows) = self.joins[*join].clone();
+                let count = joins.get(join).map_or(0, Vec::len);
+
+                let first_table = self.element_pointer(&format!("[{} x i64**]", count), &tables, 0);
+                let first_length = self.element_pointer(&format!("[{} x i64]", count), &lengths, 0);
+                let first_row = self.element_pointer(&format!("[{} x i64]", count), &rows, 0);
+                let has_entity = self.instruction(&format!(
+                    "call i1 @dolang_join_next(i64 {}, i64*** {}, i64* {}, i64* {})",
+                    count, first_table, first_length, first_row
+                ));
+                let rows_label = self.label("join.found");
+                self.emit(&format!("br i1 {}, label %{}, label %block{}", has_entity, rows_label, exit));
+
+                self.start_block(&rows_label);
+
+                for (index, register) in row_registers.iter().enumerate() {
+                    let row_pointer = self.element_pointer(&format!("[{} x i64]", count), &rows, index);
+                    let row = self.load("i64", &row_pointer);
+                    self.store_register(function, *register, &row);
+                }
+
+                self.emit(&format!("br label %block{}", body));
+            },
+            Terminator::NoMatch(_) => {
+                let context = self.string(&self.context.clone());
+                let message = self.string("No match arm matches the value.");
+                self.emit(&format!("call void @dolang_fail(i8* {}, i8* {})", context, message));
+                self.emit("unreachable");
+            },
+            Terminator::Return => self.emit("ret void"),
+        }
+    }
+
+    /// Strings compare by content.
+    fn comparison(&mut self, operator: BinaryOperator, operand_type: &IrType, lhs: &str, rhs: &str) -> String {
+        let predicate = |signed: bool| match operator {
+            BinaryOperator::Equal => "eq",
+            BinaryOperator::NotEqual => "ne",
+            BinaryOperator::Greater if signed => "sgt",
+            BinaryOperator::Less if signed => "slt",
+            BinaryOperator::GreaterOrEqual if signed => "sge",
+            BinaryOperator::LessOrEqual if signed => "sle",
+            BinaryOperator::Greater => "ogt",
+            BinaryOperator::Less => "olt",
+            BinaryOperator::GreaterOrEqual => "oge",
+            _ => "ole",
+        };
+
+        match operand_type {
+            IrType::String => {
+                let difference = self.instruction(&format!("call i32 @strcmp(i8* {}, i8* {})", lhs, rhs));
+                self.instruction(&format!("icmp {} i32 {}, 0", predicate(true), difference))
+            },
+            IrType::Float => {
+                let predicate = match predicate(false) {
+                    "eq" => "oeq",
+                    "ne" => "une",
+                    predicate => predicate,
+                };
+
+                self.instruction(&format!("fcmp {} double {}, {}", predicate, lhs, rhs))
+            },
+            operand_type => self.instruction(&format!("icmp {} {} {}, {}", predicate(true), llvm_type(operand_type), lhs, rhs)),
+        }
+    }
+
+    fn operand(&mut self, function: &IrFunction, operand: &Operand) -> String {
+        match operand {
+            Operand::Register(register) => {
+                let pointer = self.registers[*register].clone();
+                self.load(llvm_type(&function.registers[*register].1), &pointer)
+            },
+            Operand::Int(int) => int.to_string(),
+            Operand::Float(float) => float_constant(*float),
+            Operand::Bool(bool) => bool.to_string(),
+            Operand::String(string) => self.string(string),
+            Operand::Variant(_, index) => index.to_string(),
+        }
+    }
+
+    fn store_register(&mut self, function: &IrFunction, register: Register, value: &str) {
+        let register_llvm_type = llvm_type(&function.registers[register].1);
+        let pointer = self.registers[register].clone();
+        self.emit(&format!("store {0} {1}, {0}* {2}", register_llvm_type, value, pointer));
+    }
+
+    fn generate_main(&mut self, run_count: usize) {
+        for run in 0..run_count {
+            self.emit(&format!("call void @run.{}()", run));
+        }
+
+        let entity_count = self.load("i64", "@dolang.entity_count");
+        let entity_format = self.string("%ld entities\n");
+        self.emit(&format!("call i32 (i32, i8*, ...) @dprintf(i32 1, i8* {}, i64 {})", entity_format, entity_count));
+
+        for table in &self.program.tables {
+            self.emit(&format!("call void {}()", symbol('@', &format!("print.{}", table.data_name))));
+        }
+
+        self.emit("ret i32 0");
+        self.end_function("define i32 @main()");
+    }
+
+    fn zero_value(&mut self, value_type: &IrType) -> String {
+        match value_type {
+            IrType::Float => float_constant(0.0),
+            IrType::Bool => "false".to_string(),
+            IrType::String => self.string(""),
+            _ => "0".to_string(),
+        }
+    }
+
+    /// The pointers to a field of an instance and, for an optional field, to the flag telling whether it is set.
+    fn field_pointers(&mut self, data_name: &str, field_name: &str, row: &str) -> (String, Option<String>) {
+        let fields = self.program.table(data_name).map_or(Vec::new(), |table| table.fields.clone());
+        let mut member = 0;
+
+        for field in fields {
+            if field.name == field_name {
+                let struct_type = symbol('%', data_name);
+                let instances = self.load(&format!("{}*", struct_type), &symbol('@', &format!("{}.instances", data_name)));
+                let pointer = |generator: &mut LlvmGenerator, member: usize| {
+                    generator.instruction(&format!(
+                        "getelementptr inbounds {0}, {0}* {1}, i64 {2}, i32 {3}",
+                        struct_type, instances, row, member
+                    ))
+                };
+
+                let value_pointer = pointer(self, member);
+                let flag_pointer = field.optional.then(|| pointer(self, member + 1));
+                return (value_pointer, flag_pointer);
+            }
+
+            member += if field.optional { 2 } else { 1 };
+        }
+
+        ("null".to_string(), None)
+    }
+
+    fn generate_push(&mut self, table: &IrTable) {
+        let name = &table.data_name;
+        let struct_type = symbol('%', name);
+        let length_global = symbol('@', &format!("{}.length", name));
+        let capacity_global = symbol('@', &format!("{}.capacity", name));
+        let entities_global = symbol('@', &format!("{}.entities", name));
+        let instances_global = symbol('@', &format!("{}.instances", name));
+
+        let mut parameters = vec!["i64 %entity".to_string()];
+
+        for field in &table.fields {
+            parameters.push(format!("{} {}", llvm_type(&field.field_type), symbol('%', &format!("field.{}", field.name))));
+
+            if field.optional {
+                parameters.push(format!("i1 {}", symbol('%', &format!("field.{}.is_set", field.name))));
+            }
+        }
+
+        let length = self.load("i64", &length_global);
+        let capacity = self.load("i64", &capacity_global);
+        let is_full = self.instruction(&format!("icmp eq i64 {}, {}", length, capacity));
+        let grow_label = self.label("grow");
+        let push_label = self.label("push");
+        self.emit(&format!("br i1 {}, label %{}, label %{}", is_full, grow_label, push_label));
+
+        self.start_block(&grow_label);
+        let doubled_capacity = self.instruction(&format!("mul i64 {}, 2", capacity));
+        let new_capacity = self.instruction(&format!("add i64 {}, 8", doubled_capacity));
+        self.emit(&format!("store i64 {}, i64* {}", new_capacity, capacity_global));
+
+        let entities = self.load("i64*", &entities_global);
+        let entity_bytes = self.instruction(&format!("bitcast i64* {} to i8*", entities));
+        let grown_entities = self.instruction(&format!("call i8* @dolang_grow(i8* {}, i64 {}, i64 8)", entity_bytes, new_capacity));
+        let new_entities = self.instruction(&format!("bitcast i8* {} to i64*", grown_entities));
+        self.emit(&format!("store i64* {}, i64** {}", new_entities, entities_global));
+
+        let instances = self.load(&format!("{}*", struct_type), &instances_global);
+        let instance_bytes = self.instruction(&format!("bitcast {}* {} to i8*", struct_type, instances));
+        let size_pointer = self.instruction(&format!("getelementptr {0}, {0}* null, i64 1", struct_type));
+        let size = self.instruction(&format!("ptrtoint {}* {} to i64", struct_type, size_pointer));
+        let grown_instances = self.instruction(&format!(
+            "call i8* @dolang_grow(i8* {}, i64 {}, i64 {})",
+            instance_bytes, new_capacity, size
+        ));
+        let new_instances = self.instruction(&format!("bitcast i8* {} to {}*", grown_instances, struct_type));
+        self.emit(&format!("store {0}* {1}, {0}** {2}", struct_type, new_instances, instances_global));
+        self.emit(&format!("br label %{}", push_label));
+
+        self.start_block(&push_label);
+        let new_length = self.instruction(&format!("add i64 {}, 1", length));
+        self.emit(&format!("store i64 {}, i64* {}", new_length, length_global));
+
+        let entities = self.load("i64*", &entities_global);
+        let entity_pointer = self.instruction(&format!("getelementptr inbounds i64, i64* {}, i64 {}", entities, length));
+        self.emit(&format!("store i64 %entity, i64* {}", entity_pointer));
+
+        for field in &table.fields {
+            let (value_pointer, flag_pointer) = self.field_pointers(name, &field.name, &length);
+            let argument = symbol('%', &format!("field.{}", field.name));
+            self.emit(&format!("store {0} {1}, {0}* {2}", llvm_type(&field.field_type), argument, value_pointer));
+
+            if let Some(flag_pointer) = flag_pointer {
+                let flag_argument = symbol('%', &format!("field.{}.is_set", field.name));
+                self.emit(&format!("store i1 {}, i1* {}", flag_argument, flag_pointer));
+            }
+        }
+
+        self.emit("ret void");
+        self.end_function(&format!(
+            "define internal void {}({})",
+            symbol('@', &format!("push.{}", name)),
+            parameters.join(", ")
+        ));
+    }
+
+    fn generate_print(&mut self, table: &IrTable) {
+        let name = &table.data_name;
+
+        let length = self.load("i64", &symbol('@', &format!("{}.length", name)));
+        let header = self.string(&format!("{} (%ld instances)\n", name));
+        self.emit(&format!("call i32 (i32, i8*, ...) @dprintf(i32 1, i8* {}, i64 {})", header, length));
+
+        let row_pointer = self.alloca("i64", "row");
+        self.emit(&format!("store i64 0, i64* {}", row_pointer));
+
+        let condition_label = self.label("print.condition");
+        let body_label = self.label("print.body");
+        let end_label = self.label("print.end");
+        self.emit(&format!("br label %{}", condition_label));
+
+        self.start_block(&condition_label);
+        let row = self.load("i64", &row_pointer);
+        let has_row = self.instruction(&format!("icmp ult i64 {}, {}", row, length));
+        self.emit(&format!("br i1 {}, label %{}, label %{}", has_row, body_label, end_label));
+
+        self.start_block(&body_label);
+        let entities = self.load("i64*", &symbol('@', &format!("{}.entities", name)));
+        let entity_pointer = self.instruction(&format!("getelementptr inbounds i64, i64* {}, i64 {}", entities, row));
+        let entity = self.load("i64", &entity_pointer);
+        let entity_format = self.string("  entity %ld: ");
+        self.emit(&format!("call i32 (i32, i8*, ...) @dprintf(i32 1, i8* {}, i64 {})", entity_format, entity));
+
+        for (index, field) in table.fields.iter().enumerate() {
+            let separator = if index == 0 { "" } else { ", " };
+            let field_label = self.string(&format!("{}{} = ", separator, field.name));
+            self.emit(&format!("call i32 (i32, i8*, ...) @dprintf(i32 1, i8* {})", field_label));
+
+            let (value_pointer, flag_pointer) = self.field_pointers(name, &field.name, &row);
+            let field_llvm_type = llvm_type(&field.field_type);
+
+            match flag_pointer {
+                Some(flag_pointer) => {
+                    let is_set = self.load("i1", &flag_pointer);
+                    let set_label = self.label("print.set");
+                    let unset_label = self.label("print.unset");
+                    let next_label = self.label("print.next");
+                    self.emit(&format!("br i1 {}, label %{}, label %{}", is_set, set_label, unset_label));
+
+                    self.start_block(&set_label);
+                    let value = self.load(field_llvm_type, &value_pointer);
+                    self.generate_print_value(&field.field_type, &value);
+                    self.emit(&format!("br label %{}", next_label));
+
+                    self.start_block(&unset_label);
+                    let none = self.string("none");
+                    self.emit(&format!("call i32 (i32, i8*, ...) @dprintf(i32 1, i8* {})", none));
+                    self.emit(&format!("br label %{}", next_label));
+
+                    self.start_block(&next_label);
+                },
+                None => {
+                    let value = self.load(field_llvm_type, &value_pointer);
+                    self.generate_print_value(&field.field_type, &value);
+                },
+            }
+        }
+
+        let newline = self.string("\n");
+        self.emit(&format!("call i32 (i32, i8*, ...) @dprintf(i32 1, i8* {})", newline));
+        let next_row = self.instruction(&format!("add i64 {}, 1", row));
+        self.emit(&format!("store i64 {}, i64* {}", next_row, row_pointer));
+        self.emit(&format!("br label %{}", condition_label));
+
+        self.start_block(&end_label);
+        self.emit("ret void");
+        self.end_function(&format!("define internal void {}()", symbol('@', &format!("print.{}", name))));
+    }
+
+    fn generate_print_value(&mut self, value_type: &IrType, value: &str) {
+        let (format, argument) = match value_type {
+            IrType::Int | IrType::Entity => ("%ld".to_string(), format!("i64 {}", value)),
+            IrType::Float => return self.emit(&format!("call void @dolang_print_float(i32 1, double {})", value)),
+            IrType::Bool => {
+                let true_string = self.string("true");
+                let false_string = self.string("false");
+                let text = self.instruction(&format!("select i1 {}, i8* {}, i8* {}", value, true_string, false_string));
+                ("%s".to_string(), format!("i8* {}", text))
+            },
+            IrType::String => ("\"%s\"".to_string(), format!("i8* {}", value)),
+            IrType::Enum(enum_name) => {
+                let count = self.program.variants(enum_name).len();
+                let name_pointer = self.instruction(&format!(
+                    "getelementptr inbounds [{0} x i8*], [{0} x i8*]* {1}, i64 0, i64 {2}",
+                    count,
+                    symbol('@', &format!("{}.names", enum_name)),
+                    value
+                ));
+                let text = self.load("i8*", &name_pointer);
+                ("%s".to_string(), format!("i8* {}", text))
+            },
+            IrType::Instance(data_name) => (format!("{}#%ld", data_name), format!("i64 {}", value)),
+        };
+
+        let format = self.string(&format);
+        self.emit(&format!("call i32 (i32, i8*, ...) @dprintf(i32 1, i8* {}, {})", format, argument));
+    }
+
+    /// Integer arithmetic stops with the error run reports when it overflows or divides by zero.
+    fn int_arithmetic(&mut self, operator: &str, lhs: &str, rhs: &str) -> String {
+        if let Some(intrinsic) = match operator {
+            "+" => Some("sadd"),
+            "-" => Some("ssub"),
+            "*" => Some("smul"),
+            _ => None,
+        } {
+            let checked = self.instruction(&format!("call {{ i64, i1 }} @llvm.{}.with.overflow.i64(i64 {}, i64 {})", intrinsic, lhs, rhs));
+            let result = self.instruction(&format!("extractvalue {{ i64, i1 }} {}, 0", checked));
+            let overflows = self.instruction(&format!("extractvalue {{ i64, i1 }} {}, 1", checked));
+            self.fail_if(&overflows, OVERFLOW_MESSAGE, operator, lhs, rhs);
+            return result;
+        }
+
+        let is_zero = self.instruction(&format!("icmp eq i64 {}, 0", rhs));
+        self.fail_if(&is_zero, DIVISION_BY_ZERO_MESSAGE, operator, lhs, rhs);
+
+        let is_minimum = self.instruction(&format!("icmp eq i64 {}, {}", lhs, i64::MIN));
+        let is_minus_one = self.instruction(&format!("icmp eq i64 {}, -1", rhs));
+        let overflows = self.instruction(&format!("and i1 {}, {}", is_minimum, is_minus_one));
+        self.fail_if(&overflows, OVERFLOW_MESSAGE, operator, lhs, rhs);
+
+        let instruction = if operator == "/" { "sdiv" } else { "srem" };
+        self.instruction(&format!("{} i64 {}, {}", instruction, lhs, rhs))
+    }
+
+    fn fail_if(&mut self, condition: &str, message: &str, operator: &str, lhs: &str, rhs: &str) {
+        let fail_label = self.label("arithmetic.fail");
+        let next_label = self.label("arithmetic.next");
+        self.emit(&format!("br i1 {}, label %{}, label %{}", condition, fail_label, next_label));
+
+        self.start_block(&fail_label);
+        let context = self.string(&self.context.clone());
+        let message = self.string(message);
+        let operator = self.string(operator);
+        self.emit(&format!(
+            "call void @dolang_fail_int(i8* {}, i8* {}, i64 {}, i8* {}, i64 {})",
+            context, message, lhs, operator, rhs
+        ));
+        self.emit("unreachable");
+
+        self.start_block(&next_label);
+    }
+
+    fn float_arithmetic(&mut self, operator: &str, lhs: &str, rhs: &str) -> String {
+        let instruction = match operator {
+            "+" => "fadd",
+            "-" => "fsub",
+            "*" => "fmul",
+            "/" => "fdiv",
+            _ => "frem",
+        };
+
+        if instruction == "fdiv" || instruction == "frem" {
+            let is_zero = self.instruction(&format!("fcmp oeq double {}, 0.0", rhs));
+            let fail_label = self.label("arithmetic.fail");
+            let next_label = self.label("arithmetic.next");
+            self.emit(&format!("br i1 {}, label %{}, label %{}", is_zero, fail_label, next_label));
+
+            self.start_block(&fail_label);
+            let context = self.string(&self.context.clone());
+            let operator = self.string(operator);
+            self.emit(&format!(
+                "call void @dolang_fail_float_division(i8* {}, double {}, i8* {}, double {})",
+                context, lhs, operator, rhs
+            ));
+            self.emit("unreachable");
+
+            self.start_block(&next_label);
+        }
+
+        self.instruction(&format!("{} double {}, {}", instruction, lhs, rhs))
+    }
+
+    fn element_pointer(&mut self, array_type: &str, array: &str, index: usize) -> String {
+        self.instruction(&format!("getelementptr inbounds {0}, {0}* {1}, i64 0, i64 {2}", array_type, array, index))
+    }
+
+    fn alloca(&mut self, llvm_type: &str, name: &str) -> String {
+        self.register_count += 1;
+        let pointer = symbol('%', &format!("{}.{}", name, self.register_count));
+        self.entry.push_str(&format!("  {} = alloca {}\n", pointer, llvm_type));
+        pointer
+    }
+
+    fn load(&mut self, llvm_type: &str, pointer: &str) -> String {
+        self.instruction(&format!("load {0}, {0}* {1}", llvm_type, pointer))
+    }
+
+    /// Emits an instruction that gives a value, and returns the register holding it.
+    fn instruction(&mut self, instruction: &str) -> String {
+        self.register_count += 1;
+        let register = format!("%t{}", self.register_count);
+        self.emit(&format!("{} = {}", register, instruction));
+        register
+    }
+
+    fn label(&mut self, name: &str) -> String {
+        self.register_count += 1;
+        format!("{}.{}", name, self.register_count)
+    }
+
+    fn start_block(&mut self, label: &str) {
+        self.body.push_str(&format!("{}:\n", label));
+    }
+
+    fn emit(&mut self, instruction: &str) {
+        self.body.push_str(&format!("  {}\n", instruction));
+    }
+
+    /// Writes the function with its allocas first, then starts the next one.
+    fn end_function(&mut self, signature: &str) {
+        self.functions.push_str(&format!("\n{} {{\nentry:\n{}{}}}\n", signature, self.entry, self.body));
+        self.entry.clear();
+        self.body.clear();
+    }
+
+    /// Gives a pointer to the first character of a constant string, declared once per text.
+    fn string(&mut self, text: &str) -> String {
+        let length = text.len() + 1;
+
+        let global = match self.strings.get(text) {
+            Some(global) => global.clone(),
+            None => {
+                let global = format!("@string.{}", self.strings.len());
+                self.globals.push_str(&format!(
+                    "{} = private unnamed_addr constant [{} x i8] c\"{}\\00\"\n",
+                    global,
+                    length,
+                    escape(text)
+                ));
+                self.strings.insert(text.to_string(), global.clone());
+                global
+            },
+        };
+
+        format!("getelementptr inbounds ([{0} x i8], [{0} x i8]* {1}, i64 0, i64 0)", length, global)
+    }
+}
+
+fn llvm_type(value_type: &IrType) -> &'static str {
+    match value_type {
+        IrType::Int | IrType::Enum(_) | IrType::Instance(_) | IrType::Entity => "i64",
+        IrType::Float => "double",
+        IrType::Bool => "i1",
+        IrType::String => "i8*",
+    }
+}
+
+/// A global or local name, quoted when it has characters LLVM does not allow unquoted, as in %"physics::Position".
+fn symbol(prefix: char, name: &str) -> String {
+    match name.chars().all(|character| character.is_ascii_alphanumeric() || "._".contains(character)) {
+        true  => format!("{}{}", prefix, name),
+        false => format!("{}\"{}\"", prefix, escape(name)),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b' '..=b'~' if byte != b'"' && byte != b'\\' => (byte as char).to_string(),
+            _ => format!("\\{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Floats are written by their bits, as LLVM only takes decimal constants that are exact.
+fn float_constant(float: f64) -> String {
+    format!("0x{:016X}", float.to_bits())
+}
@@ -1,16 +1,25 @@
+use crate::token::Location;
+
 pub struct LookAheadIterator<T: Clone> {
     data: Vec<T>,
+    locations: Vec<Location>,
     index: usize,
 }
 
 impl<T: Clone> LookAheadIterator<T> {
-    pub fn new(data: Vec<T>) -> Self {
+    pub fn new(data: Vec<T>, locations: Vec<Location>) -> Self {
         LookAheadIterator {
-            data: data,
+            data,
+            locations,
             index: 0,
         }
     }
 
+    /// Where the next item starts in the source. There must be a next item.
+    pub fn location(&self) -> Location {
+        self.locations[self.index]
+    }
+
     pub fn peek(&mut self) -> Option<&T> {
         self.lookahead(0)
     }
@@ -37,8 +46,9 @@ pub trait ToLookaheadIterator<T: Clone> {
     fn to_lookahead_iter(self) -> LookAheadIterator<T>;
 }
 
-impl<T: Clone> ToLookaheadIterator<T> for Vec<T> {
+impl<T: Clone> ToLookaheadIterator<T> for Vec<(T, Location)> {
     fn to_lookahead_iter(self) -> LookAheadIterator<T> {
-        LookAheadIterator::new(self)
+        let (data, locations) = self.into_iter().unzip();
+        LookAheadIterator::new(data, locations)
     }
 }
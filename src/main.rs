@@ -1,3 +1,6 @@
+#![allow(clippy::vec_box)]
+
+mod cli;
 mod lookahead_iterator;
 
 mod token;
@@ -6,19 +9,152 @@ mod tokenizer;
 mod ast;
 mod ast_parser;
 
+mod diagnostic;
+mod module_loader;
+mod declaration_collector;
+mod resolver;
+mod type_checker;
+mod group_validator;
+mod match_checker;
+mod constant_folder;
+mod access_analysis;
+mod scheduler;
+mod runtime;
+mod interpreter;
+mod bytecode;
+mod bytecode_compiler;
+mod vm;
+mod ir;
+mod ir_builder;
+mod ir_validator;
+mod dead_code_eliminator;
+mod c_backend;
+mod llvm_backend;
+mod wasm_backend;
+mod rust_backend;
+mod linter;
+mod lints;
+
 use std::env;
 use std::fs;
+use std::path::Path;
+use std::process;
 
-use crate::tokenizer::tokenize;
-use crate::ast_parser::parse_ast;
+use crate::cli::{parse_options, Command, Emit, Engine, Target};
+use crate::diagnostic::{has_errors, Diagnostic};
+use crate::module_loader::load_program;
+use crate::declaration_collector::collect_declarations;
+use crate::resolver::resolve_names;
+use crate::type_checker::check_types;
+use crate::group_validator::validate_groups;
+use crate::match_checker::check_match_exhaustiveness;
+use crate::access_analysis::check_parallel_loops;
+use crate::linter::run_lints;
+use crate::constant_folder::fold_constants;
+use crate::interpreter::interpret;
+use crate::bytecode_compiler::compile_program;
+use crate::vm::run_bytecode;
+use crate::ir::IrProgram;
+use crate::ir_builder::lower_program;
+use crate::ir_validator::validate_ir;
+use crate::dead_code_eliminator::eliminate_dead_code;
+use crate::c_backend::generate_c;
+use crate::llvm_backend::generate_llvm;
+use crate::wasm_backend::generate_wasm;
+use crate::rust_backend::generate_rust;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let source_code = fs::read_to_string(args.get(1).unwrap()).unwrap();
-    
-    let tokens = tokenize(source_code);
-    println!("Tokens:\n{:#?}", tokens);
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(diagnostic) => {
+            report(&[diagnostic]);
+            return;
+        },
+    };
+
+    let (mut ast, module_files, diagnostics) = load_program(Path::new(&options.source_path));
+    report(&diagnostics);
+
+    report(&collect_declarations(&ast, &module_files));
+    report(&resolve_names(&ast));
+    report(&check_types(&ast));
+    report(&validate_groups(&ast));
+
+    report(&check_match_exhaustiveness(&ast));
+    report(&check_parallel_loops(&ast));
+    report(&run_lints(&ast, &options.lint_levels));
+    report(&fold_constants(&mut ast));
+
+    let emit = match options.command {
+        Command::Check => options.emit.or(Some(Emit::Ast)),
+        Command::Run | Command::Build => options.emit,
+    };
+
+    let ir = (emit == Some(Emit::Ir) || options.command == Command::Build).then(|| build_ir(&ast, options.verbose));
+
+    match (emit, &ir) {
+        (Some(Emit::Ast), _) => println!("Abstract syntax tree:\n{:#?}", ast),
+        (Some(Emit::Bytecode), _) => print!("{}", compile_program(&ast)),
+        (Some(Emit::Ir), Some(program)) => print!("{}", program),
+        _ => (),
+    }
+
+    if options.command == Command::Run {
+        let result = match options.engine {
+            Engine::Bytecode => run_bytecode(&compile_program(&ast), options.execution_mode),
+            Engine::TreeWalking => interpret(&ast, options.execution_mode),
+        };
+
+        match result {
+            Ok(world) => print!("{}", world),
+            Err(diagnostic) => report(&[diagnostic]),
+        }
+    }
+
+    if let (Command::Build, Some(program)) = (options.command, &ir) {
+        let code = match options.target {
+            Target::C => generate_c(program),
+            Target::Llvm => generate_llvm(program),
+            Target::Wasm => generate_wasm(program),
+            Target::Rust => generate_rust(program),
+        };
+
+        let output_path = match &options.output_path {
+            Some(output_path) => output_path.into(),
+            None => Path::new(&options.source_path).with_extension(options.target.extension()),
+        };
+
+        if let Err(error) = fs::write(&output_path, code) {
+            report(&[Diagnostic::error(format!("Cannot write {}: {}.", output_path.display(), error))]);
+        }
+    }
+}
+
+/// Lowers the program to the representation build generates code from, stopping on what it cannot lower, then
+/// leaves out what no run block reaches, listing it when verbose. The program is validated again once pruned, so
+/// nothing left behind refers to a function or table that was removed.
+fn build_ir(ast: &ast::ASTNode, verbose: bool) -> IrProgram {
+    let (mut program, diagnostics) = lower_program(ast);
+    report(&diagnostics);
+    report(&validate_ir(&program));
+
+    for removed in eliminate_dead_code(&mut program) {
+        if verbose {
+            eprintln!("Removed {} as no run block reaches it.", removed);
+        }
+    }
+
+    report(&validate_ir(&program));
+    program
+}
+
+fn report(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic);
+    }
 
-    let ast = parse_ast(tokens);
-    println!("Abstract syntax tree:\n{:#?}", ast);
+    if has_errors(diagnostics) {
+        process::exit(1);
+    }
 }
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+
+use crate::ast::{ASTNode, TypeExpr};
+use crate::diagnostic::Diagnostic;
+
+#[derive(Clone)]
+enum Pattern {
+    Wildcard,
+    Constructor(String, Vec<Pattern>),
+    Literal,
+}
+
+struct Declarations {
+    enum_variants: HashMap<String, Vec<String>>,
+    variants: HashMap<String, (String, Vec<TypeExpr>)>,
+    data_fields: HashMap<String, Vec<(String, TypeExpr)>>,
+}
+
+pub fn check_match_exhaustiveness(program: &ASTNode) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    if let ASTNode::Program(statements) = program {
+        let declarations = collect_declarations(statements);
+
+        for statement in statements {
+            match statement.as_ref() {
+                ASTNode::Do(name, instructions, _) => {
+                    check_instructions(instructions, &declarations, &format!("do {}", name), &mut diagnostics);
+                },
+                ASTNode::Run(actions_to_do, instructions) => {
+                    let context = format!("run ({})", actions_to_do.join(", "));
+                    check_instructions(instructions, &declarations, &context, &mut diagnostics);
+                },
+                _ => (),
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn collect_declarations(statements: &[Box<ASTNode>]) -> Declarations {
+    let mut declarations = Declarations {
+        enum_variants: HashMap::new(),
+        variants: HashMap::new(),
+        data_fields: HashMap::new(),
+    };
+
+    for statement in statements {
+        match statement.as_ref() {
+            ASTNode::Enum(enum_name, variants, _) => {
+                let mut variant_names = Vec::new();
+
+                for variant in variants {
+                    if let ASTNode::Variant(variant_name, fields, _) = variant.as_ref() {
+                        let field_types = fields.iter().filter_map(|field| field_of(field)).map(|(_, field_type)| field_type).collect();
+                        declarations.variants.insert(variant_name.clone(), (enum_name.clone(), field_types));
+                        variant_names.push(variant_name.clone());
+                    }
+                }
+
+                declarations.enum_variants.insert(enum_name.clone(), variant_names);
+            },
+            ASTNode::Data(data_name, fields, _) => {
+                let fields = fields.iter().filter_map(|field| field_of(field)).collect();
+                declarations.data_fields.insert(data_name.clone(), fields);
+            },
+            _ => (),
+        }
+    }
+
+    declarations
+}
+
+fn field_of(field: &ASTNode) -> Option<(String, TypeExpr)> {
+    match field {
+        ASTNode::Field(name, field_type, _, _) => Some((name.clone(), field_type.clone())),
+        _ => None,
+    }
+}
+
+fn check_instructions(instructions: &[Box<ASTNode>], declarations: &Declarations, context: &str, diagnostics: &mut Vec<Diagnostic>) {
+    for instruction in instructions {
+        match instruction.as_ref() {
+            ASTNode::If(_, body) | ASTNode::Foreach(_, _, body, _) | ASTNode::For(_, _, _, body) => {
+                check_instructions(body, declarations, context, diagnostics);
+            },
+            ASTNode::Match(_, arms) => {
+                check_match(arms, declarations, context, diagnostics);
+
+                for arm in arms {
+                    if let ASTNode::MatchArm(_, body) = arm.as_ref() {
+                        check_instructions(body, declarations, context, diagnostics);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+fn check_match(arms: &[Box<ASTNode>], declarations: &Declarations, context: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let error_count = diagnostics.len();
+    let mut rows: Vec<Vec<Pattern>> = Vec::new();
+
+    for arm in arms {
+        if let ASTNode::MatchArm(pattern, _) = arm.as_ref() {
+            rows.push(vec![lower_pattern(pattern, declarations, context, diagnostics)]);
+        }
+    }
+
+    if diagnostics.len() > error_count {
+        return;
+    }
+
+    let matched_type = match rows.iter().find_map(|row| first_constructor(&row[0])) {
+        Some(constructor) => type_of_constructor(constructor, declarations),
+        None => TypeExpr::Named("_".to_string()),
+    };
+
+    for row in &rows {
+        if let Some(constructor) = first_constructor(&row[0]) {
+            if type_of_constructor(constructor, declarations) != matched_type {
+                diagnostics.push(Diagnostic::error(format!(
+                    "In {}: pattern {} does not match the type {} of the other arms.",
+                    context,
+                    constructor,
+                    matched_type
+                )));
+                return;
+            }
+        }
+    }
+
+    let missing_arms: Vec<String> = match constructors_of(&matched_type, declarations) {
+        Some(constructors) => constructors
+            .into_iter()
+            .filter(|(name, field_types)| {
+                let specialized_rows = specialize(&rows, name, field_types.len());
+                !is_exhaustive(&specialized_rows, field_types, declarations)
+            })
+            .map(|(name, field_types)| match field_types.is_empty() {
+                true  => name,
+                false if declarations.data_fields.contains_key(&name) => format!("{} {{ .. }}", name),
+                false => format!("{}(..)", name),
+            })
+            .collect(),
+        None => match is_exhaustive(&default_rows(&rows), &[], declarations) {
+            true  => Vec::new(),
+            false => vec!["_".to_string()],
+        },
+    };
+
+    if !missing_arms.is_empty() {
+        let matched_type = match constructors_of(&matched_type, declarations) {
+            Some(_) => format!(" on {}", matched_type),
+            None => "".to_string(),
+        };
+
+        diagnostics.push(Diagnostic::error(format!(
+            "In {}: non-exhaustive match{}, missing arms for {}.",
+            context,
+            matched_type,
+            missing_arms.join(", ")
+        )));
+    }
+}
+
+fn lower_pattern(pattern: &ASTNode, declarations: &Declarations, context: &str, diagnostics: &mut Vec<Diagnostic>) -> Pattern {
+    match pattern {
+        ASTNode::WildcardPattern | ASTNode::BindingPattern(_) => Pattern::Wildcard,
+        ASTNode::LiteralPattern(literal) if literal == "true" || literal == "false" => {
+            Pattern::Constructor(literal.clone(), Vec::new())
+        },
+        ASTNode::LiteralPattern(_) => Pattern::Literal,
+        ASTNode::VariantPattern(name, field_patterns) => {
+            let field_count = match (declarations.variants.get(name), declarations.data_fields.get(name)) {
+                (Some((_, field_types)), _) => field_types.len(),
+                (None, Some(fields)) if field_patterns.is_empty() => fields.len(),
+                _ => {
+                    diagnostics.push(Diagnostic::error(format!("In {}: unknown enum variant {} in pattern.", context, name)));
+                    return Pattern::Wildcard;
+                },
+            };
+
+            if field_patterns.is_empty() {
+                return Pattern::Constructor(name.clone(), vec![Pattern::Wildcard; field_count]);
+            }
+
+            if field_patterns.len() != field_count {
+                diagnostics.push(Diagnostic::error(format!(
+                    "In {}: variant {} has {} field(s) but its pattern has {}.",
+                    context,
+                    name,
+                    field_count,
+                    field_patterns.len()
+                )));
+                return Pattern::Wildcard;
+            }
+
+            let field_patterns = field_patterns
+                .iter()
+                .map(|field_pattern| lower_pattern(field_pattern, declarations, context, diagnostics))
+                .collect();
+
+            Pattern::Constructor(name.clone(), field_patterns)
+        },
+        ASTNode::DataPattern(name, field_patterns) => {
+            let fields = match declarations.data_fields.get(name) {
+                Some(fields) => fields,
+                None => {
+                    diagnostics.push(Diagnostic::error(format!("In {}: unknown data {} in pattern.", context, name)));
+                    return Pattern::Wildcard;
+                },
+            };
+
+            let mut lowered_fields = vec![Pattern::Wildcard; fields.len()];
+
+            for field_pattern in field_patterns {
+                if let ASTNode::FieldPattern(field_name, sub_pattern) = field_pattern.as_ref() {
+                    match fields.iter().position(|(name, _)| name == field_name) {
+                        Some(index) => lowered_fields[index] = lower_pattern(sub_pattern, declarations, context, diagnostics),
+                        None => diagnostics.push(Diagnostic::error(format!(
+                            "In {}: data {} has no field named {}.",
+                            context,
+                            name,
+                            field_name
+                        ))),
+                    }
+                }
+            }
+
+            Pattern::Constructor(name.clone(), lowered_fields)
+        },
+        _ => Pattern::Wildcard,
+    }
+}
+
+fn first_constructor(pattern: &Pattern) -> Option<&String> {
+    match pattern {
+        Pattern::Constructor(name, _) => Some(name),
+        _ => None,
+    }
+}
+
+fn type_of_constructor(constructor: &str, declarations: &Declarations) -> TypeExpr {
+    if constructor == "true" || constructor == "false" {
+        return TypeExpr::Named("bool".to_string());
+    }
+
+    match declarations.variants.get(constructor) {
+        Some((enum_name, _)) => TypeExpr::Named(enum_name.clone()),
+        None => TypeExpr::Named(constructor.to_string()),
+    }
+}
+
+/// Lists every constructor of a type with the types of its fields, or None when the type has too many values to enumerate.
+fn constructors_of(matched_type: &TypeExpr, declarations: &Declarations) -> Option<Vec<(String, Vec<TypeExpr>)>> {
+    let type_name = match matched_type {
+        TypeExpr::Named(type_name) => type_name,
+        _ => return None,
+    };
+
+    if type_name == "bool" {
+        return Some(vec![("true".to_string(), Vec::new()), ("false".to_string(), Vec::new())]);
+    }
+
+    if let Some(variant_names) = declarations.enum_variants.get(type_name) {
+        return Some(
+            variant_names
+                .iter()
+                .map(|variant_name| (variant_name.clone(), declarations.variants[variant_name].1.clone()))
+                .collect()
+        );
+    }
+
+    declarations.data_fields.get(type_name).map(|fields| {
+        vec![(type_name.clone(), fields.iter().map(|(_, field_type)| field_type.clone()).collect())]
+    })
+}
+
+fn is_exhaustive(rows: &[Vec<Pattern>], column_types: &[TypeExpr], declarations: &Declarations) -> bool {
+    if column_types.is_empty() {
+        return !rows.is_empty();
+    }
+
+    let only_wildcards = rows.iter().all(|row| matches!(row[0], Pattern::Wildcard));
+
+    match constructors_of(&column_types[0], declarations) {
+        Some(constructors) if !only_wildcards => constructors.iter().all(|(name, field_types)| {
+            let specialized_rows = specialize(rows, name, field_types.len());
+            let specialized_types: Vec<TypeExpr> = field_types.iter().chain(&column_types[1..]).cloned().collect();
+            is_exhaustive(&specialized_rows, &specialized_types, declarations)
+        }),
+        _ => is_exhaustive(&default_rows(rows), &column_types[1..], declarations),
+    }
+}
+
+/// Keeps the rows that match the constructor, replacing their first pattern by the patterns of its fields.
+fn specialize(rows: &[Vec<Pattern>], constructor: &str, field_count: usize) -> Vec<Vec<Pattern>> {
+    rows.iter()
+        .filter_map(|row| {
+            let field_patterns = match &row[0] {
+                Pattern::Wildcard => vec![Pattern::Wildcard; field_count],
+                Pattern::Constructor(name, field_patterns) if name == constructor => field_patterns.clone(),
+                _ => return None,
+            };
+
+            Some(field_patterns.into_iter().chain(row[1..].iter().cloned()).collect())
+        })
+        .collect()
+}
+
+fn default_rows(rows: &[Vec<Pattern>]) -> Vec<Vec<Pattern>> {
+    rows.iter()
+        .filter(|row| matches!(row[0], Pattern::Wildcard))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
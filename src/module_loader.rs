@@ -0,0 +1,321 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::ast::{ASTNode, TypeExpr};
+use crate::ast_parser::parse_ast;
+use crate::diagnostic::Diagnostic;
+use crate::tokenizer::tokenize;
+
+/// Loads a file and every file it imports into a single program.
+/// Declarations of an imported file are renamed after its module, so Position in physics.do becomes physics::Position.
+/// Also returns the file of each imported module, by module name.
+pub fn load_program(path: &Path) -> (ASTNode, HashMap<String, PathBuf>, Vec<Diagnostic>) {
+    let mut loader = ModuleLoader {
+        loaded_files: HashSet::new(),
+        module_files: HashMap::new(),
+        import_stack: Vec::new(),
+        statements: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    loader.load_module(path, None);
+
+    (ASTNode::new_program(loader.statements), loader.module_files, loader.diagnostics)
+}
+
+struct ModuleLoader {
+    loaded_files: HashSet<PathBuf>,
+    module_files: HashMap<String, PathBuf>,
+    import_stack: Vec<PathBuf>,
+    statements: Vec<ASTNode>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+struct ModuleNames {
+    module_name: Option<String>,
+    declared_names: HashSet<String>,
+    used_names: HashMap<String, String>,
+}
+
+impl ModuleLoader {
+    fn load_module(&mut self, path: &Path, module_name: Option<String>) {
+        let file = match fs::canonicalize(path) {
+            Ok(file) => file,
+            Err(error) => {
+                self.diagnostics.push(Diagnostic::error(format!("Could not find {}: {}.", path.display(), error)));
+                return;
+            },
+        };
+
+        if let Some(cycle_start) = self.import_stack.iter().position(|importing_file| importing_file == &file) {
+            let cycle: Vec<String> = self.import_stack[cycle_start..]
+                .iter()
+                .chain([&file])
+                .map(|importing_file| importing_file.display().to_string())
+                .collect();
+
+            self.diagnostics.push(Diagnostic::error(format!("Import cycle detected: {}.", cycle.join(" -> "))));
+            return;
+        }
+
+        if let Some(module_name) = &module_name {
+            match self.module_files.get(module_name) {
+                Some(module_file) if module_file != &file => {
+                    self.diagnostics.push(Diagnostic::error(format!(
+                        "Module name {} is used by both {} and {}.",
+                        module_name,
+                        module_file.display(),
+                        file.display()
+                    )));
+                    return;
+                },
+                _ => self.module_files.insert(module_name.clone(), file.clone()),
+            };
+        }
+
+        if self.loaded_files.contains(&file) {
+            return;
+        }
+
+        let source_code = match fs::read_to_string(&file) {
+            Ok(source_code) => source_code,
+            Err(error) => {
+                self.diagnostics.push(Diagnostic::error(format!("Could not read {}: {}.", file.display(), error)));
+                return;
+            },
+        };
+
+        let statements = match parse_ast(tokenize(source_code)) {
+            ASTNode::Program(statements) => statements,
+            _ => unreachable!(),
+        };
+
+        self.import_stack.push(file.clone());
+
+        let directory = file.parent().unwrap().to_path_buf();
+        let mut imported_modules: HashSet<String> = HashSet::new();
+
+        for statement in &statements {
+            if let ASTNode::Import(import_path) = statement.as_ref() {
+                let import_path = directory.join(import_path);
+                let imported_module = match import_path.file_stem() {
+                    Some(stem) => stem.to_string_lossy().to_string(),
+                    None => {
+                        self.diagnostics.push(Diagnostic::error(format!("Invalid import path {}.", import_path.display())));
+                        continue;
+                    },
+                };
+
+                self.load_module(&import_path, Some(imported_module.clone()));
+                imported_modules.insert(imported_module);
+            }
+        }
+
+        self.import_stack.pop();
+        self.loaded_files.insert(file.clone());
+
+        let names = self.collect_module_names(&statements, module_name, &imported_modules, &file);
+
+        for mut statement in statements {
+            match statement.as_mut() {
+                ASTNode::Import(_) | ASTNode::Use(_) => continue,
+                ASTNode::Run(..) if names.module_name.is_some() => {
+                    self.diagnostics.push(Diagnostic::error(format!(
+                        "Imported file {} cannot contain run blocks.",
+                        file.display()
+                    )));
+                    continue;
+                },
+                node => qualify_names(node, &names),
+            }
+
+            self.statements.push(*statement);
+        }
+    }
+
+    fn collect_module_names(
+        &mut self,
+        statements: &[Box<ASTNode>],
+        module_name: Option<String>,
+        imported_modules: &HashSet<String>,
+        file: &Path
+    ) -> ModuleNames {
+        let mut names = ModuleNames {
+            module_name,
+            declared_names: HashSet::new(),
+            used_names: HashMap::new(),
+        };
+
+        for statement in statements {
+            match statement.as_ref() {
+                ASTNode::Data(name, _, _) | ASTNode::Group(name, _, _, _) | ASTNode::Do(name, _, _) => {
+                    names.declared_names.insert(name.clone());
+                },
+                ASTNode::Enum(name, variants, _) => {
+                    names.declared_names.insert(name.clone());
+
+                    for variant in variants {
+                        if let ASTNode::Variant(variant_name, _, _) = variant.as_ref() {
+                            names.declared_names.insert(variant_name.clone());
+                        }
+                    }
+                },
+                ASTNode::Use(used_name) => {
+                    let used_module = used_name.split("::").next().unwrap();
+
+                    if !imported_modules.contains(used_module) {
+                        self.diagnostics.push(Diagnostic::error(format!(
+                            "In {}: use {} refers to module {} which is not imported.",
+                            file.display(),
+                            used_name,
+                            used_module
+                        )));
+                        continue;
+                    }
+
+                    let alias = used_name.rsplit("::").next().unwrap().to_string();
+                    names.used_names.insert(alias, used_name.clone());
+                },
+                _ => (),
+            }
+        }
+
+        names
+    }
+}
+
+impl ModuleNames {
+    fn qualify(&self, name: &str) -> String {
+        if name.contains("::") {
+            return name.to_string();
+        }
+
+        if let Some(used_name) = self.used_names.get(name) {
+            return used_name.clone();
+        }
+
+        match &self.module_name {
+            Some(module_name) if self.declared_names.contains(name) => format!("{}::{}", module_name, name),
+            _ => name.to_string(),
+        }
+    }
+
+    /// Qualifies the head of a value such as Position or p.x, leaving literals untouched.
+    fn qualify_value(&self, value: &str) -> String {
+        if value.starts_with('"') {
+            return value.to_string();
+        }
+
+        match value.split_once('.') {
+            Some((head, rest)) => format!("{}.{}", self.qualify(head), rest),
+            None => self.qualify(value),
+        }
+    }
+}
+
+fn qualify_names(node: &mut ASTNode, names: &ModuleNames) {
+    match node {
+        ASTNode::Data(name, children, _) | ASTNode::Do(name, children, _) | ASTNode::Enum(name, children, _) => {
+            *name = names.qualify(name);
+            qualify_all(children, names);
+        },
+        ASTNode::Group(name, parameters, data_instanciations, _) => {
+            *name = names.qualify(name);
+            qualify_all(parameters, names);
+            qualify_all(data_instanciations, names);
+        },
+        ASTNode::Run(actions_to_do, instructions) => {
+            for action_to_do in actions_to_do.iter_mut() {
+                *action_to_do = names.qualify(action_to_do);
+            }
+            qualify_all(instructions, names);
+        },
+        ASTNode::Variant(name, fields, _) | ASTNode::VariantPattern(name, fields) | ASTNode::DataPattern(name, fields) => {
+            *name = names.qualify(name);
+            qualify_all(fields, names);
+        },
+        ASTNode::DataInstanciation(name, children)
+        | ASTNode::CreateInstruction(name, children)
+        | ASTNode::VariantConstructor(name, children) => {
+            *name = names.qualify(name);
+            qualify_all(children, names);
+        },
+        ASTNode::Parameter(_, node_type, _) => qualify_type(node_type, names),
+        ASTNode::Field(_, node_type, default_value, _) => {
+            qualify_type(node_type, names);
+
+            if let Some(default_value) = default_value {
+                qualify_names(default_value, names);
+            }
+        },
+        ASTNode::FieldValue(_, value) | ASTNode::Value(value) => *value = names.qualify_value(value),
+        ASTNode::Assignment(target, child) => {
+            *target = names.qualify_value(target);
+            qualify_names(child, names);
+        },
+        ASTNode::Declaration(_, child)
+        | ASTNode::NamedArgument(_, child)
+        | ASTNode::FieldPattern(_, child) => qualify_names(child, names),
+        ASTNode::Sum(lhs, rhs)
+        | ASTNode::Substraction(lhs, rhs)
+        | ASTNode::Multiplication(lhs, rhs)
+        | ASTNode::Division(lhs, rhs, _)
+        | ASTNode::Modulo(lhs, rhs, _)
+        | ASTNode::Equality(lhs, rhs)
+        | ASTNode::Inequality(lhs, rhs)
+        | ASTNode::GreaterThan(lhs, rhs)
+        | ASTNode::LessThan(lhs, rhs)
+        | ASTNode::GreaterOrEqual(lhs, rhs)
+        | ASTNode::LessOrEqual(lhs, rhs) => {
+            qualify_names(lhs, names);
+            qualify_names(rhs, names);
+        },
+        ASTNode::If(condition, instructions) => {
+            qualify_names(condition, names);
+            qualify_all(instructions, names);
+        },
+        ASTNode::Foreach(_, collections, instructions, _) => {
+            qualify_all(collections, names);
+            qualify_all(instructions, names);
+        },
+        ASTNode::For(declaration, condition, progression, instructions) => {
+            qualify_names(declaration, names);
+            qualify_names(condition, names);
+            qualify_names(progression, names);
+            qualify_all(instructions, names);
+        },
+        ASTNode::Match(value, arms) | ASTNode::MatchArm(value, arms) => {
+            qualify_names(value, names);
+            qualify_all(arms, names);
+        },
+        ASTNode::Program(statements) | ASTNode::ListLiteral(statements) => qualify_all(statements, names),
+        ASTNode::Import(_)
+        | ASTNode::Use(_)
+        | ASTNode::Attribute(_, _)
+        | ASTNode::WildcardPattern
+        | ASTNode::LiteralPattern(_)
+        | ASTNode::BindingPattern(_) => (),
+    }
+}
+
+fn qualify_all(nodes: &mut [Box<ASTNode>], names: &ModuleNames) {
+    for node in nodes {
+        qualify_names(node, names);
+    }
+}
+
+fn qualify_type(type_expr: &mut TypeExpr, names: &ModuleNames) {
+    match type_expr {
+        TypeExpr::Named(name) => *name = names.qualify(name),
+        TypeExpr::Generic(name, arguments) => {
+            *name = names.qualify(name);
+
+            for argument in arguments {
+                qualify_type(argument, names);
+            }
+        },
+        TypeExpr::Array(element_type, _) => qualify_type(element_type, names),
+        TypeExpr::Optional(inner_type) => qualify_type(inner_type, names),
+    }
+}
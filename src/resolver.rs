@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use crate::ast::{is_literal, ASTNode, TypeExpr};
+use crate::diagnostic::Diagnostic;
+
+pub const PRIMITIVE_TYPES: [&str; 10] = ["int", "float", "bool", "string", "i32", "i64", "u32", "u64", "f32", "f64"];
+pub const GENERIC_TYPES: [&str; 2] = ["List", "Map"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DeclarationKind {
+    Data,
+    Group,
+    Do,
+    Enum,
+    Variant,
+}
+
+struct Resolver {
+    globals: HashMap<String, DeclarationKind>,
+    scopes: Vec<Vec<String>>,
+    context: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Checks that every name refers to a declaration or to a variable in scope.
+pub fn resolve_names(program: &ASTNode) -> Vec<Diagnostic> {
+    let mut resolver = Resolver {
+        globals: HashMap::new(),
+        scopes: Vec::new(),
+        context: "".to_string(),
+        diagnostics: Vec::new(),
+    };
+
+    if let ASTNode::Program(statements) = program {
+        resolver.collect_globals(statements);
+
+        for statement in statements {
+            resolver.resolve_statement(statement);
+        }
+    }
+
+    resolver.diagnostics
+}
+
+impl Resolver {
+    fn collect_globals(&mut self, statements: &[Box<ASTNode>]) {
+        for statement in statements {
+            match statement.as_ref() {
+                ASTNode::Data(name, _, _) => self.declare_global(name, DeclarationKind::Data),
+                ASTNode::Group(name, _, _, _) => self.declare_global(name, DeclarationKind::Group),
+                ASTNode::Do(name, _, _) => self.declare_global(name, DeclarationKind::Do),
+                ASTNode::Enum(name, variants, _) => {
+                    self.declare_global(name, DeclarationKind::Enum);
+
+                    for variant in variants {
+                        if let ASTNode::Variant(variant_name, _, _) = variant.as_ref() {
+                            self.declare_global(variant_name, DeclarationKind::Variant);
+                        }
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+
+    fn declare_global(&mut self, name: &str, kind: DeclarationKind) {
+        self.globals.entry(name.to_string()).or_insert(kind);
+    }
+
+    fn resolve_statement(&mut self, statement: &ASTNode) {
+        match statement {
+            ASTNode::Data(name, fields, _) => {
+                self.context = format!("data {}", name);
+                self.resolve_all(fields);
+            },
+            ASTNode::Enum(name, variants, _) => {
+                self.context = format!("enum {}", name);
+
+                for variant in variants {
+                    if let ASTNode::Variant(_, fields, _) = variant.as_ref() {
+                        self.resolve_all(fields);
+                    }
+                }
+            },
+            ASTNode::Group(name, parameters, data_instanciations, _) => {
+                self.context = format!("group {}", name);
+                self.resolve_all(parameters);
+
+                let parameter_names = parameters
+                    .iter()
+                    .filter_map(|parameter| match parameter.as_ref() {
+                        ASTNode::Parameter(parameter_name, _, _) => Some(parameter_name.clone()),
+                        _ => None,
+                    })
+                    .collect();
+
+                self.scopes.push(parameter_names);
+                self.resolve_all(data_instanciations);
+                self.scopes.pop();
+            },
+            ASTNode::Do(name, instructions, _) => {
+                self.context = format!("do {}", name);
+                self.resolve_block(instructions);
+            },
+            ASTNode::Run(actions_to_do, instructions) => {
+                self.context = format!("run ({})", actions_to_do.join(", "));
+
+                for action_to_do in actions_to_do {
+                    self.resolve_declaration(action_to_do, &[DeclarationKind::Do], "do action");
+                }
+
+                self.resolve_block(instructions);
+            },
+            _ => (),
+        }
+    }
+
+    fn resolve_block(&mut self, instructions: &[Box<ASTNode>]) {
+        self.scopes.push(Vec::new());
+        self.resolve_all(instructions);
+        self.scopes.pop();
+    }
+
+    fn resolve_all(&mut self, nodes: &[Box<ASTNode>]) {
+        for node in nodes {
+            self.resolve_node(node);
+        }
+    }
+
+    fn resolve_node(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Parameter(_, parameter_type, _) => self.resolve_type(parameter_type),
+            ASTNode::Field(_, field_type, default_value, _) => {
+                self.resolve_type(field_type);
+
+                if let Some(default_value) = default_value {
+                    self.resolve_node(default_value);
+                }
+            },
+            ASTNode::DataInstanciation(data_name, field_values) => {
+                self.resolve_declaration(data_name, &[DeclarationKind::Data], "data");
+                self.resolve_all(field_values);
+            },
+            ASTNode::FieldValue(_, value) | ASTNode::Value(value) => self.resolve_value(value),
+            ASTNode::CreateInstruction(group_name, parameter_values) => {
+                self.resolve_declaration(group_name, &[DeclarationKind::Group], "group");
+                self.resolve_all(parameter_values);
+            },
+            ASTNode::NamedArgument(_, child) => self.resolve_node(child),
+            ASTNode::ListLiteral(elements) => self.resolve_all(elements),
+            ASTNode::VariantConstructor(variant_name, field_values) => {
+                self.resolve_declaration(variant_name, &[DeclarationKind::Variant], "enum variant");
+                self.resolve_all(field_values);
+            },
+            ASTNode::Sum(lhs, rhs)
+            | ASTNode::Substraction(lhs, rhs)
+            | ASTNode::Multiplication(lhs, rhs)
+            | ASTNode::Division(lhs, rhs, _)
+            | ASTNode::Modulo(lhs, rhs, _)
+            | ASTNode::Equality(lhs, rhs)
+            | ASTNode::Inequality(lhs, rhs)
+            | ASTNode::GreaterThan(lhs, rhs)
+            | ASTNode::LessThan(lhs, rhs)
+            | ASTNode::GreaterOrEqual(lhs, rhs)
+            | ASTNode::LessOrEqual(lhs, rhs) => {
+                self.resolve_node(lhs);
+                self.resolve_node(rhs);
+            },
+            ASTNode::Declaration(variable_name, value) => {
+                self.resolve_node(value);
+                self.declare_local(variable_name);
+            },
+            ASTNode::Assignment(target, value) => {
+                self.resolve_value(target);
+                self.resolve_node(value);
+            },
+            ASTNode::If(condition, instructions) => {
+                self.resolve_node(condition);
+                self.resolve_block(instructions);
+            },
+            ASTNode::Foreach(values, collections, instructions, _) => {
+                self.resolve_all(collections);
+                self.scopes.push(values.clone());
+                self.resolve_block(instructions);
+                self.scopes.pop();
+            },
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                self.scopes.push(Vec::new());
+                self.resolve_node(declaration);
+                self.resolve_node(condition);
+                self.resolve_node(progression);
+                self.resolve_block(instructions);
+                self.scopes.pop();
+            },
+            ASTNode::Match(value, arms) => {
+                self.resolve_node(value);
+                self.resolve_all(arms);
+            },
+            ASTNode::MatchArm(pattern, instructions) => {
+                self.scopes.push(Vec::new());
+                self.resolve_node(pattern);
+                self.resolve_block(instructions);
+                self.scopes.pop();
+            },
+            ASTNode::BindingPattern(name) => self.declare_local(name),
+            ASTNode::VariantPattern(name, field_patterns) => {
+                match field_patterns.is_empty() {
+                    true  => self.resolve_declaration(name, &[DeclarationKind::Variant, DeclarationKind::Data], "enum variant"),
+                    false => self.resolve_declaration(name, &[DeclarationKind::Variant], "enum variant"),
+                }
+                self.resolve_all(field_patterns);
+            },
+            ASTNode::DataPattern(name, field_patterns) => {
+                self.resolve_declaration(name, &[DeclarationKind::Data], "data");
+                self.resolve_all(field_patterns);
+            },
+            ASTNode::FieldPattern(_, pattern) => self.resolve_node(pattern),
+            _ => (),
+        }
+    }
+
+    fn resolve_type(&mut self, type_expr: &TypeExpr) {
+        match type_expr {
+            TypeExpr::Named(name) => {
+                let type_kinds = [DeclarationKind::Data, DeclarationKind::Enum];
+
+                if PRIMITIVE_TYPES.contains(&name.as_str()) || self.globals.get(name).is_some_and(|kind| type_kinds.contains(kind)) {
+                    return;
+                }
+
+                let declared_types = self.globals
+                    .iter()
+                    .filter(|(_, kind)| type_kinds.contains(kind))
+                    .map(|(declared_type, _)| declared_type.clone());
+                let primitive_types = PRIMITIVE_TYPES.iter().map(|primitive_type| primitive_type.to_string());
+
+                let suggestion = closest_name(name, declared_types.chain(primitive_types).collect::<Vec<String>>().into_iter());
+                self.report_unresolved("type", name, suggestion);
+            },
+            TypeExpr::Generic(name, arguments) => {
+                if !GENERIC_TYPES.contains(&name.as_str()) {
+                    let suggestion = closest_name(name, GENERIC_TYPES.iter().map(|generic| generic.to_string()));
+                    self.report_unresolved("generic type", name, suggestion);
+                }
+
+                for argument in arguments {
+                    self.resolve_type(argument);
+                }
+            },
+            TypeExpr::Array(element_type, _) => self.resolve_type(element_type),
+            TypeExpr::Optional(inner_type) => self.resolve_type(inner_type),
+        }
+    }
+
+    fn resolve_declaration(&mut self, name: &str, kinds: &[DeclarationKind], description: &str) {
+        if let Some(kind) = self.globals.get(name) {
+            if kinds.contains(kind) {
+                return;
+            }
+        }
+
+        let candidates: Vec<String> = self.globals
+            .iter()
+            .filter(|(_, kind)| kinds.contains(kind))
+            .map(|(candidate, _)| candidate.clone())
+            .collect();
+
+        let suggestion = closest_name(name, candidates.into_iter());
+        self.report_unresolved(description, name, suggestion);
+    }
+
+    /// Resolves the variable, data or variant a value starts with, as p in p.x.
+    fn resolve_value(&mut self, value: &str) {
+        if value.is_empty() || is_literal(value) {
+            return;
+        }
+
+        let name = value.split('.').next().unwrap();
+
+        if self.scopes.iter().any(|scope| scope.iter().any(|local| local == name)) {
+            return;
+        }
+
+        let value_kinds = [DeclarationKind::Data, DeclarationKind::Variant];
+
+        if let Some(kind) = self.globals.get(name) {
+            if value_kinds.contains(kind) {
+                return;
+            }
+        }
+
+        let locals = self.scopes.iter().flatten().cloned();
+        let globals = self.globals
+            .iter()
+            .filter(|(_, kind)| value_kinds.contains(kind))
+            .map(|(global, _)| global.clone());
+
+        let suggestion = closest_name(name, locals.chain(globals).collect::<Vec<String>>().into_iter());
+        self.report_unresolved("name", name, suggestion);
+    }
+
+    fn declare_local(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(name.to_string());
+        }
+    }
+
+    fn report_unresolved(&mut self, description: &str, name: &str, suggestion: Option<String>) {
+        let mut diagnostic = Diagnostic::error(format!("In {}: unresolved {} {}.", self.context, description, name));
+
+        if let Some(suggestion) = suggestion {
+            diagnostic = diagnostic.with_note(format!("did you mean {}?", suggestion));
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+/// Picks the candidate with the smallest edit distance, if it is close enough to be a likely typo.
+pub fn closest_name(name: &str, candidates: impl Iterator<Item = String>) -> Option<String> {
+    let maximum_distance = name.chars().count().div_ceil(3);
+
+    let mut candidates: Vec<String> = candidates.collect();
+    candidates.sort();
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, &candidate), candidate))
+        .filter(|(distance, _)| *distance <= maximum_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(lhs: &str, rhs: &str) -> usize {
+    let lhs: Vec<char> = lhs.chars().collect();
+    let rhs: Vec<char> = rhs.chars().collect();
+
+    let mut previous_row: Vec<usize> = (0..=rhs.len()).collect();
+
+    for (i, lhs_character) in lhs.iter().enumerate() {
+        let mut current_row = vec![i + 1];
+
+        for (j, rhs_character) in rhs.iter().enumerate() {
+            let substitution_cost = if lhs_character == rhs_character { 0 } else { 1 };
+
+            current_row.push(
+                (previous_row[j] + substitution_cost)
+                    .min(previous_row[j + 1] + 1)
+                    .min(current_row[j] + 1)
+            );
+        }
+
+        previous_row = current_row;
+    }
+
+    previous_row[rhs.len()]
+}
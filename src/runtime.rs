@@ -0,0 +1,459 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ast::TypeExpr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Variant(String, Vec<Value>),
+    List(Vec<Value>),
+    /// Refers to a row of the table of a data.
+    Instance(String, usize),
+    None,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "\"{}\"", value),
+            Value::Variant(name, fields) if fields.is_empty() => write!(f, "{}", name),
+            Value::Variant(name, fields) => {
+                let fields: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+                write!(f, "{}({})", name, fields.join(", "))
+            },
+            Value::List(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| element.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            },
+            Value::Instance(data_name, row) => write!(f, "{}#{}", data_name, row),
+            Value::None => write!(f, "none"),
+        }
+    }
+}
+
+pub fn parse_literal(literal: &str) -> Value {
+    match literal {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ if literal.starts_with('"') => Value::String(literal.trim_matches('"').to_string()),
+        _ => match literal.parse::<i64>() {
+            Ok(value) => Value::Int(value),
+            Err(_) => Value::Float(literal.parse::<f64>().unwrap()),
+        },
+    }
+}
+
+/// As for their type, a list mixing integers and floats only holds floats.
+pub fn new_list(elements: Vec<Value>) -> Value {
+    let element_type = match elements.iter().any(|element| matches!(element, Value::Float(_))) {
+        true  => ColumnType::Float,
+        false => ColumnType::Any,
+    };
+
+    Value::List(elements.into_iter().map(|element| element_type.convert(element)).collect())
+}
+
+/// Applies +, -, *, / or %. Integers stay integers and report overflow, any float operand makes the result a float,
+/// and + also concatenates strings.
+pub fn apply_arithmetic(operator: &str, lhs: &Value, rhs: &Value) -> Result<Value, String> {
+    match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => {
+            let result = match operator {
+                "+" => lhs.checked_add(*rhs),
+                "-" => lhs.checked_sub(*rhs),
+                "*" => lhs.checked_mul(*rhs),
+                "/" => lhs.checked_div(*rhs),
+                _   => lhs.checked_rem(*rhs),
+            };
+
+            match (result, rhs) {
+                (Some(result), _) => Ok(Value::Int(result)),
+                (None, 0) => Err(format!("Division by zero in {} {} {}.", lhs, operator, rhs)),
+                (None, _) => Err(format!("{} {} {} overflows a 64 bit integer.", lhs, operator, rhs)),
+            }
+        },
+        (Value::String(lhs), Value::String(rhs)) if operator == "+" => Ok(Value::String(format!("{}{}", lhs, rhs))),
+        _ => {
+            let (lhs, rhs) = match (as_float(lhs), as_float(rhs)) {
+                (Some(lhs), Some(rhs)) => (lhs, rhs),
+                _ => return Err(format!("Cannot apply {} to {} and {}.", operator, lhs, rhs)),
+            };
+
+            if rhs == 0.0 && (operator == "/" || operator == "%") {
+                return Err(format!("Division by zero in {:?} {} {:?}.", lhs, operator, rhs));
+            }
+
+            Ok(Value::Float(match operator {
+                "+" => lhs + rhs,
+                "-" => lhs - rhs,
+                "*" => lhs * rhs,
+                "/" => lhs / rhs,
+                _   => lhs % rhs,
+            }))
+        },
+    }
+}
+
+/// Numbers are equal across int and float, every other value only to the same value.
+pub fn values_equal(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Int(_), Value::Float(_)) | (Value::Float(_), Value::Int(_)) => as_float(lhs) == as_float(rhs),
+        _ => lhs == rhs,
+    }
+}
+
+/// Applies >, <, >= or <= to two numbers.
+pub fn compare(operator: &str, lhs: &Value, rhs: &Value) -> Result<bool, String> {
+    let ordering = match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => Some(lhs.cmp(rhs)),
+        _ => match (as_float(lhs), as_float(rhs)) {
+            (Some(lhs), Some(rhs)) => lhs.partial_cmp(&rhs),
+            _ => None,
+        },
+    };
+
+    let ordering = match ordering {
+        Some(ordering) => ordering,
+        None => return Err(format!("Cannot compare {} and {} with {}.", lhs, rhs, operator)),
+    };
+
+    Ok(match operator {
+        ">"  => ordering.is_gt(),
+        "<"  => ordering.is_lt(),
+        ">=" => ordering.is_ge(),
+        _    => ordering.is_le(),
+    })
+}
+
+fn as_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// Primitive fields get a column of unboxed values, every other field a column of values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnType {
+    Int,
+    Float,
+    Bool,
+    String,
+    OptionalFloat,
+    Any,
+}
+
+impl ColumnType {
+    pub fn of(type_expr: &TypeExpr) -> ColumnType {
+        match type_expr {
+            TypeExpr::Named(name) => match name.as_str() {
+                "int" | "i32" | "i64" | "u32" | "u64" => ColumnType::Int,
+                "float" | "f32" | "f64" => ColumnType::Float,
+                "bool" => ColumnType::Bool,
+                "string" => ColumnType::String,
+                _ => ColumnType::Any,
+            },
+            TypeExpr::Optional(inner_type) if ColumnType::of(inner_type) == ColumnType::Float => ColumnType::OptionalFloat,
+            _ => ColumnType::Any,
+        }
+    }
+
+    /// Integers stored where a float is expected become floats, including inside an optional float.
+    pub fn convert(&self, value: Value) -> Value {
+        match (self, value) {
+            (ColumnType::Float | ColumnType::OptionalFloat, Value::Int(value)) => Value::Float(value as f64),
+            (_, value) => value,
+        }
+    }
+}
+
+pub enum Column {
+    Int(Vec<i64>),
+    Float(Vec<f64>),
+    Bool(Vec<bool>),
+    String(Vec<String>),
+    Any(Vec<Value>),
+}
+
+impl Column {
+    fn new(column_type: ColumnType) -> Column {
+        match column_type {
+            ColumnType::Int => Column::Int(Vec::new()),
+            ColumnType::Float => Column::Float(Vec::new()),
+            ColumnType::Bool => Column::Bool(Vec::new()),
+            ColumnType::String => Column::String(Vec::new()),
+            ColumnType::OptionalFloat | ColumnType::Any => Column::Any(Vec::new()),
+        }
+    }
+
+    pub fn get(&self, row: usize) -> Value {
+        match self {
+            Column::Int(values) => Value::Int(values[row]),
+            Column::Float(values) => Value::Float(values[row]),
+            Column::Bool(values) => Value::Bool(values[row]),
+            Column::String(values) => Value::String(values[row].clone()),
+            Column::Any(values) => values[row].clone(),
+        }
+    }
+
+    pub fn set(&mut self, row: usize, value: Value) {
+        match (self, value) {
+            (Column::Int(values), Value::Int(value)) => values[row] = value,
+            (Column::Float(values), Value::Float(value)) => values[row] = value,
+            (Column::Bool(values), Value::Bool(value)) => values[row] = value,
+            (Column::String(values), Value::String(value)) => values[row] = value,
+            (Column::Any(values), value) => values[row] = value,
+            (_, value) => panic!("Cannot store {} in a column of another type.", value),
+        }
+    }
+
+    fn push(&mut self, value: Value) {
+        match (self, value) {
+            (Column::Int(values), Value::Int(value)) => values.push(value),
+            (Column::Float(values), Value::Float(value)) => values.push(value),
+            (Column::Bool(values), Value::Bool(value)) => values.push(value),
+            (Column::String(values), Value::String(value)) => values.push(value),
+            (Column::Any(values), value) => values.push(value),
+            (_, value) => panic!("Cannot store {} in a column of another type.", value),
+        }
+    }
+}
+
+/// Instances of one data stored as struct of arrays: one column per field, one row per instance.
+/// Rows are only ever appended, so the entity column stays sorted.
+pub struct Table {
+    pub field_names: Vec<String>,
+    pub column_types: Vec<ColumnType>,
+    pub entities: Vec<usize>,
+    pub columns: Vec<Column>,
+}
+
+impl Table {
+    pub fn new(fields: Vec<(String, ColumnType)>) -> Table {
+        let (field_names, column_types): (Vec<String>, Vec<ColumnType>) = fields.into_iter().unzip();
+
+        Table {
+            field_names,
+            columns: column_types.iter().copied().map(Column::new).collect(),
+            column_types,
+            entities: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn field_index(&self, field_name: &str) -> Option<usize> {
+        self.field_names.iter().position(|name| name == field_name)
+    }
+
+    /// Appends a row, with one value per field in declaration order.
+    pub fn push_row(&mut self, entity: usize, values: Vec<Value>) {
+        self.entities.push(entity);
+
+        for ((column, column_type), value) in self.columns.iter_mut().zip(&self.column_types).zip(values) {
+            column.push(column_type.convert(value));
+        }
+    }
+
+    pub fn get(&self, row: usize, field_index: usize) -> Value {
+        self.columns[field_index].get(row)
+    }
+
+    pub fn set(&mut self, row: usize, field_index: usize, value: Value) {
+        self.columns[field_index].set(row, self.column_types[field_index].convert(value));
+    }
+}
+
+/// Every table, in declaration order of the data. An entity is one creation of a group.
+#[derive(Default)]
+pub struct World {
+    pub entity_count: usize,
+    pub data_names: Vec<String>,
+    pub tables: HashMap<String, Table>,
+}
+
+impl World {
+    pub fn add_table(&mut self, data_name: &str, fields: Vec<(String, ColumnType)>) {
+        self.data_names.push(data_name.to_string());
+        self.tables.insert(data_name.to_string(), Table::new(fields));
+    }
+}
+
+/// Where the interpreter finds tables: the whole world, or the share of it one action of a parallel stage gets.
+pub trait Storage: Sync {
+    fn table(&self, data_name: &str) -> &Table;
+
+    fn table_mut(&mut self, data_name: &str) -> &mut Table;
+
+    fn new_entity(&mut self) -> usize;
+
+    fn get_field(&self, data_name: &str, row: usize, field_index: usize) -> Value {
+        self.table(data_name).get(row, field_index)
+    }
+
+    fn set_field(&mut self, data_name: &str, row: usize, field_index: usize, value: Value) {
+        self.table_mut(data_name).set(row, field_index, value);
+    }
+
+    /// Gives, for each entity that has an instance of every data, the row of that instance in each table.
+    /// As entity columns are sorted, this is a merge join that walks every table once, front to back.
+    fn join(&self, data_names: &[&str]) -> Vec<Vec<usize>> {
+        let tables: Vec<&Table> = data_names.iter().map(|data_name| self.table(data_name)).collect();
+        let mut rows = vec![0; tables.len()];
+        let mut joined_rows = Vec::new();
+
+        if tables.is_empty() {
+            return joined_rows;
+        }
+
+        while tables.iter().zip(&rows).all(|(table, row)| *row < table.len()) {
+            let entities: Vec<usize> = tables.iter().zip(&rows).map(|(table, row)| table.entities[*row]).collect();
+            let highest_entity = *entities.iter().max().unwrap();
+
+            if entities.iter().all(|entity| *entity == highest_entity) {
+                joined_rows.push(rows.clone());
+
+                for row in rows.iter_mut() {
+                    *row += 1;
+                }
+            }
+            else {
+                for (row, entity) in rows.iter_mut().zip(entities) {
+                    if entity < highest_entity {
+                        *row += 1;
+                    }
+                }
+            }
+        }
+
+        joined_rows
+    }
+}
+
+impl Storage for World {
+    fn table(&self, data_name: &str) -> &Table {
+        &self.tables[data_name]
+    }
+
+    fn table_mut(&mut self, data_name: &str) -> &mut Table {
+        self.tables.get_mut(data_name).unwrap()
+    }
+
+    fn new_entity(&mut self) -> usize {
+        self.entity_count += 1;
+        self.entity_count - 1
+    }
+}
+
+/// The tables an action writes, taken out of the world for the length of a stage, and read access to the others.
+/// Actions of a stage never read what another one writes, so the tables they read are all still in the world.
+pub struct WorldShare<'w> {
+    world: &'w World,
+    tables: HashMap<String, Table>,
+}
+
+impl<'w> WorldShare<'w> {
+    pub fn new(world: &'w World, tables: HashMap<String, Table>) -> WorldShare<'w> {
+        WorldShare { world, tables }
+    }
+
+    pub fn into_tables(self) -> HashMap<String, Table> {
+        self.tables
+    }
+}
+
+impl Storage for WorldShare<'_> {
+    fn table(&self, data_name: &str) -> &Table {
+        self.tables.get(data_name).unwrap_or_else(|| &self.world.tables[data_name])
+    }
+
+    fn table_mut(&mut self, data_name: &str) -> &mut Table {
+        match self.tables.get_mut(data_name) {
+            Some(table) => table,
+            None => panic!("Data {} is written by an action that was not scheduled to write it.", data_name),
+        }
+    }
+
+    fn new_entity(&mut self) -> usize {
+        unreachable!("Actions that create entities run alone, on the whole world.")
+    }
+}
+
+/// Values written to fields, by data, row and field index.
+pub type FieldWrites = HashMap<(String, usize, usize), Value>;
+
+/// Lets a chunk of the iterations of a parallel foreach read the tables while keeping its writes aside,
+/// to be applied once every chunk is done. Each iteration only writes its own instances, so writes of chunks never overlap.
+pub struct IterationBuffer<'s> {
+    storage: &'s dyn Storage,
+    writes: FieldWrites,
+}
+
+impl<'s> IterationBuffer<'s> {
+    pub fn new(storage: &'s dyn Storage) -> IterationBuffer<'s> {
+        IterationBuffer { storage, writes: HashMap::new() }
+    }
+
+    pub fn into_writes(self) -> FieldWrites {
+        self.writes
+    }
+}
+
+impl Storage for IterationBuffer<'_> {
+    fn table(&self, data_name: &str) -> &Table {
+        self.storage.table(data_name)
+    }
+
+    fn table_mut(&mut self, data_name: &str) -> &mut Table {
+        unreachable!("Iterations of a parallel foreach write {} field by field.", data_name)
+    }
+
+    fn new_entity(&mut self) -> usize {
+        unreachable!("Iterations of a parallel foreach cannot create entities.")
+    }
+
+    fn get_field(&self, data_name: &str, row: usize, field_index: usize) -> Value {
+        match self.writes.get(&(data_name.to_string(), row, field_index)) {
+            Some(value) => value.clone(),
+            None => self.storage.get_field(data_name, row, field_index),
+        }
+    }
+
+    fn set_field(&mut self, data_name: &str, row: usize, field_index: usize, value: Value) {
+        let value = self.table(data_name).column_types[field_index].convert(value);
+        self.writes.insert((data_name.to_string(), row, field_index), value);
+    }
+}
+
+impl fmt::Display for World {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} entities", self.entity_count)?;
+
+        for data_name in &self.data_names {
+            let table = &self.tables[data_name];
+            writeln!(f, "{} ({} instances)", data_name, table.len())?;
+
+            for row in 0..table.len() {
+                let fields: Vec<String> = table.field_names
+                    .iter()
+                    .enumerate()
+                    .map(|(field_index, field_name)| format!("{} = {}", field_name, table.get(row, field_index)))
+                    .collect();
+
+                writeln!(f, "  entity {}: {}", table.entities[row], fields.join(", "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
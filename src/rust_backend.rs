@@ -0,0 +1,588 @@
+use crate::ir::{BinaryOperator, BlockId, FunctionKind, Instruction, IrField, IrFunction, IrProgram, IrTable, IrType, Operand, Register, Terminator};
+
+/// Items every generated module starts with: the table type, the merge join over entity columns, and arithmetic that
+/// fails where run would. Errors are the messages run reports, without the error prefix.
+const PRELUDE: &str = r#"// Generated by dolang-compiler. Include it in a module of its own, then call World::run on a World::default().
+
+/// The instances of a data, each with the entity it belongs to, in the order they were created.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table<T> {
+    pub entities: Vec<usize>,
+    pub rows: Vec<T>,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Table { entities: Vec::new(), rows: Vec::new() }
+    }
+}
+
+#[allow(dead_code)]
+impl<T> Table<T> {
+    pub fn push(&mut self, entity: usize, row: T) {
+        self.entities.push(entity);
+        self.rows.push(row);
+    }
+}
+
+/// Gives the rows at which every table has an instance of the same entity, as entities only grow within a table.
+#[allow(dead_code)]
+fn dolang_join(entity_columns: &[&[usize]]) -> Vec<Vec<usize>> {
+    let mut rows = vec![0; entity_columns.len()];
+    let mut joined = Vec::new();
+
+    loop {
+        let mut highest = 0;
+        let mut same = true;
+
+        for (index, entities) in entity_columns.iter().enumerate() {
+            let entity = match entities.get(rows[index]) {
+                Some(entity) => *entity,
+                None => return joined,
+            };
+
+            same &= index == 0 || entity == highest;
+            highest = highest.max(entity);
+        }
+
+        if same {
+            joined.push(rows.clone());
+            rows.iter_mut().for_each(|row| *row += 1);
+        }
+        else {
+            for (index, entities) in entity_columns.iter().enumerate() {
+                if entities[rows[index]] < highest {
+                    rows[index] += 1;
+                }
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+fn dolang_arithmetic(lhs: i64, operator: &str, rhs: i64, context: &str) -> Result<i64, String> {
+    let result = match operator {
+        "+" => lhs.checked_add(rhs),
+        "-" => lhs.checked_sub(rhs),
+        "*" => lhs.checked_mul(rhs),
+        "/" => lhs.checked_div(rhs),
+        _ => lhs.checked_rem(rhs),
+    };
+
+    match (result, rhs) {
+        (Some(result), _) => Ok(result),
+        (None, 0) => Err(format!("In {}: Division by zero in {} {} {}.", context, lhs, operator, rhs)),
+        (None, _) => Err(format!("In {}: {} {} {} overflows a 64 bit integer.", context, lhs, operator, rhs)),
+    }
+}
+
+#[allow(dead_code)]
+fn dolang_divide_float(lhs: f64, operator: &str, rhs: f64, context: &str) -> Result<f64, String> {
+    if rhs == 0.0 {
+        return Err(format!("In {}: Division by zero in {:?} {} {:?}.", context, lhs, operator, rhs));
+    }
+
+    Ok(if operator == "/" { lhs / rhs } else { lhs % rhs })
+}
+"#;
+
+const RUST_KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "false", "fn", "for", "if", "impl",
+    "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static", "struct", "trait", "true",
+    "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "yield",
+];
+
+
+struct RustGenerator<'a> {
+    program: &'a IrProgram,
+    output: String,
+    indentation: usize,
+}
+
+/// Generates a Rust module: a derived struct per data and an enum per enum, and a World holding a table per data,
+/// with a method per group and per do and a run method executing the run blocks. A function of several blocks loops
+/// over a match on the block to execute next. Displaying the world prints it as run does.
+pub fn generate_rust(program: &IrProgram) -> String {
+    let mut generator = RustGenerator {
+        program,
+        output: PRELUDE.to_string(),
+        indentation: 0,
+    };
+
+    for (enum_name, variants) in &program.enums {
+        generator.generate_enum(enum_name, variants);
+    }
+
+    for table in &program.tables {
+        generator.generate_struct(table);
+    }
+
+    generator.generate_world();
+
+    generator.line("");
+    generator.line("#[allow(dead_code, unused_mut, unused_variables, unused_assignments, clippy::all)]");
+    generator.line("impl World {");
+    generator.indentation += 1;
+
+    let mut run_count = 0;
+
+    for function in &program.functions {
+        let name = match &function.kind {
+            FunctionKind::Group(group_name) => format!("pub fn create_{}", snake_name(group_name)),
+            FunctionKind::Do(action_name) => format!("pub fn do_{}", snake_name(action_name)),
+            FunctionKind::Run(_) => {
+                run_count += 1;
+                format!("fn run_{}", run_count - 1)
+            },
+        };
+
+        generator.generate_function(function, &name);
+    }
+
+    generator.generate_run(run_count);
+    generator.indentation -= 1;
+    generator.line("}");
+
+    generator.generate_display();
+    generator.output
+}
+
+impl<'a> RustGenerator<'a> {
+    fn generate_enum(&mut self, name: &str, variants: &[String]) {
+        self.line("");
+        self.line("#[allow(dead_code)]");
+        self.line("#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]");
+        self.line(&format!("pub enum {} {{", type_name(name)));
+        self.indentation += 1;
+
+        for (index, variant_name) in variants.iter().enumerate() {
+            if index == 0 {
+                self.line("#[default]");
+            }
+
+            self.line(&format!("{},", variant_ident(variant_name)));
+        }
+
+        self.indentation -= 1;
+        self.line("}");
+        self.line("");
+        self.line(&format!("impl std::fmt::Display for {} {{", type_name(name)));
+        self.indentation += 1;
+        self.line("fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {");
+        self.indentation += 1;
+        self.line("f.write_str(match self {");
+        self.indentation += 1;
+
+        for variant_name in variants {
+            self.line(&format!("{} => {:?},", variant_path(name, variant_name), variant_name));
+        }
+
+        self.indentation -= 1;
+        self.line("})");
+        self.indentation -= 1;
+        self.line("}");
+        self.indentation -= 1;
+        self.line("}");
+    }
+
+    /// A data becomes a struct with a field per field, an optional field holding an Option. A field holding an
+    /// instance holds its row.
+    fn generate_struct(&mut self, table: &IrTable) {
+        self.line("");
+        self.line("#[derive(Debug, Clone, PartialEq)]");
+        self.line(&format!("pub struct {} {{", type_name(&table.data_name)));
+        self.indentation += 1;
+
+        for field in &table.fields {
+            let field_type = match field.optional {
+                true  => format!("Option<{}>", rust_type(&field.field_type)),
+                false => rust_type(&field.field_type),
+            };
+
+            self.line(&format!("pub {}: {},", rust_name(&field.name), field_type));
+        }
+
+        self.indentation -= 1;
+        self.line("}");
+    }
+
+    fn generate_world(&mut self) {
+        self.line("");
+        self.line("/// Every instance of the program, a table per data.");
+        self.line("#[derive(Debug, Clone, Default, PartialEq)]");
+        self.line("pub struct World {");
+        self.indentation += 1;
+        self.line("pub entity_count: usize,");
+
+        for table in &self.program.tables {
+            self.line(&format!("pub {}: Table<{}>,", table_name(&table.data_name), type_name(&table.data_name)));
+        }
+
+        self.indentation -= 1;
+        self.line("}");
+    }
+
+    /// Registers become variables and each join the rows it gives, worked out when it starts, so that instances
+    /// created in the loop are not visited.
+    fn generate_function(&mut self, function: &IrFunction, name: &str) {
+        let parameters: Vec<String> = function.registers[..function.parameter_count]
+            .iter()
+            .enumerate()
+            .map(|(register, (_, register_type))| format!("mut {}: {}", register_name(register), rust_type(register_type)))
+            .collect();
+
+        self.line("");
+        self.line(&format!("{}(&mut self{}) -> Result<(), String> {{", name, parameters.iter().map(|parameter| format!(", {}", parameter)).collect::<String>()));
+        self.indentation += 1;
+
+        for (register, (_, register_type)) in function.registers.iter().enumerate().skip(function.parameter_count) {
+            self.line(&format!("let mut {}: {} = Default::default();", register_name(register), rust_type(register_type)));
+        }
+
+        for join in 0..function.join_count {
+            self.line(&format!("let mut dolang_joined_{}: Vec<Vec<usize>> = Vec::new();", join));
+            self.line(&format!("let mut dolang_next_{} = 0;", join));
+        }
+
+        let reachable = function.reachable_blocks();
+
+        match reachable.as_slice() {
+            [block] if function.successors(*block).is_empty() => self.generate_block(function, *block),
+            _ => {
+                self.line("let mut dolang_block = 0;");
+                self.line("");
+                self.line("loop {");
+                self.indentation += 1;
+                self.line("match dolang_block {");
+                self.indentation += 1;
+
+                for block in reachable {
+                    self.line(&format!("{} => {{", block));
+                    self.indentation += 1;
+                    self.generate_block(function, block);
+                    self.indentation -= 1;
+                    self.line("},");
+                }
+
+                self.line("_ => unreachable!(),");
+                self.indentation -= 1;
+                self.line("}");
+                self.indentation -= 1;
+                self.line("}");
+            },
+        }
+
+        self.indentation -= 1;
+        self.line("}");
+    }
+
+    fn generate_block(&mut self, function: &IrFunction, block: BlockId) {
+        for instruction in &function.blocks[block].instructions {
+            self.generate_instruction(function, instruction);
+        }
+
+        self.generate_terminator(function, &function.blocks[block].terminator);
+    }
+
+    fn generate_instruction(&mut self, function: &IrFunction, instruction: &Instruction) {
+        match instruction {
+            Instruction::Copy(target, value) => self.line(&format!("{} = {};", register_name(*target), self.operand(function, value))),
+            Instruction::IntToFloat(target, value) => {
+                self.line(&format!("{} = {} as f64;", register_name(*target), self.operand(function, value)));
+            },
+            Instruction::Binary(target, operator, lhs, rhs) => {
+                let value = self.binary(function, *operator, lhs, rhs);
+                self.line(&format!("{} = {};", register_name(*target), value));
+            },
+            Instruction::LoadField(target, data_name, field_name, row) => {
+                let field = format!("self.{}.rows[{}].{}", table_name(data_name), self.operand(function, row), rust_name(field_name));
+
+                let value = match self.program.table(data_name).and_then(|table| table.field(field_name)) {
+                    Some(ir_field) => field_read(&field, ir_field),
+                    None => field,
+                };
+
+                self.line(&format!("{} = {};", register_name(*target), value));
+            },
+            Instruction::StoreField(data_name, field_name, row, value) => {
+                let value = self.operand(function, value);
+                let optional = self.program.table(data_name).and_then(|table| table.field(field_name)).is_some_and(|field| field.optional);
+
+                self.line(&format!(
+                    "self.{}.rows[{}].{} = {};",
+                    table_name(data_name),
+                    self.operand(function, row),
+                    rust_name(field_name),
+                    if optional { format!("Some({})", value) } else { value }
+                ));
+            },
+            Instruction::NewEntity(target) => {
+                self.line(&format!("{} = self.entity_count;", register_name(*target)));
+                self.line("self.entity_count += 1;");
+            },
+            Instruction::PushRow(data_name, entity, values) => {
+                let mut fields = Vec::new();
+
+                if let Some(table) = self.program.table(data_name) {
+                    for (field, value) in table.fields.iter().zip(values) {
+                        let value = match (value, field.optional) {
+                            (Some(value), true) => format!("Some({})", self.operand(function, value)),
+                            (Some(value), false) => self.operand(function, value),
+                            (None, true) => "None".to_string(),
+                            (None, false) => "Default::default()".to_string(),
+                        };
+
+                        fields.push(format!("{}: {}", rust_name(&field.name), value));
+                    }
+                }
+
+                self.line(&format!(
+                    "self.{}.push({}, {} {{ {} }});",
+                    table_name(data_name),
+                    self.operand(function, entity),
+                    type_name(data_name),
+                    fields.join(", ")
+                ));
+            },
+            Instruction::Create(group_name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| self.operand(function, argument)).collect();
+                self.line(&format!("self.create_{}({})?;", snake_name(group_name), arguments.join(", ")));
+            },
+            Instruction::Do(action_name) => self.line(&format!("self.do_{}()?;", snake_name(action_name))),
+            Instruction::StartJoin(join, data_names) => {
+                let entity_columns: Vec<String> = data_names.iter().map(|data_name| format!("&self.{}.entities", table_name(data_name))).collect();
+                self.line(&format!("dolang_joined_{} = dolang_join(&[{}]);", join, entity_columns.join(", ")));
+                self.line(&format!("dolang_next_{} = 0;", join));
+            },
+            Instruction::AdvanceJoin(join) => self.line(&format!("dolang_next_{} += 1;", join)),
+        }
+    }
+
+    fn generate_terminator(&mut self, function: &IrFunction, terminator: &Terminator) {
+        match terminator {
+            Terminator::Jump(target) => self.line(&format!("dolang_block = {};", target)),
+            Terminator::Branch(condition, then_block, else_block) => {
+                self.line(&format!("dolang_block = if {} {{ {} }} else {{ {} }};", self.operand(function, condition), then_block, else_block));
+            },
+            Terminator::NextJoin(join, rows, body, exit) => {
+                self.line(&format!("match dolang_joined_{}.get(dolang_next_{}) {{", join, join));
+                self.indentation += 1;
+                self.line("Some(rows) => {");
+                self.indentation += 1;
+
+                for (index, row) in rows.iter().enumerate() {
+                    self.line(&format!("{} = rows[{}];", register_name(*row), index));
+                }
+
+                self.line(&format!("dolang_block = {};", body));
+                self.indentation -= 1;
+                self.line("},");
+                self.line(&format!("None => dolang_block = {},", exit));
+                self.indentation -= 1;
+                self.line("}");
+            },
+            Terminator::NoMatch(value) => {
+                let message = format!("In {}: No match arm matches {}.", function.context, display_format(&function.operand_type(value)));
+                self.line(&format!("return Err(format!({:?}, {}));", message, self.operand(function, value)));
+            },
+            Terminator::Return => self.line("return Ok(());"),
+        }
+    }
+
+    /// Integer arithmetic fails on overflow, and division by zero fails for both integers and floats.
+    fn binary(&self, function: &IrFunction, operator: BinaryOperator, lhs: &Operand, rhs: &Operand) -> String {
+        let operand_type = function.operand_type(lhs);
+        let (lhs, rhs) = (self.operand(function, lhs), self.operand(function, rhs));
+        let (context, symbol) = (&function.context, operator.symbol());
+
+        match (operator, operand_type) {
+            (BinaryOperator::Concat, _) => format!("format!(\"{{}}{{}}\", {}, {})", lhs, rhs),
+            (operator, _) if operator.is_comparison() => format!("{} {} {}", lhs, symbol, rhs),
+            (_, IrType::Int) => format!("dolang_arithmetic({}, {:?}, {}, {:?})?", lhs, symbol, rhs, context),
+            (BinaryOperator::Divide | BinaryOperator::Modulo, _) => {
+                format!("dolang_divide_float({}, {:?}, {}, {:?})?", lhs, symbol, rhs, context)
+            },
+            _ => format!("{} {} {}", lhs, symbol, rhs),
+        }
+    }
+
+    /// Gives the Rust expression for an operand, copying strings held in registers so that they can be used again.
+    fn operand(&self, function: &IrFunction, operand: &Operand) -> String {
+        match operand {
+            Operand::Register(register) if function.registers[*register].1 == IrType::String => {
+                format!("{}.clone()", register_name(*register))
+            },
+            Operand::Register(register) => register_name(*register),
+            Operand::Int(i64::MIN) => "i64::MIN".to_string(),
+            Operand::Int(value) => format!("{}_i64", value),
+            Operand::Float(value) if value.is_nan() => "f64::NAN".to_string(),
+            Operand::Float(value) if value.is_infinite() => format!("f64::{}INFINITY", if *value < 0.0 { "NEG_" } else { "" }),
+            Operand::Float(value) => format!("{:?}_f64", value),
+            Operand::Bool(value) => value.to_string(),
+            Operand::String(value) => format!("{:?}.to_string()", value),
+            Operand::Variant(enum_name, index) => variant_path(enum_name, &self.program.variants(enum_name)[*index]),
+        }
+    }
+
+    fn generate_run(&mut self, run_count: usize) {
+        self.line("");
+        self.line("/// Executes every run block in order, each followed by the actions it lists.");
+        self.line("pub fn run(&mut self) -> Result<(), String> {");
+        self.indentation += 1;
+
+        for run in 0..run_count {
+            self.line(&format!("self.run_{}()?;", run));
+        }
+
+        self.line("Ok(())");
+        self.indentation -= 1;
+        self.line("}");
+    }
+
+    fn generate_display(&mut self) {
+        self.line("");
+        self.line("impl std::fmt::Display for World {");
+        self.indentation += 1;
+        self.line("fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {");
+        self.indentation += 1;
+        self.line("writeln!(f, \"{} entities\", self.entity_count)?;");
+
+        for table in &self.program.tables {
+            let rows = table_name(&table.data_name);
+            self.line(&format!("writeln!(f, \"{} ({{}} instances)\", self.{}.rows.len())?;", table.data_name, rows));
+            self.line("");
+            self.line(&format!("for (entity, row) in self.{}.entities.iter().zip(&self.{}.rows) {{", rows, rows));
+            self.indentation += 1;
+            self.line("write!(f, \"  entity {}: \", entity)?;");
+
+            for (index, field) in table.fields.iter().enumerate() {
+                let separator = if index == 0 { "" } else { ", " };
+                let label = format!("{}{} = ", separator, field.name);
+                let value = format!("row.{}", rust_name(&field.name));
+                let format = format!("{}{}", label, display_format(&field.field_type));
+
+                match field.optional {
+                    true => self.line(&format!(
+                        "match &{} {{ Some(value) => write!(f, {:?}, value)?, None => write!(f, {:?})? }}",
+                        value,
+                        format,
+                        format!("{}none", label)
+                    )),
+                    false => self.line(&format!("write!(f, {:?}, {})?;", format, value)),
+                }
+            }
+
+            self.line("writeln!(f)?;");
+            self.indentation -= 1;
+            self.line("}");
+            self.line("");
+        }
+
+        self.line("Ok(())");
+        self.indentation -= 1;
+        self.line("}");
+        self.indentation -= 1;
+        self.line("}");
+    }
+
+    fn line(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.output.push_str(&"    ".repeat(self.indentation));
+            self.output.push_str(text);
+        }
+
+        self.output.push('\n');
+    }
+}
+
+fn register_name(register: Register) -> String {
+    format!("dolang_r{}", register)
+}
+
+fn rust_type(value_type: &IrType) -> String {
+    match value_type {
+        IrType::Int => "i64".to_string(),
+        IrType::Float => "f64".to_string(),
+        IrType::Bool => "bool".to_string(),
+        IrType::String => "String".to_string(),
+        IrType::Enum(enum_name) => type_name(enum_name),
+        IrType::Instance(_) | IrType::Entity => "usize".to_string(),
+    }
+}
+
+/// Reads a field, copying strings so that they can be used again, and an optional field as its value, or the
+/// default value of its type when unset.
+fn field_read(value: &str, field: &IrField) -> String {
+    match (field.optional, &field.field_type) {
+        (true, IrType::String) => format!("{}.clone().unwrap_or_default()", value),
+        (true, _) => format!("{}.unwrap_or_default()", value),
+        (false, IrType::String) => format!("{}.clone()", value),
+        (false, _) => value.to_string(),
+    }
+}
+
+/// The format string printing a value the way run prints it.
+fn display_format(value_type: &IrType) -> String {
+    match value_type {
+        IrType::Float => "{:?}".to_string(),
+        IrType::String => "\"{}\"".to_string(),
+        IrType::Instance(data_name) => format!("{}#{{}}", data_name),
+        _ => "{}".to_string(),
+    }
+}
+
+/// Qualified names such as physics::Position become PhysicsPosition.
+fn type_name(name: &str) -> String {
+    name.split("::")
+        .map(|segment| {
+            let mut characters = segment.chars();
+
+            match characters.next() {
+                Some(first) => first.to_uppercase().chain(characters).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Names such as physics::MoveFast become physics_move_fast, for methods once prefixed.
+fn snake_name(name: &str) -> String {
+    let mut snake = String::new();
+    let mut previous = '_';
+
+    for character in name.replace("::", "_").chars() {
+        if character.is_uppercase() && (previous.is_lowercase() || previous.is_ascii_digit()) {
+            snake.push('_');
+        }
+
+        snake.extend(character.to_lowercase());
+        previous = character;
+    }
+
+    snake
+}
+
+/// The field of the world holding the table of a data.
+fn table_name(data_name: &str) -> String {
+    rust_name(&snake_name(data_name))
+}
+
+/// Rust keywords become raw identifiers, except those that cannot be, which get an underscore.
+fn rust_name(name: &str) -> String {
+    let name = name.replace("::", "_");
+
+    match name.as_str() {
+        "self" | "Self" | "super" | "crate" | "_" => format!("{}_", name),
+        _ if RUST_KEYWORDS.contains(&name.as_str()) => format!("r#{}", name),
+        _ => name,
+    }
+}
+
+/// A variant keeps its own name, without the module it comes from.
+fn variant_ident(variant_name: &str) -> String {
+    rust_name(variant_name.rsplit("::").next().unwrap_or(variant_name))
+}
+
+fn variant_path(enum_name: &str, variant_name: &str) -> String {
+    format!("{}::{}", type_name(enum_name), variant_ident(variant_name))
+}
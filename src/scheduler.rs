@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::access_analysis::DataAccess;
+use crate::diagnostic::Diagnostic;
+use crate::runtime::{FieldWrites, IterationBuffer, Storage, Table, World, WorldShare};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExecutionMode {
+    /// Actions that do not conflict run at the same time, each on its own thread.
+    Parallel,
+    /// Actions run one after the other in the order of the run block.
+    Sequential,
+}
+
+/// Splits the actions of a run block into stages whose actions can run at the same time.
+/// Each action goes in the earliest stage after every earlier action it conflicts with,
+/// so conflicting actions keep the order of the run block and the result matches a sequential run.
+pub fn schedule(actions_to_do: &[String], accesses: &HashMap<String, DataAccess>, mode: ExecutionMode) -> Vec<Vec<String>> {
+    if mode == ExecutionMode::Sequential {
+        return actions_to_do.iter().map(|action_to_do| vec![action_to_do.clone()]).collect();
+    }
+
+    let mut stages: Vec<Vec<String>> = Vec::new();
+    let mut action_stages: Vec<(&DataAccess, usize)> = Vec::new();
+
+    for action_to_do in actions_to_do {
+        let access = &accesses[action_to_do];
+
+        let stage = action_stages
+            .iter()
+            .filter(|(other_access, _)| access.conflicts_with(other_access))
+            .map(|(_, other_stage)| other_stage + 1)
+            .max()
+            .unwrap_or(0);
+
+        if stage == stages.len() {
+            stages.push(Vec::new());
+        }
+
+        stages[stage].push(action_to_do.clone());
+        action_stages.push((access, stage));
+    }
+
+    stages
+}
+
+/// Runs each action of a stage on its own thread, with the tables it writes moved out of the world until they all finish.
+/// A stage of a single action runs on the whole world, which lets it create entities.
+/// When several actions fail, the error of the first one in the run block is reported.
+pub fn execute_stage<F>(
+    world: &mut World,
+    stage: &[String],
+    accesses: &HashMap<String, DataAccess>,
+    execute_action: &F
+) -> Result<(), Diagnostic>
+where
+    F: Fn(&str, &mut dyn Storage) -> Result<(), Diagnostic> + Sync,
+{
+    if let [action_to_do] = stage {
+        return execute_action(action_to_do, world);
+    }
+
+    let written_tables: Vec<HashMap<String, Table>> = stage
+        .iter()
+        .map(|action_to_do| {
+            accesses[action_to_do].writes
+                .iter()
+                .map(|data_name| (data_name.clone(), world.tables.remove(data_name).unwrap()))
+                .collect()
+        })
+        .collect();
+
+    let shared_world = &*world;
+
+    let results: Vec<(HashMap<String, Table>, Result<(), Diagnostic>)> = thread::scope(|scope| {
+        let threads: Vec<_> = stage
+            .iter()
+            .zip(written_tables)
+            .map(|(action_to_do, tables)| scope.spawn(move || {
+                let mut share = WorldShare::new(shared_world, tables);
+                let result = execute_action(action_to_do, &mut share);
+
+                (share.into_tables(), result)
+            }))
+            .collect();
+
+        threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+    });
+
+    let mut stage_result = Ok(());
+
+    for (tables, result) in results {
+        world.tables.extend(tables);
+        stage_result = stage_result.and(result);
+    }
+
+    stage_result
+}
+
+/// Splits the iterations of a parallel foreach into one chunk per thread. Chunks read the tables as they were
+/// before the loop and their writes are applied afterwards, chunk by chunk, up to the chunk of the first error.
+pub fn execute_in_chunks<T, F>(storage: &mut dyn Storage, iterations: &[T], execute_chunk: &F) -> Result<(), Diagnostic>
+where
+    T: Sync,
+    F: Fn(&mut dyn Storage, &[T]) -> Result<(), Diagnostic> + Sync,
+{
+    let thread_count = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk_size = iterations.len().div_ceil(thread_count).max(1);
+    let shared_storage = &*storage;
+
+    let results: Vec<(FieldWrites, Result<(), Diagnostic>)> = thread::scope(|scope| {
+        let threads: Vec<_> = iterations
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || {
+                let mut buffer = IterationBuffer::new(shared_storage);
+                let result = execute_chunk(&mut buffer, chunk);
+
+                (buffer.into_writes(), result)
+            }))
+            .collect();
+
+        threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+    });
+
+    for (writes, result) in results {
+        for ((data_name, row, field_index), value) in writes {
+            storage.set_field(&data_name, row, field_index, value);
+        }
+
+        result?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access_analysis::analyze_accesses;
+    use crate::ast::ASTNode;
+    use crate::ast_parser::parse_ast;
+    use crate::bytecode_compiler::compile_program;
+    use crate::interpreter::interpret;
+    use crate::tokenizer::tokenize;
+    use crate::vm::run_bytecode;
+
+    /// Move and Damage write different data, while Heal reads what Move writes and writes what Damage writes.
+    const ACTIONS: &str = r#"
+        data Position { x: float, y: float }
+        data Velocity { dx: float, dy: float }
+        data Health { hp: int }
+        group Mover(x: float, dx: float) { Position(x = x, y = 0.0), Velocity(dx = dx, dy = 1.0), Health(hp = 10) }
+        do Move { foreach p, v in Position, Velocity { p.x = p.x + v.dx  p.y = p.y + v.dy } }
+        do Damage { foreach h in Health { h.hp = h.hp - 1 } }
+        do Heal { foreach p, h in Position, Health { if p.x > 50.0 { h.hp = h.hp + 100 } } }
+        run (Move, Damage, Heal) {
+            for let i = 0; i < 100; i = i + 1 { create Mover(i, 0.5) }
+        }
+        run (Heal, Damage, Move) { }
+    "#;
+
+    /// Enough entities that each loop is split into several chunks, and a write only some iterations make.
+    const PARALLEL_LOOPS: &str = r#"
+        data Position { x: float, y: float }
+        data Velocity { dx: float, dy: float }
+        group Mover(x: float, dx: float) { Position(x = x, y = 0.0), Velocity(dx = dx, dy = 1.0) }
+        do Move {
+            parallel foreach p, v in Position, Velocity {
+                p.x = p.x + v.dx
+                if p.x > 500.0 { p.y = p.y + v.dy }
+            }
+        }
+        do Accelerate { parallel foreach v in Velocity { v.dx = v.dx * 2 } }
+        run (Move, Accelerate) {
+            for let i = 0; i < 1000; i = i + 1 { create Mover(i, 0.5) }
+        }
+        run (Move, Accelerate) { }
+    "#;
+
+    fn parse(source: &str) -> ASTNode {
+        parse_ast(tokenize(source.to_string()))
+    }
+
+    fn stages(actions_to_do: &[&str], mode: ExecutionMode) -> Vec<Vec<String>> {
+        let accesses = analyze_accesses(&parse(ACTIONS));
+        let actions_to_do: Vec<String> = actions_to_do.iter().map(|action_to_do| action_to_do.to_string()).collect();
+
+        schedule(&actions_to_do, &accesses, mode)
+    }
+
+    /// The world the interpreter and the VM each give, which must be the same.
+    fn run(program: &ASTNode, mode: ExecutionMode) -> String {
+        let interpreted_world = interpret(program, mode).unwrap().to_string();
+        let executed_world = run_bytecode(&compile_program(program), mode).unwrap().to_string();
+
+        assert_eq!(interpreted_world, executed_world);
+        interpreted_world
+    }
+
+    #[test]
+    fn non_conflicting_actions_share_a_stage() {
+        assert_eq!(stages(&["Move", "Damage", "Heal"], ExecutionMode::Parallel), vec![vec!["Move", "Damage"], vec!["Heal"]]);
+    }
+
+    #[test]
+    fn conflicting_actions_keep_run_block_order() {
+        assert_eq!(stages(&["Heal", "Move"], ExecutionMode::Parallel), vec![vec!["Heal"], vec!["Move"]]);
+        assert_eq!(stages(&["Heal", "Damage", "Move"], ExecutionMode::Parallel), vec![vec!["Heal"], vec!["Damage", "Move"]]);
+    }
+
+    #[test]
+    fn sequential_mode_runs_one_action_per_stage() {
+        assert_eq!(stages(&["Move", "Damage", "Heal"], ExecutionMode::Sequential), vec![vec!["Move"], vec!["Damage"], vec!["Heal"]]);
+    }
+
+    #[test]
+    fn sequential_and_parallel_runs_give_the_same_world() {
+        let program = parse(ACTIONS);
+
+        assert_eq!(run(&program, ExecutionMode::Sequential), run(&program, ExecutionMode::Parallel));
+    }
+
+    #[test]
+    fn parallel_foreach_gives_the_same_world_as_foreach() {
+        let parallel_program = parse(PARALLEL_LOOPS);
+        let sequential_program = parse(&PARALLEL_LOOPS.replace("parallel foreach", "foreach"));
+
+        assert_eq!(run(&parallel_program, ExecutionMode::Sequential), run(&sequential_program, ExecutionMode::Sequential));
+    }
+}
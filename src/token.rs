@@ -1,19 +1,29 @@
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Data,
     Group,
     Do,
     Run,
+    Enum,
+    Import,
+    Use,
     LBrace,
     RBrace,
     LParenthesis,
     RParenthesis,
+    LBracket,
+    RBracket,
     Colon,
+    DoubleColon,
     Semicolon,
     In,
     DoubleQuote,
     SingleQuote,
     Equal,
+    DoubleEqual,
+    NotEqual,
     GreaterThan,
     LessThan,
     GreaterOrEqual,
@@ -25,12 +35,18 @@ pub enum Token {
     ModSign,
     Dot,
     Comma,
+    QuestionMark,
+    Hash,
     CreateInstructionKeyword,
     Let,
     If,
     Foreach,
+    Parallel,
     For,
+    Match,
+    FatArrow,
     Symbol(String),
+    StringLiteral(String),
 }
 
 impl Token {
@@ -40,16 +56,24 @@ impl Token {
             Token::Group                      => "group".to_string(),
             Token::Do                         => "do".to_string(),
             Token::Run                        => "run".to_string(),
+            Token::Enum                       => "enum".to_string(),
+            Token::Import                     => "import".to_string(),
+            Token::Use                        => "use".to_string(),
             Token::LBrace                     => "{".to_string(),
             Token::RBrace                     => "}".to_string(),
             Token::LParenthesis               => "(".to_string(),
             Token::RParenthesis               => ")".to_string(),
+            Token::LBracket                   => "[".to_string(),
+            Token::RBracket                   => "]".to_string(),
             Token::Colon                      => ":".to_string(),
+            Token::DoubleColon                => "::".to_string(),
             Token::Semicolon                  => ";".to_string(),
             Token::In                         => "in".to_string(),
             Token::DoubleQuote                => "\"".to_string(),
             Token::SingleQuote                => "'".to_string(),
             Token::Equal                      => "=".to_string(),
+            Token::DoubleEqual                => "==".to_string(),
+            Token::NotEqual                   => "!=".to_string(),
             Token::GreaterThan                => ">".to_string(),
             Token::LessThan                   => "<".to_string(),
             Token::GreaterOrEqual             => ">=".to_string(),
@@ -61,12 +85,18 @@ impl Token {
             Token::ModSign                    => "%".to_string(),
             Token::Dot                        => ".".to_string(),
             Token::Comma                      => ",".to_string(),
+            Token::QuestionMark               => "?".to_string(),
+            Token::Hash                       => "#".to_string(),
             Token::CreateInstructionKeyword   => "create".to_string(),
             Token::Let                        => "let".to_string(),
             Token::If                         => "if".to_string(),
             Token::Foreach                    => "foreach".to_string(),
+            Token::Parallel                   => "parallel".to_string(),
             Token::For                        => "for".to_string(),
+            Token::Match                      => "match".to_string(),
+            Token::FatArrow                   => "=>".to_string(),
             Token::Symbol(value)     => value.clone(),
+            Token::StringLiteral(value) => format!("\"{}\"", value),
         }
     }
 
@@ -76,16 +106,24 @@ impl Token {
             Token::Group,
             Token::Do,
             Token::Run,
+            Token::Enum,
+            Token::Import,
+            Token::Use,
             Token::LBrace,
             Token::RBrace,
             Token::LParenthesis,
             Token::RParenthesis,
+            Token::LBracket,
+            Token::RBracket,
             Token::Colon,
+            Token::DoubleColon,
             Token::Semicolon,
             Token::In,
             Token::DoubleQuote,
             Token::SingleQuote,
             Token::Equal,
+            Token::DoubleEqual,
+            Token::NotEqual,
             Token::GreaterThan,
             Token::LessThan,
             Token::GreaterOrEqual,
@@ -97,11 +135,29 @@ impl Token {
             Token::ModSign,
             Token::Dot,
             Token::Comma,
+            Token::QuestionMark,
+            Token::Hash,
             Token::CreateInstructionKeyword,
             Token::Let,
             Token::If,
             Token::Foreach,
+            Token::Parallel,
             Token::For,
+            Token::Match,
+            Token::FatArrow,
         ].to_vec()
     }
 }
+
+/// Where a token starts in its source file. Lines and columns count from 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
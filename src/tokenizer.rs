@@ -1,59 +1,90 @@
-use crate::token::Token;
-
-pub fn tokenize(source_code_raw: String) -> Vec<Token> {
-    let source_code = &source_code_raw
-        .lines()
-        .map(
-            |line|
-            line.split("//").next().unwrap_or("").trim()
-        )
-        .collect::<Vec<&str>>()
-        .join(" ")
-        .trim()
-        .to_string();
+use crate::token::{Location, Token};
+
+/// Splits source code into tokens, each with the location it starts at. Comments run from // to the end of the line,
+/// and lines are joined by a space.
+pub fn tokenize(source_code: String) -> Vec<(Token, Location)> {
+    let mut characters = Vec::new();
+    let mut locations = Vec::new();
+
+    for (line_index, line) in source_code.lines().enumerate() {
+        let code = line.split("//").next().unwrap_or("");
+        let indentation = code.chars().take_while(|character| character.is_whitespace()).count();
+
+        for (column_index, character) in code.trim().chars().enumerate() {
+            characters.push(character);
+            locations.push(Location { line: line_index + 1, column: indentation + column_index + 1 });
+        }
+
+        characters.push(' ');
+        locations.push(Location { line: line_index + 1, column: code.chars().count() + 1 });
+    }
 
     let mut tokens = Vec::new();
 
     let mut cursor = 0;
-    while cursor < source_code.len() {
-        let mut current_character = source_code.chars().nth(cursor).unwrap();
+    while cursor < characters.len() {
+        let current_character = characters[cursor];
+        let location = locations[cursor];
 
-        if current_character == ' ' {
+        if current_character.is_whitespace() {
             cursor += 1;
             continue;
         }
 
-        for possible_token in Token::iter_static() {
-            let left = cursor;
-            let right = cursor + possible_token.get_value().len();
+        if current_character == '"' {
+            let mut string_value = "".to_string();
+            cursor += 1;
 
-            if right > source_code.len() {
-                continue;
+            while cursor < characters.len() && characters[cursor] != '"' {
+                string_value.push(characters[cursor]);
+                cursor += 1;
             }
 
-            if &source_code[left..right] == possible_token.get_value() {
-                tokens.push(possible_token.clone());
-                
-                if right < source_code.len() {
-                    cursor = right;
-                    current_character = source_code.chars().nth(cursor).unwrap();
-                }
+            if cursor == characters.len() {
+                panic!("Unterminated string literal at {}: \"{}", location, string_value.trim_end());
             }
+
+            cursor += 1;
+            tokens.push((Token::StringLiteral(string_value), location));
+            continue;
         }
 
-        if current_character.is_alphanumeric() {
+        if is_symbol_character(current_character) {
             let mut symbol_name = "".to_string();
-            while current_character.is_alphanumeric() {
-                symbol_name.push(current_character);
+            while cursor < characters.len() && is_symbol_character(characters[cursor]) {
+                symbol_name.push(characters[cursor]);
                 cursor += 1;
-                current_character = source_code.chars().nth(cursor).unwrap();
             }
-            tokens.push(Token::Symbol(symbol_name));
+
+            let keyword = Token::iter_static()
+                .into_iter()
+                .find(|possible_token| possible_token.get_value() == symbol_name);
+
+            tokens.push((keyword.unwrap_or(Token::Symbol(symbol_name)), location));
+            continue;
         }
-        else {
-            cursor += 1;
+
+        let longest_match = Token::iter_static()
+            .into_iter()
+            .filter(|possible_token| !possible_token.get_value().starts_with(is_symbol_character))
+            .filter(|possible_token| {
+                let value: Vec<char> = possible_token.get_value().chars().collect();
+                characters[cursor..].starts_with(&value)
+            })
+            .max_by_key(|possible_token| possible_token.get_value().len());
+
+        match longest_match {
+            Some(possible_token) => {
+                cursor += possible_token.get_value().chars().count();
+                tokens.push((possible_token, location));
+            },
+            None => panic!("Unknown character {} at {}.", current_character, location),
         }
     }
 
     tokens
 }
+
+fn is_symbol_character(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
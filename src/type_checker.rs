@@ -0,0 +1,611 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ast::{ASTNode, TypeExpr};
+use crate::diagnostic::Diagnostic;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    String,
+    Data(String),
+    Enum(String),
+    List(Box<Type>),
+    Array(Box<Type>, usize),
+    Map(Box<Type>, Box<Type>),
+    Optional(Box<Type>),
+    Unknown,
+}
+
+impl Type {
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Float)
+    }
+
+    /// Tells whether a value of the given type can be stored where this type is expected.
+    /// Integers widen to floats, any value fits in an optional of its type and arrays fit in lists.
+    pub fn accepts(&self, actual: &Type) -> bool {
+        match (self, actual) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Float, Type::Int) => true,
+            (Type::Optional(expected), Type::Optional(actual)) => expected.accepts(actual),
+            (Type::Optional(expected), actual) => expected.accepts(actual),
+            (Type::List(expected), Type::List(actual) | Type::Array(actual, _)) => expected.accepts(actual),
+            (Type::Array(expected, expected_length), Type::Array(actual, actual_length)) => {
+                expected_length == actual_length && expected.accepts(actual)
+            },
+            (expected, actual) => expected == actual,
+        }
+    }
+
+    /// Type of the elements produced when iterating over a value of this type.
+    pub fn element_type(&self) -> Option<Type> {
+        match self {
+            Type::List(element_type) | Type::Array(element_type, _) => Some(*element_type.clone()),
+            Type::Unknown => Some(Type::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::String => write!(f, "string"),
+            Type::Data(name) | Type::Enum(name) => write!(f, "{}", name),
+            Type::List(element_type) => write!(f, "List<{}>", element_type),
+            Type::Array(element_type, length) => write!(f, "[{}; {}]", element_type, length),
+            Type::Map(key_type, value_type) => write!(f, "Map<{}, {}>", key_type, value_type),
+            Type::Optional(inner_type) => write!(f, "Option<{}>", inner_type),
+            Type::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+pub struct TypeChecker {
+    pub data_fields: HashMap<String, Vec<(String, Type)>>,
+    pub variants: HashMap<String, (String, Vec<(String, Type)>)>,
+    pub group_parameters: HashMap<String, Vec<(String, Type)>>,
+    data_names: Vec<String>,
+    enum_names: Vec<String>,
+    scopes: Vec<HashMap<String, Type>>,
+    pub context: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+pub fn check_types(program: &ASTNode) -> Vec<Diagnostic> {
+    let mut type_checker = TypeChecker::new(program);
+
+    if let ASTNode::Program(statements) = program {
+        for statement in statements {
+            type_checker.check_statement(statement);
+        }
+    }
+
+    type_checker.diagnostics
+}
+
+impl TypeChecker {
+    pub fn new(program: &ASTNode) -> TypeChecker {
+        let mut type_checker = TypeChecker {
+            data_fields: HashMap::new(),
+            variants: HashMap::new(),
+            group_parameters: HashMap::new(),
+            data_names: Vec::new(),
+            enum_names: Vec::new(),
+            scopes: Vec::new(),
+            context: "".to_string(),
+            diagnostics: Vec::new(),
+        };
+
+        if let ASTNode::Program(statements) = program {
+            for statement in statements {
+                match statement.as_ref() {
+                    ASTNode::Data(name, _, _) => type_checker.data_names.push(name.clone()),
+                    ASTNode::Enum(name, _, _) => type_checker.enum_names.push(name.clone()),
+                    _ => (),
+                }
+            }
+
+            for statement in statements {
+                type_checker.collect_declaration(statement);
+            }
+        }
+
+        type_checker
+    }
+
+    fn collect_declaration(&mut self, statement: &ASTNode) {
+        match statement {
+            ASTNode::Data(name, fields, _) => {
+                self.context = format!("data {}", name);
+                let fields = self.typed_names(fields);
+                self.data_fields.insert(name.clone(), fields);
+            },
+            ASTNode::Enum(name, variants, _) => {
+                self.context = format!("enum {}", name);
+
+                for variant in variants {
+                    if let ASTNode::Variant(variant_name, fields, _) = variant.as_ref() {
+                        let fields = self.typed_names(fields);
+                        self.variants.insert(variant_name.clone(), (name.clone(), fields));
+                    }
+                }
+            },
+            ASTNode::Group(name, parameters, _, _) => {
+                self.context = format!("group {}", name);
+                let parameters = self.typed_names(parameters);
+                self.group_parameters.insert(name.clone(), parameters);
+            },
+            _ => (),
+        }
+    }
+
+    fn typed_names(&mut self, nodes: &[Box<ASTNode>]) -> Vec<(String, Type)> {
+        nodes
+            .iter()
+            .filter_map(|node| match node.as_ref() {
+                ASTNode::Field(name, type_expr, default_value, _) => {
+                    let field_type = self.type_of(type_expr);
+
+                    if let Some(default_value) = default_value {
+                        let default_type = self.infer_type(default_value);
+
+                        if !field_type.accepts(&default_type) {
+                            self.error(format!(
+                                "Default value of field {} expects {} but got {}.",
+                                name,
+                                field_type,
+                                default_type
+                            ));
+                        }
+                    }
+
+                    Some((name.clone(), field_type))
+                },
+                ASTNode::Parameter(name, type_expr, _) => Some((name.clone(), self.type_of(type_expr))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn type_of(&mut self, type_expr: &TypeExpr) -> Type {
+        match type_expr {
+            TypeExpr::Named(name) => match name.as_str() {
+                "int" | "i32" | "i64" | "u32" | "u64" => Type::Int,
+                "float" | "f32" | "f64" => Type::Float,
+                "bool" => Type::Bool,
+                "string" => Type::String,
+                _ if self.data_names.contains(name) => Type::Data(name.clone()),
+                _ if self.enum_names.contains(name) => Type::Enum(name.clone()),
+                _ => Type::Unknown,
+            },
+            TypeExpr::Generic(name, arguments) => {
+                let arguments: Vec<Type> = arguments.iter().map(|argument| self.type_of(argument)).collect();
+
+                match (name.as_str(), arguments.as_slice()) {
+                    ("List", [element_type]) => Type::List(Box::new(element_type.clone())),
+                    ("Map", [key_type, value_type]) => Type::Map(Box::new(key_type.clone()), Box::new(value_type.clone())),
+                    ("List", _) | ("Map", _) => {
+                        let expected_count = if name == "List" { 1 } else { 2 };
+                        self.error(format!(
+                            "{} expects {} type argument(s) but got {}.",
+                            name,
+                            expected_count,
+                            arguments.len()
+                        ));
+                        Type::Unknown
+                    },
+                    _ => Type::Unknown,
+                }
+            },
+            TypeExpr::Array(element_type, length) => Type::Array(Box::new(self.type_of(element_type)), *length),
+            TypeExpr::Optional(inner_type) => Type::Optional(Box::new(self.type_of(inner_type))),
+        }
+    }
+
+    pub fn check_statement(&mut self, statement: &ASTNode) {
+        match statement {
+            ASTNode::Do(name, instructions, _) => {
+                self.context = format!("do {}", name);
+                self.check_block(instructions);
+            },
+            ASTNode::Run(actions_to_do, instructions) => {
+                self.context = format!("run ({})", actions_to_do.join(", "));
+                self.check_block(instructions);
+            },
+            _ => (),
+        }
+    }
+
+    pub fn check_block(&mut self, instructions: &[Box<ASTNode>]) {
+        self.scopes.push(HashMap::new());
+
+        for instruction in instructions {
+            self.check_instruction(instruction);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn check_instruction(&mut self, instruction: &ASTNode) {
+        match instruction {
+            ASTNode::Declaration(variable_name, value) => {
+                let value_type = self.infer_type(value);
+                self.declare(variable_name, value_type);
+            },
+            ASTNode::Assignment(target, value) => {
+                let variable_name = target.split('.').next().unwrap();
+
+                if !self.scopes.iter().any(|scope| scope.contains_key(variable_name)) {
+                    self.error(format!("Cannot assign to {}, which is not a variable.", target));
+                    return;
+                }
+
+                let target_type = self.infer_value(target);
+                let value_type = self.infer_type(value);
+
+                if !target_type.accepts(&value_type) {
+                    self.error(format!("Cannot assign a value of type {} to {} of type {}.", value_type, target, target_type));
+                }
+            },
+            ASTNode::If(condition, instructions) => {
+                self.expect_bool(condition, "if condition");
+                self.check_block(instructions);
+            },
+            ASTNode::For(declaration, condition, progression, instructions) => {
+                self.scopes.push(HashMap::new());
+                self.check_instruction(declaration);
+                self.expect_bool(condition, "for condition");
+                self.check_instruction(progression);
+                self.check_block(instructions);
+                self.scopes.pop();
+            },
+            ASTNode::Foreach(values, collections, instructions, _) => {
+                let scope = self.check_foreach(values, collections);
+                self.scopes.push(scope);
+                self.check_block(instructions);
+                self.scopes.pop();
+            },
+            ASTNode::Match(value, arms) => {
+                let value_type = self.infer_type(value);
+
+                for arm in arms {
+                    if let ASTNode::MatchArm(pattern, instructions) = arm.as_ref() {
+                        self.scopes.push(HashMap::new());
+                        self.bind_pattern(pattern, &value_type);
+                        self.check_block(instructions);
+                        self.scopes.pop();
+                    }
+                }
+            },
+            ASTNode::CreateInstruction(group_name, parameter_values) => {
+                self.check_create_instruction(group_name, parameter_values);
+            },
+            node => {
+                self.infer_type(node);
+            },
+        }
+    }
+
+    /// Binds each name to the element type of its collection, and checks that the collections can be iterated together:
+    /// either all data, joined by entity, or all lists and arrays, zipped, in which case arrays must have the same length.
+    pub fn check_foreach(&mut self, values: &[String], collections: &[Box<ASTNode>]) -> HashMap<String, Type> {
+        if values.len() != collections.len() {
+            self.error(format!(
+                "Foreach binds {} name(s) but iterates over {} collection(s).",
+                values.len(),
+                collections.len()
+            ));
+        }
+
+        let mut scope = HashMap::new();
+        let mut data_count = 0;
+        let mut array_lengths: Vec<usize> = Vec::new();
+
+        for (index, collection) in collections.iter().enumerate() {
+            let collection_type = self.infer_type(collection);
+
+            match (collection.as_ref(), &collection_type) {
+                (ASTNode::Value(name), _) if self.data_names.contains(name) => data_count += 1,
+                (_, Type::Array(_, length)) => array_lengths.push(*length),
+                _ => (),
+            }
+
+            let element_type = match collection_type.element_type() {
+                Some(element_type) => element_type,
+                None => {
+                    self.error(format!("Cannot iterate over a value of type {} in foreach.", collection_type));
+                    Type::Unknown
+                },
+            };
+
+            if let Some(value) = values.get(index) {
+                scope.insert(value.clone(), element_type);
+            }
+        }
+
+        if data_count > 0 && data_count < collections.len() {
+            self.error("Foreach cannot iterate over data and lists together, data are joined by entity while lists are zipped.".to_string());
+        }
+
+        if let Some(length) = array_lengths.iter().find(|length| **length != array_lengths[0]) {
+            self.error(format!("Foreach zips arrays of different lengths, {} and {}.", array_lengths[0], length));
+        }
+
+        scope
+    }
+
+    fn check_create_instruction(&mut self, group_name: &str, parameter_values: &[Box<ASTNode>]) {
+        let parameters = self.group_parameters.get(group_name).cloned().unwrap_or_default();
+
+        for (index, parameter_value) in parameter_values.iter().enumerate() {
+            let (parameter, value) = match parameter_value.as_ref() {
+                ASTNode::NamedArgument(parameter_name, value) => {
+                    (parameters.iter().find(|(name, _)| name == parameter_name), value.as_ref())
+                },
+                value => (parameters.get(index), value),
+            };
+
+            let value_type = self.infer_type(value);
+
+            if let Some((parameter_name, parameter_type)) = parameter {
+                if !parameter_type.accepts(&value_type) {
+                    self.error(format!(
+                        "Parameter {} of group {} expects {} but got {}.",
+                        parameter_name,
+                        group_name,
+                        parameter_type,
+                        value_type
+                    ));
+                }
+            }
+        }
+    }
+
+    fn bind_pattern(&mut self, pattern: &ASTNode, matched_type: &Type) {
+        match pattern {
+            ASTNode::BindingPattern(name) => self.declare(name, matched_type.clone()),
+            ASTNode::LiteralPattern(literal) => {
+                let literal_type = self.infer_value(literal);
+
+                if !matched_type.accepts(&literal_type) {
+                    self.error(format!("Pattern {} of type {} cannot match a value of type {}.", literal, literal_type, matched_type));
+                }
+            },
+            ASTNode::VariantPattern(name, field_patterns) => {
+                let pattern_type = match self.variants.get(name) {
+                    Some((enum_name, _)) => Type::Enum(enum_name.clone()),
+                    None => Type::Data(name.clone()),
+                };
+
+                if !matched_type.accepts(&pattern_type) {
+                    self.error(format!("Pattern {} of type {} cannot match a value of type {}.", name, pattern_type, matched_type));
+                }
+
+                let field_types: Vec<Type> = match self.variants.get(name) {
+                    Some((_, fields)) => fields.iter().map(|(_, field_type)| field_type.clone()).collect(),
+                    None => Vec::new(),
+                };
+
+                for (index, field_pattern) in field_patterns.iter().enumerate() {
+                    let field_type = field_types.get(index).cloned().unwrap_or(Type::Unknown);
+                    self.bind_pattern(field_pattern, &field_type);
+                }
+            },
+            ASTNode::DataPattern(name, field_patterns) => {
+                let pattern_type = Type::Data(name.clone());
+
+                if !matched_type.accepts(&pattern_type) {
+                    self.error(format!("Pattern {} cannot match a value of type {}.", name, matched_type));
+                }
+
+                for field_pattern in field_patterns {
+                    if let ASTNode::FieldPattern(field_name, sub_pattern) = field_pattern.as_ref() {
+                        let field_type = self.field_type(name, field_name).unwrap_or(Type::Unknown);
+                        self.bind_pattern(sub_pattern, &field_type);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn expect_bool(&mut self, condition: &ASTNode, description: &str) {
+        let condition_type = self.infer_type(condition);
+
+        if !Type::Bool.accepts(&condition_type) {
+            self.error(format!("The {} must be a bool but is {}.", description, condition_type));
+        }
+    }
+
+    pub fn infer_type(&mut self, node: &ASTNode) -> Type {
+        match node {
+            ASTNode::Value(value) => self.infer_value(value),
+            ASTNode::Sum(lhs, rhs) => self.infer_arithmetic("+", lhs, rhs),
+            ASTNode::Substraction(lhs, rhs) => self.infer_arithmetic("-", lhs, rhs),
+            ASTNode::Multiplication(lhs, rhs) => self.infer_arithmetic("*", lhs, rhs),
+            ASTNode::Division(lhs, rhs, _) => self.infer_arithmetic("/", lhs, rhs),
+            ASTNode::Modulo(lhs, rhs, _) => self.infer_arithmetic("%", lhs, rhs),
+            ASTNode::Equality(lhs, rhs) => self.infer_comparison("==", lhs, rhs),
+            ASTNode::Inequality(lhs, rhs) => self.infer_comparison("!=", lhs, rhs),
+            ASTNode::GreaterThan(lhs, rhs) => self.infer_comparison(">", lhs, rhs),
+            ASTNode::LessThan(lhs, rhs) => self.infer_comparison("<", lhs, rhs),
+            ASTNode::GreaterOrEqual(lhs, rhs) => self.infer_comparison(">=", lhs, rhs),
+            ASTNode::LessOrEqual(lhs, rhs) => self.infer_comparison("<=", lhs, rhs),
+            ASTNode::ListLiteral(elements) => self.infer_list_literal(elements),
+            ASTNode::VariantConstructor(variant_name, field_values) => self.infer_variant_constructor(variant_name, field_values),
+            _ => Type::Unknown,
+        }
+    }
+
+    /// A variant constructor gives one value per field of the variant, each of the type of its field.
+    fn infer_variant_constructor(&mut self, variant_name: &str, field_values: &[Box<ASTNode>]) -> Type {
+        let (enum_name, fields) = match self.variants.get(variant_name).cloned() {
+            Some(variant) => variant,
+            None => return Type::Unknown,
+        };
+
+        if field_values.len() != fields.len() {
+            self.error(format!("Variant {} has {} fields but got {} values.", variant_name, fields.len(), field_values.len()));
+        }
+
+        for (field_value, (field_name, field_type)) in field_values.iter().zip(&fields) {
+            let value_type = self.infer_type(field_value);
+
+            if !field_type.accepts(&value_type) {
+                self.error(format!(
+                    "Field {} of variant {} expects {} but got {}.",
+                    field_name, variant_name, field_type, value_type
+                ));
+            }
+        }
+
+        Type::Enum(enum_name)
+    }
+
+    /// A list literal is an array of its length, whose elements all have the same type or widen to it.
+    fn infer_list_literal(&mut self, elements: &[Box<ASTNode>]) -> Type {
+        let mut element_type = Type::Unknown;
+
+        for element in elements {
+            let current_type = self.infer_type(element);
+
+            let widens = current_type.accepts(&element_type) && !element_type.accepts(&current_type);
+
+            if element_type == Type::Unknown || widens {
+                element_type = current_type;
+            }
+            else if !element_type.accepts(&current_type) {
+                self.error(format!("List elements must have the same type but found {} and {}.", element_type, current_type));
+            }
+        }
+
+        Type::Array(Box::new(element_type), elements.len())
+    }
+
+    fn infer_arithmetic(&mut self, operator: &str, lhs: &ASTNode, rhs: &ASTNode) -> Type {
+        let lhs_type = self.infer_type(lhs);
+        let rhs_type = self.infer_type(rhs);
+
+        match (&lhs_type, &rhs_type) {
+            (Type::Unknown, _) | (_, Type::Unknown) => Type::Unknown,
+            (Type::Int, Type::Int) => Type::Int,
+            (lhs_type, rhs_type) if lhs_type.is_numeric() && rhs_type.is_numeric() => Type::Float,
+            (Type::String, Type::String) if operator == "+" => Type::String,
+            _ => {
+                self.error(format!("Cannot apply {} to {} and {}.", operator, lhs_type, rhs_type));
+                Type::Unknown
+            },
+        }
+    }
+
+    fn infer_comparison(&mut self, operator: &str, lhs: &ASTNode, rhs: &ASTNode) -> Type {
+        let lhs_type = self.infer_type(lhs);
+        let rhs_type = self.infer_type(rhs);
+
+        let comparable = match operator {
+            "==" | "!=" => lhs_type.accepts(&rhs_type) || rhs_type.accepts(&lhs_type),
+            _ => {
+                (lhs_type.is_numeric() || lhs_type == Type::Unknown) && (rhs_type.is_numeric() || rhs_type == Type::Unknown)
+            },
+        };
+
+        if !comparable {
+            self.error(format!("Cannot compare {} and {} with {}.", lhs_type, rhs_type, operator));
+        }
+
+        Type::Bool
+    }
+
+    /// Infers the type of a literal or of a name followed by field accesses, as p.x.
+    pub fn infer_value(&mut self, value: &str) -> Type {
+        if value.is_empty() {
+            return Type::Unknown;
+        }
+
+        if value.starts_with('"') {
+            return Type::String;
+        }
+
+        if value == "true" || value == "false" {
+            return Type::Bool;
+        }
+
+        if value.trim_start_matches('-').starts_with(|character: char| character.is_ascii_digit()) {
+            return match value.contains('.') {
+                true  => Type::Float,
+                false => Type::Int,
+            };
+        }
+
+        let mut segments = value.split('.');
+        let name = segments.next().unwrap();
+
+        let mut value_type = match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some(local_type) => local_type.clone(),
+            None if self.data_names.iter().any(|data_name| data_name == name) => {
+                Type::List(Box::new(Type::Data(name.to_string())))
+            },
+            None => match self.variants.get(name).cloned() {
+                Some((enum_name, fields)) => {
+                    if !fields.is_empty() {
+                        self.error(format!("Variant {} has fields, its values must be given as in {}(...).", name, name));
+                    }
+                    Type::Enum(enum_name)
+                },
+                None => Type::Unknown,
+            },
+        };
+
+        for field_name in segments {
+            value_type = match &value_type {
+                Type::Data(data_name) => match self.field_type(data_name, field_name) {
+                    Some(field_type) => field_type,
+                    None => {
+                        self.error(format!("Data {} has no field named {}.", data_name, field_name));
+                        Type::Unknown
+                    },
+                },
+                Type::Unknown => Type::Unknown,
+                other => {
+                    self.error(format!("Cannot access field {} on a value of type {}.", field_name, other));
+                    Type::Unknown
+                },
+            };
+        }
+
+        value_type
+    }
+
+    pub fn field_type(&self, data_name: &str, field_name: &str) -> Option<Type> {
+        self.data_fields
+            .get(data_name)?
+            .iter()
+            .find(|(name, _)| name == field_name)
+            .map(|(_, field_type)| field_type.clone())
+    }
+
+    pub fn enter_scope(&mut self, scope: HashMap<String, Type>) {
+        self.scopes.push(scope);
+    }
+
+    pub fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    pub fn declare(&mut self, name: &str, value_type: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), value_type);
+        }
+    }
+
+    fn error(&mut self, message: String) {
+        self.diagnostics.push(Diagnostic::error(format!("In {}: {}", self.context, message)));
+    }
+}
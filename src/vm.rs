@@ -0,0 +1,269 @@
+use crate::bytecode::{BytecodeProgram, Function, Instruction};
+use crate::diagnostic::Diagnostic;
+use crate::runtime::{apply_arithmetic, compare, new_list, values_equal, Storage, Value, World};
+use crate::scheduler::{execute_in_chunks, execute_stage, schedule, ExecutionMode};
+
+struct Vm<'p, 's> {
+    program: &'p BytecodeProgram,
+    world: &'s mut dyn Storage,
+    context: &'p str,
+}
+
+/// Executes every run block in order, as the interpreter does, with each function on a stack of its own.
+pub fn run_bytecode(program: &BytecodeProgram, mode: ExecutionMode) -> Result<World, Diagnostic> {
+    let mut world = World::default();
+
+    for (data_name, columns) in &program.tables {
+        world.add_table(data_name, columns.clone());
+    }
+
+    for run_block in &program.run_blocks {
+        let function = &program.functions[run_block.function];
+        Vm::new(program, &mut world, &function.name).call(function, Vec::new())?;
+
+        for stage in schedule(&run_block.actions_to_do, &program.accesses, mode) {
+            execute_stage(&mut world, &stage, &program.accesses, &|action_to_do, storage| {
+                let function = &program.functions[program.actions[action_to_do]];
+                Vm::new(program, storage, &function.name).call(function, Vec::new())
+            })?;
+        }
+    }
+
+    Ok(world)
+}
+
+impl<'p, 's> Vm<'p, 's> {
+    fn new(program: &'p BytecodeProgram, world: &'s mut dyn Storage, context: &'p str) -> Vm<'p, 's> {
+        Vm { program, world, context }
+    }
+
+    fn call(&mut self, function: &'p Function, arguments: Vec<Value>) -> Result<(), Diagnostic> {
+        let mut locals = vec![Value::None; function.local_count];
+
+        for ((local, argument), parameter_type) in locals.iter_mut().zip(arguments).zip(&function.parameter_types) {
+            *local = parameter_type.convert(argument);
+        }
+
+        self.execute(function, &mut locals, 0)
+    }
+
+    /// Executes instructions from the given position until the function returns or, in a parallel loop, the iteration ends.
+    fn execute(&mut self, function: &'p Function, locals: &mut [Value], start: usize) -> Result<(), Diagnostic> {
+        let mut stack: Vec<Value> = Vec::new();
+        // The values to bind for each iteration of the loops being run, and the next iteration of each.
+        let mut loops: Vec<(Vec<Vec<Value>>, usize)> = Vec::new();
+        let mut position = start;
+
+        loop {
+            let instruction = function.code[position];
+            position += 1;
+
+            match instruction {
+                Instruction::Constant(index) => stack.push(function.constants[index].clone()),
+                Instruction::Pop => {
+                    stack.pop();
+                },
+                Instruction::LoadLocal(local) => stack.push(locals[local].clone()),
+                Instruction::StoreLocal(local) => locals[local] = stack.pop().unwrap(),
+                Instruction::LoadField(name) => {
+                    let field_name = &function.names[name];
+
+                    match stack.pop().unwrap() {
+                        Value::Instance(data_name, row) => {
+                            let field_index = self.field_index(&data_name, field_name)?;
+                            stack.push(self.world.get_field(&data_name, row, field_index));
+                        },
+                        other => return Err(self.error(format!("Cannot read field {} of {}.", field_name, other))),
+                    }
+                },
+                Instruction::StoreField(name) => {
+                    let field_name = &function.names[name];
+                    let value = stack.pop().unwrap();
+
+                    match stack.pop().unwrap() {
+                        Value::Instance(data_name, row) => {
+                            let field_index = self.field_index(&data_name, field_name)?;
+                            self.world.set_field(&data_name, row, field_index, value);
+                        },
+                        other => return Err(self.error(format!("Cannot assign field {} of {}.", field_name, other))),
+                    }
+                },
+                Instruction::Add => self.apply_arithmetic("+", &mut stack)?,
+                Instruction::Subtract => self.apply_arithmetic("-", &mut stack)?,
+                Instruction::Multiply => self.apply_arithmetic("*", &mut stack)?,
+                Instruction::Divide => self.apply_arithmetic("/", &mut stack)?,
+                Instruction::Modulo => self.apply_arithmetic("%", &mut stack)?,
+                Instruction::Equal | Instruction::NotEqual => {
+                    let rhs = stack.pop().unwrap();
+                    let lhs = stack.pop().unwrap();
+                    stack.push(Value::Bool(values_equal(&lhs, &rhs) == (instruction == Instruction::Equal)));
+                },
+                Instruction::Greater => self.compare(">", &mut stack)?,
+                Instruction::Less => self.compare("<", &mut stack)?,
+                Instruction::GreaterOrEqual => self.compare(">=", &mut stack)?,
+                Instruction::LessOrEqual => self.compare("<=", &mut stack)?,
+                Instruction::MakeList(length) => {
+                    let elements = stack.split_off(stack.len() - length);
+                    stack.push(new_list(elements));
+                },
+                Instruction::MakeVariant(name, field_count) => {
+                    let variant_name = &function.names[name];
+                    let fields = stack
+                        .split_off(stack.len() - field_count)
+                        .into_iter()
+                        .zip(&self.program.variant_fields[variant_name])
+                        .map(|(value, field_type)| field_type.convert(value))
+                        .collect();
+
+                    stack.push(Value::Variant(variant_name.clone(), fields));
+                },
+                Instruction::Jump(target) => position = target,
+                Instruction::JumpIfFalse(target) => match stack.pop().unwrap() {
+                    Value::Bool(true) => (),
+                    Value::Bool(false) => position = target,
+                    other => return Err(self.error(format!("The condition evaluated to {} instead of a bool.", other))),
+                },
+                Instruction::Create(group) => {
+                    let group = &self.program.functions[group];
+                    let arguments = stack.split_off(stack.len() - group.parameter_types.len());
+                    self.call(group, arguments)?;
+                },
+                Instruction::NewEntity => stack.push(Value::Int(self.world.new_entity() as i64)),
+                Instruction::PushRow(name, field_count) => {
+                    let values = stack.split_off(stack.len() - field_count);
+
+                    match stack.pop().unwrap() {
+                        Value::Int(entity) => self.world.table_mut(&function.names[name]).push_row(entity as usize, values),
+                        other => return Err(self.error(format!("Cannot add a row for entity {}.", other))),
+                    }
+                },
+                Instruction::StartJoin(first_name, count) => {
+                    let data_names: Vec<&str> = function.names[first_name..first_name + count].iter().map(String::as_str).collect();
+
+                    let iterations = self.world
+                        .join(&data_names)
+                        .into_iter()
+                        .map(|rows| {
+                            data_names
+                                .iter()
+                                .zip(rows)
+                                .map(|(data_name, row)| Value::Instance(data_name.to_string(), row))
+                                .collect()
+                        })
+                        .collect();
+
+                    loops.push((iterations, 0));
+                },
+                Instruction::StartZip(count) => {
+                    let mut lists = Vec::new();
+
+                    for collection in stack.split_off(stack.len() - count) {
+                        match collection {
+                            Value::List(elements) => lists.push(elements),
+                            other => return Err(self.error(format!("Cannot iterate over {} in foreach.", other))),
+                        }
+                    }
+
+                    let length = lists.first().map_or(0, Vec::len);
+
+                    if let Some(other_list) = lists.iter().find(|list| list.len() != length) {
+                        return Err(self.error(format!("Foreach zips lists of different lengths, {} and {}.", length, other_list.len())));
+                    }
+
+                    let iterations = (0..length).map(|index| lists.iter().map(|list| list[index].clone()).collect()).collect();
+                    loops.push((iterations, 0));
+                },
+                Instruction::NextIteration(first_local, exit) => {
+                    let (iterations, next_iteration) = loops.last_mut().unwrap();
+
+                    match iterations.get(*next_iteration) {
+                        Some(values) => {
+                            locals[first_local..first_local + values.len()].clone_from_slice(values);
+                            *next_iteration += 1;
+                        },
+                        None => {
+                            loops.pop();
+                            position = exit;
+                        },
+                    }
+                },
+                Instruction::RunParallel(first_local, exit) => {
+                    let (iterations, _) = loops.pop().unwrap();
+                    self.execute_in_parallel(function, locals, first_local, position, &iterations)?;
+                    position = exit;
+                },
+                Instruction::EndIteration | Instruction::Return => return Ok(()),
+                Instruction::IsVariant(name) => {
+                    let is_variant = match stack.pop().unwrap() {
+                        Value::Variant(variant_name, _) | Value::Instance(variant_name, _) => variant_name == function.names[name],
+                        _ => false,
+                    };
+
+                    stack.push(Value::Bool(is_variant));
+                },
+                Instruction::IsData(name) => {
+                    let is_data = matches!(stack.pop().unwrap(), Value::Instance(data_name, _) if data_name == function.names[name]);
+                    stack.push(Value::Bool(is_data));
+                },
+                Instruction::VariantField(index) => match stack.pop().unwrap() {
+                    Value::Variant(_, mut fields) if index < fields.len() => stack.push(fields.swap_remove(index)),
+                    other => return Err(self.error(format!("{} has no field at position {}.", other, index))),
+                },
+                Instruction::NoMatch => {
+                    let value = stack.pop().unwrap();
+                    return Err(self.error(format!("No match arm matches {}.", value)));
+                },
+            }
+        }
+    }
+
+    /// Runs the loop body that starts at the given position once per iteration, in chunks on several threads.
+    fn execute_in_parallel(
+        &mut self,
+        function: &'p Function,
+        locals: &[Value],
+        first_local: usize,
+        body_start: usize,
+        iterations: &[Vec<Value>]
+    ) -> Result<(), Diagnostic> {
+        let program = self.program;
+        let context = self.context;
+
+        execute_in_chunks(self.world, iterations, &|storage, chunk| {
+            let mut vm = Vm::new(program, storage, context);
+            let mut locals = locals.to_vec();
+
+            for values in chunk {
+                locals[first_local..first_local + values.len()].clone_from_slice(values);
+                vm.execute(function, &mut locals, body_start)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn apply_arithmetic(&self, operator: &str, stack: &mut Vec<Value>) -> Result<(), Diagnostic> {
+        let rhs = stack.pop().unwrap();
+        let lhs = stack.pop().unwrap();
+        stack.push(apply_arithmetic(operator, &lhs, &rhs).map_err(|message| self.error(message))?);
+        Ok(())
+    }
+
+    fn compare(&self, operator: &str, stack: &mut Vec<Value>) -> Result<(), Diagnostic> {
+        let rhs = stack.pop().unwrap();
+        let lhs = stack.pop().unwrap();
+        stack.push(Value::Bool(compare(operator, &lhs, &rhs).map_err(|message| self.error(message))?));
+        Ok(())
+    }
+
+    fn field_index(&self, data_name: &str, field_name: &str) -> Result<usize, Diagnostic> {
+        match self.world.table(data_name).field_index(field_name) {
+            Some(field_index) => Ok(field_index),
+            None => Err(self.error(format!("Data {} has no field named {}.", data_name, field_name))),
+        }
+    }
+
+    fn error(&self, message: String) -> Diagnostic {
+        Diagnostic::error(format!("In {}: {}", self.context, message))
+    }
+}
@@ -0,0 +1,2229 @@
+use std::collections::HashMap;
+
+use crate::ir::{BinaryOperator, BlockId, FunctionKind, Instruction, IrFunction, IrProgram, IrTable, IrType, Operand, Terminator};
+
+/// Where the static area of linear memory starts: the strings, the table headers, the names of the variants and the
+/// rows of each join. Address 0 is left unused so that it can stand for no column.
+const STATIC_START: usize = 16;
+
+const PAGE_SIZE: usize = 65536;
+
+/// Imports writing to a file descriptor and exiting from WASI, then helpers every module starts with: writing text and
+/// numbers, allocating, strings, checked arithmetic, reporting errors and the merge join over entity columns. A {name}
+/// stands for the address of the string of that name in PRELUDE_STRINGS, or of the buffer in PRELUDE_BUFFERS.
+const PRELUDE: &str = r#"  (import "wasi_snapshot_preview1" "fd_write" (func $dolang_fd_write (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "proc_exit" (func $dolang_exit (param i32)))
+
+  (global $dolang_entity_count (mut i32) (i32.const 0))
+
+  ;; Writes the bytes at an address to a stream, 1 for output and 2 for errors.
+  (func $dolang_write_bytes (param $stream i32) (param $address i32) (param $length i32)
+    i32.const {iovec}
+    local.get $address
+    i32.store
+    i32.const {iovec}
+    local.get $length
+    i32.store offset=4
+    local.get $stream
+    i32.const {iovec}
+    i32.const 1
+    i32.const {written}
+    call $dolang_fd_write
+    drop)
+
+  (func $dolang_write_string (param $stream i32) (param $string i32)
+    local.get $stream
+    local.get $string
+    local.get $string
+    call $dolang_string_length
+    call $dolang_write_bytes)
+
+  ;; Writes the digits backwards from the end of the text buffer. The value is kept negative, as the smallest integer
+  ;; has no positive counterpart.
+  (func $dolang_write_int (param $stream i32) (param $value i64)
+    (local $position i32)
+    (local $negative i32)
+    i32.const {text}
+    i32.const 32
+    i32.add
+    local.set $position
+    local.get $value
+    i64.const 0
+    i64.lt_s
+    local.set $negative
+    local.get $negative
+    i32.eqz
+    if
+      i64.const 0
+      local.get $value
+      i64.sub
+      local.set $value
+    end
+    loop $digit
+      local.get $position
+      i32.const 1
+      i32.sub
+      local.set $position
+      local.get $position
+      i32.const 48
+      local.get $value
+      i64.const 10
+      i64.rem_s
+      i32.wrap_i64
+      i32.sub
+      i32.store8
+      local.get $value
+      i64.const 10
+      i64.div_s
+      local.tee $value
+      i64.const 0
+      i64.ne
+      br_if $digit
+    end
+    local.get $negative
+    if
+      local.get $position
+      i32.const 1
+      i32.sub
+      local.tee $position
+      i32.const 45
+      i32.store8
+    end
+    local.get $stream
+    local.get $position
+    i32.const {text}
+    i32.const 32
+    i32.add
+    local.get $position
+    i32.sub
+    call $dolang_write_bytes)
+
+  ;; Big integers are 40 limbs of 32 bits, the lowest first, enough for the scaled values of any float.
+  (func $dolang_big_set (param $big i32) (param $value i64)
+    (local $offset i32)
+    loop $limb
+      local.get $big
+      local.get $offset
+      i32.add
+      i32.const 0
+      i32.store
+      local.get $offset
+      i32.const 4
+      i32.add
+      local.tee $offset
+      i32.const 160
+      i32.lt_u
+      br_if $limb
+    end
+    local.get $big
+    local.get $value
+    i64.store)
+
+  (func $dolang_big_multiply (param $big i32) (param $factor i64)
+    (local $offset i32)
+    (local $address i32)
+    (local $product i64)
+    (local $carry i64)
+    loop $limb
+      local.get $big
+      local.get $offset
+      i32.add
+      local.tee $address
+      i64.load32_u
+      local.get $factor
+      i64.mul
+      local.get $carry
+      i64.add
+      local.set $product
+      local.get $address
+      local.get $product
+      i64.store32
+      local.get $product
+      i64.const 32
+      i64.shr_u
+      local.set $carry
+      local.get $offset
+      i32.const 4
+      i32.add
+      local.tee $offset
+      i32.const 160
+      i32.lt_u
+      br_if $limb
+    end)
+
+  (func $dolang_big_multiply_power (param $big i32) (param $base i64) (param $exponent i32)
+    block $done
+      loop $power
+        local.get $exponent
+        i32.eqz
+        br_if $done
+        local.get $big
+        local.get $base
+        call $dolang_big_multiply
+        local.get $exponent
+        i32.const 1
+        i32.sub
+        local.set $exponent
+        br $power
+      end
+    end)
+
+  (func $dolang_big_add (param $sum i32) (param $lhs i32) (param $rhs i32)
+    (local $offset i32)
+    (local $total i64)
+    loop $limb
+      local.get $sum
+      local.get $offset
+      i32.add
+      local.get $lhs
+      local.get $offset
+      i32.add
+      i64.load32_u
+      local.get $rhs
+      local.get $offset
+      i32.add
+      i64.load32_u
+      i64.add
+      local.get $total
+      i64.const 32
+      i64.shr_u
+      i64.add
+      local.tee $total
+      i64.store32
+      local.get $offset
+      i32.const 4
+      i32.add
+      local.tee $offset
+      i32.const 160
+      i32.lt_u
+      br_if $limb
+    end)
+
+  ;; Subtracts the right hand side from the left hand side, which is not smaller.
+  (func $dolang_big_subtract (param $lhs i32) (param $rhs i32)
+    (local $offset i32)
+    (local $address i32)
+    (local $difference i64)
+    loop $limb
+      local.get $lhs
+      local.get $offset
+      i32.add
+      local.tee $address
+      local.get $address
+      i64.load32_u
+      local.get $rhs
+      local.get $offset
+      i32.add
+      i64.load32_u
+      i64.sub
+      local.get $difference
+      i64.const 63
+      i64.shr_u
+      i64.sub
+      local.tee $difference
+      i64.store32
+      local.get $offset
+      i32.const 4
+      i32.add
+      local.tee $offset
+      i32.const 160
+      i32.lt_u
+      br_if $limb
+    end)
+
+  ;; Gives -1, 0 or 1 as the left hand side is smaller, equal or greater.
+  (func $dolang_big_compare (param $lhs i32) (param $rhs i32) (result i32)
+    (local $offset i32)
+    (local $lhs_limb i32)
+    (local $rhs_limb i32)
+    i32.const 160
+    local.set $offset
+    loop $limb
+      local.get $offset
+      i32.const 4
+      i32.sub
+      local.set $offset
+      local.get $lhs
+      local.get $offset
+      i32.add
+      i32.load
+      local.set $lhs_limb
+      local.get $rhs
+      local.get $offset
+      i32.add
+      i32.load
+      local.set $rhs_limb
+      local.get $lhs_limb
+      local.get $rhs_limb
+      i32.ne
+      if
+        local.get $lhs_limb
+        local.get $rhs_limb
+        i32.gt_u
+        i32.const 1
+        i32.shl
+        i32.const 1
+        i32.sub
+        return
+      end
+      local.get $offset
+      br_if $limb
+    end
+    i32.const 0)
+
+  ;; Writes the shortest decimal form that reads back as the same float, always with a fractional part. The digits
+  ;; come from the free-format algorithm of Burger and Dybvig: the value is r / s times a power of ten, and digits are
+  ;; taken until the rest is within the gap to the next float down, low, or up, high.
+  (func $dolang_write_float (param $stream i32) (param $value f64)
+    (local $bits i64)
+    (local $mantissa i64)
+    (local $exponent i32)
+    (local $shift i32)
+    (local $inclusive i32)
+    (local $count i32)
+    (local $digit i32)
+    (local $low_reached i32)
+    (local $high_reached i32)
+    (local $position i32)
+    (local $place i32)
+    (local $last_place i32)
+    (local $index i32)
+    (local $character i32)
+    local.get $value
+    local.get $value
+    f64.ne
+    if
+      local.get $stream
+      i32.const {nan}
+      call $dolang_write_string
+      return
+    end
+    local.get $value
+    i64.reinterpret_f64
+    local.set $bits
+    i32.const {text}
+    local.set $position
+    local.get $bits
+    i64.const 0
+    i64.lt_s
+    if
+      local.get $position
+      i32.const 45
+      i32.store8
+      local.get $position
+      i32.const 1
+      i32.add
+      local.set $position
+    end
+    local.get $value
+    f64.abs
+    f64.const inf
+    f64.eq
+    local.get $value
+    f64.const 0
+    f64.eq
+    i32.or
+    if
+      local.get $stream
+      i32.const {text}
+      local.get $position
+      i32.const {text}
+      i32.sub
+      call $dolang_write_bytes
+      local.get $stream
+      i32.const {zero}
+      i32.const {infinity}
+      local.get $value
+      f64.const 0
+      f64.eq
+      select
+      call $dolang_write_string
+      return
+    end
+    local.get $bits
+    i64.const 52
+    i64.shr_u
+    i32.wrap_i64
+    i32.const 2047
+    i32.and
+    local.set $exponent
+    local.get $bits
+    i64.const 4503599627370495
+    i64.and
+    local.set $mantissa
+    local.get $exponent
+    if
+      local.get $mantissa
+      i64.const 4503599627370496
+      i64.or
+      local.set $mantissa
+      local.get $exponent
+      i32.const 1075
+      i32.sub
+      local.set $exponent
+    else
+      i32.const -1074
+      local.set $exponent
+    end
+    ;; The gap to the next float down is half the gap up when the mantissa is a power of two, but not for the
+    ;; smallest exponent. Floats at either end of the gaps read back as this one when the mantissa is even.
+    local.get $mantissa
+    i64.const 4503599627370496
+    i64.eq
+    local.get $exponent
+    i32.const -1074
+    i32.ne
+    i32.and
+    local.set $shift
+    local.get $mantissa
+    i32.wrap_i64
+    i32.const 1
+    i32.and
+    i32.eqz
+    local.set $inclusive
+    i32.const {big_r}
+    local.get $mantissa
+    call $dolang_big_set
+    i32.const {big_r}
+    i64.const 2
+    local.get $exponent
+    i32.const 0
+    local.get $exponent
+    i32.const 0
+    i32.gt_s
+    select
+    local.get $shift
+    i32.add
+    i32.const 1
+    i32.add
+    call $dolang_big_multiply_power
+    i32.const {big_s}
+    i64.const 1
+    call $dolang_big_set
+    i32.const {big_s}
+    i64.const 2
+    i32.const 0
+    local.get $exponent
+    i32.sub
+    i32.const 0
+    local.get $exponent
+    i32.const 0
+    i32.lt_s
+    select
+    local.get $shift
+    i32.add
+    i32.const 1
+    i32.add
+    call $dolang_big_multiply_power
+    i32.const {big_high}
+    i64.const 1
+    call $dolang_big_set
+    i32.const {big_high}
+    i64.const 2
+    local.get $exponent
+    i32.const 0
+    local.get $exponent
+    i32.const 0
+    i32.gt_s
+    select
+    local.get $shift
+    i32.add
+    call $dolang_big_multiply_power
+    i32.const {big_low}
+    i64.const 1
+    call $dolang_big_set
+    i32.const {big_low}
+    i64.const 2
+    local.get $exponent
+    i32.const 0
+    local.get $exponent
+    i32.const 0
+    i32.gt_s
+    select
+    call $dolang_big_multiply_power
+    ;; Estimates the power of ten from the number of bits, which is at most one too small.
+    local.get $exponent
+    i32.const 63
+    local.get $mantissa
+    i64.clz
+    i32.wrap_i64
+    i32.sub
+    i32.add
+    f64.convert_i32_s
+    f64.const 0.3010299956639812
+    f64.mul
+    f64.const 1e-10
+    f64.sub
+    f64.ceil
+    i32.trunc_f64_s
+    local.set $exponent
+    local.get $exponent
+    i32.const 0
+    i32.ge_s
+    if
+      i32.const {big_s}
+      i64.const 10
+      local.get $exponent
+      call $dolang_big_multiply_power
+    else
+      i32.const {big_r}
+      i64.const 10
+      i32.const 0
+      local.get $exponent
+      i32.sub
+      call $dolang_big_multiply_power
+      i32.const {big_high}
+      i64.const 10
+      i32.const 0
+      local.get $exponent
+      i32.sub
+      call $dolang_big_multiply_power
+      i32.const {big_low}
+      i64.const 10
+      i32.const 0
+      local.get $exponent
+      i32.sub
+      call $dolang_big_multiply_power
+    end
+    i32.const {big_sum}
+    i32.const {big_r}
+    i32.const {big_high}
+    call $dolang_big_add
+    i32.const {big_sum}
+    i32.const {big_s}
+    call $dolang_big_compare
+    local.get $inclusive
+    i32.add
+    i32.const 0
+    i32.gt_s
+    if
+      i32.const {big_s}
+      i64.const 10
+      call $dolang_big_multiply
+      local.get $exponent
+      i32.const 1
+      i32.add
+      local.set $exponent
+    end
+    loop $generate
+      i32.const {big_r}
+      i64.const 10
+      call $dolang_big_multiply
+      i32.const {big_high}
+      i64.const 10
+      call $dolang_big_multiply
+      i32.const {big_low}
+      i64.const 10
+      call $dolang_big_multiply
+      i32.const 0
+      local.set $digit
+      block $divided
+        loop $divide
+          i32.const {big_r}
+          i32.const {big_s}
+          call $dolang_big_compare
+          i32.const 0
+          i32.lt_s
+          br_if $divided
+          i32.const {big_r}
+          i32.const {big_s}
+          call $dolang_big_subtract
+          local.get $digit
+          i32.const 1
+          i32.add
+          local.set $digit
+          br $divide
+        end
+      end
+      i32.const {big_low}
+      i32.const {big_r}
+      call $dolang_big_compare
+      local.get $inclusive
+      i32.add
+      i32.const 0
+      i32.gt_s
+      local.set $low_reached
+      i32.const {big_sum}
+      i32.const {big_r}
+      i32.const {big_high}
+      call $dolang_big_add
+      i32.const {big_sum}
+      i32.const {big_s}
+      call $dolang_big_compare
+      local.get $inclusive
+      i32.add
+      i32.const 0
+      i32.gt_s
+      local.set $high_reached
+      ;; Within both gaps, the last digit rounds to the nearest.
+      local.get $low_reached
+      local.get $high_reached
+      i32.and
+      if
+        i32.const {big_sum}
+        i32.const {big_r}
+        i32.const {big_r}
+        call $dolang_big_add
+        i32.const {big_sum}
+        i32.const {big_s}
+        call $dolang_big_compare
+        i32.const 0
+        i32.ge_s
+        local.set $high_reached
+      end
+      i32.const {digits}
+      local.get $count
+      i32.add
+      local.get $digit
+      local.get $high_reached
+      i32.add
+      i32.const 48
+      i32.add
+      i32.store8
+      local.get $count
+      i32.const 1
+      i32.add
+      local.set $count
+      local.get $low_reached
+      local.get $high_reached
+      i32.or
+      i32.eqz
+      br_if $generate
+    end
+    ;; The value is 0.d1d2... times ten to the exponent, written d1.d2...e(exponent - 1) from 1e16 up and below 1e-4.
+    local.get $exponent
+    i32.const 1
+    i32.sub
+    local.set $exponent
+    local.get $exponent
+    i32.const 4
+    i32.add
+    i32.const 19
+    i32.gt_u
+    if
+      local.get $position
+      i32.const {digits}
+      i32.load8_u
+      i32.store8
+      local.get $position
+      i32.const 1
+      i32.add
+      local.set $position
+      local.get $count
+      i32.const 1
+      i32.gt_u
+      if
+        local.get $position
+        i32.const 46
+        i32.store8
+        local.get $position
+        i32.const 1
+        i32.add
+        i32.const {digits}
+        i32.const 1
+        i32.add
+        local.get $count
+        i32.const 1
+        i32.sub
+        memory.copy
+        local.get $position
+        local.get $count
+        i32.add
+        local.set $position
+      end
+      local.get $position
+      i32.const 101
+      i32.store8
+      local.get $stream
+      i32.const {text}
+      local.get $position
+      i32.const 1
+      i32.add
+      i32.const {text}
+      i32.sub
+      call $dolang_write_bytes
+      local.get $stream
+      local.get $exponent
+      i64.extend_i32_s
+      call $dolang_write_int
+      return
+    end
+    ;; Otherwise writes each decimal place from the highest down, with zeros where there is no digit.
+    local.get $exponent
+    i32.const 0
+    local.get $exponent
+    i32.const 0
+    i32.gt_s
+    select
+    local.set $place
+    local.get $exponent
+    local.get $count
+    i32.sub
+    i32.const 1
+    i32.add
+    local.tee $last_place
+    i32.const -1
+    local.get $last_place
+    i32.const -1
+    i32.lt_s
+    select
+    local.set $last_place
+    loop $write_place
+      local.get $exponent
+      local.get $place
+      i32.sub
+      local.set $index
+      i32.const 48
+      local.set $character
+      local.get $index
+      local.get $count
+      i32.lt_u
+      if
+        i32.const {digits}
+        local.get $index
+        i32.add
+        i32.load8_u
+        local.set $character
+      end
+      local.get $position
+      local.get $character
+      i32.store8
+      local.get $position
+      i32.const 1
+      i32.add
+      local.set $position
+      local.get $place
+      i32.eqz
+      if
+        local.get $position
+        i32.const 46
+        i32.store8
+        local.get $position
+        i32.const 1
+        i32.add
+        local.set $position
+      end
+      local.get $place
+      i32.const 1
+      i32.sub
+      local.tee $place
+      local.get $last_place
+      i32.ge_s
+      br_if $write_place
+    end
+    local.get $stream
+    i32.const {text}
+    local.get $position
+    i32.const {text}
+    i32.sub
+    call $dolang_write_bytes)
+
+  ;; The remainder of a float division, with the sign of the dividend, computed exactly on the mantissas as fmod does.
+  (func $dolang_float_remainder (param $lhs f64) (param $rhs f64) (result f64)
+    (local $lhs_bits i64)
+    (local $rhs_bits i64)
+    (local $lhs_exponent i32)
+    (local $rhs_exponent i32)
+    (local $difference i64)
+    local.get $lhs
+    i64.reinterpret_f64
+    i64.const 9223372036854775807
+    i64.and
+    local.set $lhs_bits
+    local.get $rhs
+    i64.reinterpret_f64
+    i64.const 9223372036854775807
+    i64.and
+    local.set $rhs_bits
+    local.get $lhs_bits
+    i64.const 52
+    i64.shr_u
+    i32.wrap_i64
+    local.set $lhs_exponent
+    local.get $rhs_bits
+    i64.const 52
+    i64.shr_u
+    i32.wrap_i64
+    local.set $rhs_exponent
+    local.get $rhs_bits
+    i64.eqz
+    local.get $rhs
+    local.get $rhs
+    f64.ne
+    i32.or
+    local.get $lhs_exponent
+    i32.const 2047
+    i32.eq
+    i32.or
+    if
+      f64.const nan
+      return
+    end
+    local.get $lhs_bits
+    local.get $rhs_bits
+    i64.le_u
+    if
+      local.get $lhs
+      f64.const 0
+      local.get $lhs
+      f64.mul
+      local.get $lhs_bits
+      local.get $rhs_bits
+      i64.lt_u
+      select
+      return
+    end
+    local.get $lhs_exponent
+    if
+      local.get $lhs_bits
+      i64.const 4503599627370495
+      i64.and
+      i64.const 4503599627370496
+      i64.or
+      local.set $lhs_bits
+    else
+      i32.const 0
+      local.get $lhs_bits
+      i64.const 12
+      i64.shl
+      i64.clz
+      i32.wrap_i64
+      i32.sub
+      local.set $lhs_exponent
+      local.get $lhs_bits
+      i32.const 1
+      local.get $lhs_exponent
+      i32.sub
+      i64.extend_i32_u
+      i64.shl
+      local.set $lhs_bits
+    end
+    local.get $rhs_exponent
+    if
+      local.get $rhs_bits
+      i64.const 4503599627370495
+      i64.and
+      i64.const 4503599627370496
+      i64.or
+      local.set $rhs_bits
+    else
+      i32.const 0
+      local.get $rhs_bits
+      i64.const 12
+      i64.shl
+      i64.clz
+      i32.wrap_i64
+      i32.sub
+      local.set $rhs_exponent
+      local.get $rhs_bits
+      i32.const 1
+      local.get $rhs_exponent
+      i32.sub
+      i64.extend_i32_u
+      i64.shl
+      local.set $rhs_bits
+    end
+    ;; Subtracts the divisor from the dividend shifted left one bit at a time, as in a long division.
+    loop $reduce
+      local.get $lhs_bits
+      local.get $rhs_bits
+      i64.sub
+      local.tee $difference
+      i64.const 0
+      i64.ge_s
+      if
+        local.get $difference
+        i64.eqz
+        if
+          f64.const 0
+          local.get $lhs
+          f64.mul
+          return
+        end
+        local.get $difference
+        local.set $lhs_bits
+      end
+      local.get $lhs_exponent
+      local.get $rhs_exponent
+      i32.gt_s
+      if
+        local.get $lhs_bits
+        i64.const 1
+        i64.shl
+        local.set $lhs_bits
+        local.get $lhs_exponent
+        i32.const 1
+        i32.sub
+        local.set $lhs_exponent
+        br $reduce
+      end
+    end
+    block $normalized
+      loop $normalize
+        local.get $lhs_bits
+        i64.const 52
+        i64.shr_u
+        i32.wrap_i64
+        br_if $normalized
+        local.get $lhs_bits
+        i64.const 1
+        i64.shl
+        local.set $lhs_bits
+        local.get $lhs_exponent
+        i32.const 1
+        i32.sub
+        local.set $lhs_exponent
+        br $normalize
+      end
+    end
+    local.get $lhs_exponent
+    i32.const 0
+    i32.gt_s
+    if
+      local.get $lhs_bits
+      i64.const 4503599627370495
+      i64.and
+      local.get $lhs_exponent
+      i64.extend_i32_u
+      i64.const 52
+      i64.shl
+      i64.or
+      local.set $lhs_bits
+    else
+      local.get $lhs_bits
+      i32.const 1
+      local.get $lhs_exponent
+      i32.sub
+      i64.extend_i32_u
+      i64.shr_u
+      local.set $lhs_bits
+    end
+    local.get $lhs_bits
+    local.get $lhs
+    i64.reinterpret_f64
+    i64.const -9223372036854775808
+    i64.and
+    i64.or
+    f64.reinterpret_i64)
+
+  ;; Hands out memory that is never freed, growing the memory when needed.
+  (func $dolang_alloc (param $size i32) (result i32)
+    (local $pointer i32)
+    global.get $dolang_heap
+    local.set $pointer
+    global.get $dolang_heap
+    local.get $size
+    i32.add
+    i32.const 7
+    i32.add
+    i32.const -8
+    i32.and
+    global.set $dolang_heap
+    block $enough
+      loop $grow
+        global.get $dolang_heap
+        memory.size
+        i32.const 16
+        i32.shl
+        i32.le_u
+        br_if $enough
+        i32.const 1
+        memory.grow
+        i32.const -1
+        i32.eq
+        if
+          i32.const 2
+          i32.const {out_of_memory}
+          call $dolang_write_string
+          i32.const 1
+          call $dolang_exit
+          unreachable
+        end
+        br $grow
+      end
+    end
+    local.get $pointer)
+
+  (func $dolang_string_length (param $string i32) (result i32)
+    (local $end i32)
+    local.get $string
+    local.set $end
+    block $found
+      loop $scan
+        local.get $end
+        i32.load8_u
+        i32.eqz
+        br_if $found
+        local.get $end
+        i32.const 1
+        i32.add
+        local.set $end
+        br $scan
+      end
+    end
+    local.get $end
+    local.get $string
+    i32.sub)
+
+  (func $dolang_concat (param $lhs i32) (param $rhs i32) (result i32)
+    (local $lhs_length i32)
+    (local $rhs_length i32)
+    (local $result i32)
+    local.get $lhs
+    call $dolang_string_length
+    local.set $lhs_length
+    local.get $rhs
+    call $dolang_string_length
+    local.set $rhs_length
+    local.get $lhs_length
+    local.get $rhs_length
+    i32.add
+    i32.const 1
+    i32.add
+    call $dolang_alloc
+    local.set $result
+    local.get $result
+    local.get $lhs
+    local.get $lhs_length
+    memory.copy
+    local.get $result
+    local.get $lhs_length
+    i32.add
+    local.get $rhs
+    local.get $rhs_length
+    i32.const 1
+    i32.add
+    memory.copy
+    local.get $result)
+
+  ;; Compares two strings byte by byte, as strcmp does.
+  (func $dolang_string_compare (param $lhs i32) (param $rhs i32) (result i32)
+    (local $lhs_byte i32)
+    (local $rhs_byte i32)
+    block $different
+      loop $scan
+        local.get $lhs
+        i32.load8_u
+        local.set $lhs_byte
+        local.get $rhs
+        i32.load8_u
+        local.set $rhs_byte
+        local.get $lhs_byte
+        local.get $rhs_byte
+        i32.ne
+        br_if $different
+        local.get $lhs_byte
+        i32.eqz
+        br_if $different
+        local.get $lhs
+        i32.const 1
+        i32.add
+        local.set $lhs
+        local.get $rhs
+        i32.const 1
+        i32.add
+        local.set $rhs
+        br $scan
+      end
+    end
+    local.get $lhs_byte
+    local.get $rhs_byte
+    i32.sub)
+
+  (func $dolang_start_error (param $context i32)
+    i32.const 2
+    i32.const {error_prefix}
+    call $dolang_write_string
+    i32.const 2
+    local.get $context
+    call $dolang_write_string
+    i32.const 2
+    i32.const {colon}
+    call $dolang_write_string)
+
+  (func $dolang_end_error
+    i32.const 2
+    i32.const {newline}
+    call $dolang_write_string
+    i32.const 1
+    call $dolang_exit
+    unreachable)
+
+  (func $dolang_fail (param $context i32) (param $message i32)
+    local.get $context
+    call $dolang_start_error
+    i32.const 2
+    local.get $message
+    call $dolang_write_string
+    call $dolang_end_error)
+
+  (func $dolang_write_operation (param $lhs i64) (param $operator i32) (param $rhs i64)
+    i32.const 2
+    local.get $lhs
+    call $dolang_write_int
+    i32.const 2
+    i32.const {space}
+    call $dolang_write_string
+    i32.const 2
+    local.get $operator
+    call $dolang_write_string
+    i32.const 2
+    i32.const {space}
+    call $dolang_write_string
+    i32.const 2
+    local.get $rhs
+    call $dolang_write_int)
+
+  (func $dolang_fail_overflow (param $context i32) (param $lhs i64) (param $operator i32) (param $rhs i64)
+    local.get $context
+    call $dolang_start_error
+    local.get $lhs
+    local.get $operator
+    local.get $rhs
+    call $dolang_write_operation
+    i32.const 2
+    i32.const {overflows}
+    call $dolang_write_string
+    call $dolang_end_error)
+
+  (func $dolang_add (param $lhs i64) (param $rhs i64) (param $context i32) (result i64)
+    (local $result i64)
+    local.get $lhs
+    local.get $rhs
+    i64.add
+    local.set $result
+    local.get $lhs
+    local.get $result
+    i64.xor
+    local.get $rhs
+    local.get $result
+    i64.xor
+    i64.and
+    i64.const 0
+    i64.lt_s
+    if
+      local.get $context
+      local.get $lhs
+      i32.const {plus}
+      local.get $rhs
+      call $dolang_fail_overflow
+    end
+    local.get $result)
+
+  (func $dolang_subtract (param $lhs i64) (param $rhs i64) (param $context i32) (result i64)
+    (local $result i64)
+    local.get $lhs
+    local.get $rhs
+    i64.sub
+    local.set $result
+    local.get $lhs
+    local.get $rhs
+    i64.xor
+    local.get $lhs
+    local.get $result
+    i64.xor
+    i64.and
+    i64.const 0
+    i64.lt_s
+    if
+      local.get $context
+      local.get $lhs
+      i32.const {minus}
+      local.get $rhs
+      call $dolang_fail_overflow
+    end
+    local.get $result)
+
+  (func $dolang_multiply (param $lhs i64) (param $rhs i64) (param $context i32) (result i64)
+    (local $result i64)
+    (local $overflows i32)
+    local.get $lhs
+    local.get $rhs
+    i64.mul
+    local.set $result
+    local.get $lhs
+    i64.const -1
+    i64.eq
+    if
+      local.get $rhs
+      i64.const -9223372036854775808
+      i64.eq
+      local.set $overflows
+    else
+      local.get $lhs
+      i64.eqz
+      i32.eqz
+      if
+        local.get $result
+        local.get $lhs
+        i64.div_s
+        local.get $rhs
+        i64.ne
+        local.set $overflows
+      end
+    end
+    local.get $overflows
+    if
+      local.get $context
+      local.get $lhs
+      i32.const {times}
+      local.get $rhs
+      call $dolang_fail_overflow
+    end
+    local.get $result)
+
+  (func $dolang_check_division (param $lhs i64) (param $rhs i64) (param $context i32) (param $operator i32)
+    local.get $rhs
+    i64.eqz
+    if
+      local.get $context
+      call $dolang_start_error
+      i32.const 2
+      i32.const {division_by_zero}
+      call $dolang_write_string
+      local.get $lhs
+      local.get $operator
+      local.get $rhs
+      call $dolang_write_operation
+      i32.const 2
+      i32.const {period}
+      call $dolang_write_string
+      call $dolang_end_error
+    end
+    local.get $lhs
+    i64.const -9223372036854775808
+    i64.eq
+    local.get $rhs
+    i64.const -1
+    i64.eq
+    i32.and
+    if
+      local.get $context
+      local.get $lhs
+      local.get $operator
+      local.get $rhs
+      call $dolang_fail_overflow
+    end)
+
+  (func $dolang_divide (param $lhs i64) (param $rhs i64) (param $context i32) (param $operator i32) (result i64)
+    local.get $lhs
+    local.get $rhs
+    local.get $context
+    local.get $operator
+    call $dolang_check_division
+    local.get $lhs
+    local.get $rhs
+    i64.div_s)
+
+  (func $dolang_modulo (param $lhs i64) (param $rhs i64) (param $context i32) (param $operator i32) (result i64)
+    local.get $lhs
+    local.get $rhs
+    local.get $context
+    local.get $operator
+    call $dolang_check_division
+    local.get $lhs
+    local.get $rhs
+    i64.rem_s)
+
+  (func $dolang_check_float_division (param $lhs f64) (param $rhs f64) (param $context i32) (param $operator i32)
+    local.get $rhs
+    f64.const 0
+    f64.eq
+    if
+      local.get $context
+      call $dolang_start_error
+      i32.const 2
+      i32.const {division_by_zero}
+      call $dolang_write_string
+      i32.const 2
+      local.get $lhs
+      call $dolang_write_float
+      i32.const 2
+      i32.const {space}
+      call $dolang_write_string
+      i32.const 2
+      local.get $operator
+      call $dolang_write_string
+      i32.const 2
+      i32.const {space}
+      call $dolang_write_string
+      i32.const 2
+      local.get $rhs
+      call $dolang_write_float
+      i32.const 2
+      i32.const {period}
+      call $dolang_write_string
+      call $dolang_end_error
+    end)
+
+  (func $dolang_divide_float (param $lhs f64) (param $rhs f64) (param $context i32) (param $operator i32) (result f64)
+    local.get $lhs
+    local.get $rhs
+    local.get $context
+    local.get $operator
+    call $dolang_check_float_division
+    local.get $lhs
+    local.get $rhs
+    f64.div)
+
+  (func $dolang_modulo_float (param $lhs f64) (param $rhs f64) (param $context i32) (param $operator i32) (result f64)
+    local.get $lhs
+    local.get $rhs
+    local.get $context
+    local.get $operator
+    call $dolang_check_float_division
+    local.get $lhs
+    local.get $rhs
+    call $dolang_float_remainder)
+
+  ;; Moves the rows forward until they all point to the same entity, and tells whether there is one. A table header
+  ;; holds the length of the table, its capacity, then its entity column.
+  (func $dolang_join_next (param $count i32) (param $headers i32) (param $lengths i32) (param $rows i32) (result i32)
+    (local $index i32)
+    (local $offset i32)
+    (local $row i32)
+    (local $entity i32)
+    (local $highest i32)
+    (local $same i32)
+    loop $join
+      i32.const 0
+      local.set $index
+      i32.const 0
+      local.set $highest
+      i32.const 1
+      local.set $same
+      block $scanned
+        loop $scan
+          local.get $index
+          local.get $count
+          i32.ge_u
+          br_if $scanned
+          local.get $index
+          i32.const 4
+          i32.mul
+          local.set $offset
+          local.get $rows
+          local.get $offset
+          i32.add
+          i32.load
+          local.set $row
+          local.get $row
+          local.get $lengths
+          local.get $offset
+          i32.add
+          i32.load
+          i32.ge_u
+          if
+            i32.const 0
+            return
+          end
+          local.get $headers
+          local.get $offset
+          i32.add
+          i32.load
+          i32.load offset=8
+          local.get $row
+          i32.const 4
+          i32.mul
+          i32.add
+          i32.load
+          local.set $entity
+          local.get $index
+          i32.eqz
+          local.get $entity
+          local.get $highest
+          i32.eq
+          i32.or
+          local.get $same
+          i32.and
+          local.set $same
+          local.get $entity
+          local.get $highest
+          i32.gt_u
+          if
+            local.get $entity
+            local.set $highest
+          end
+          local.get $index
+          i32.const 1
+          i32.add
+          local.set $index
+          br $scan
+        end
+      end
+      local.get $same
+      if
+        i32.const 1
+        return
+      end
+      i32.const 0
+      local.set $index
+      block $advanced
+        loop $advance
+          local.get $index
+          local.get $count
+          i32.ge_u
+          br_if $advanced
+          local.get $index
+          i32.const 4
+          i32.mul
+          local.set $offset
+          local.get $rows
+          local.get $offset
+          i32.add
+          i32.load
+          local.set $row
+          local.get $headers
+          local.get $offset
+          i32.add
+          i32.load
+          i32.load offset=8
+          local.get $row
+          i32.const 4
+          i32.mul
+          i32.add
+          i32.load
+          local.get $highest
+          i32.lt_u
+          if
+            local.get $rows
+            local.get $offset
+            i32.add
+            local.get $row
+            i32.const 1
+            i32.add
+            i32.store
+          end
+          local.get $index
+          i32.const 1
+          i32.add
+          local.set $index
+          br $advance
+        end
+      end
+      br $join
+    end
+    unreachable)
+"#;
+
+const PRELUDE_STRINGS: [(&str, &str); 15] = [
+    ("out_of_memory", "error: Out of memory.\n"),
+    ("error_prefix", "error: In "),
+    ("colon", ": "),
+    ("newline", "\n"),
+    ("space", " "),
+    ("period", "."),
+    ("overflows", " overflows a 64 bit integer."),
+    ("division_by_zero", "Division by zero in "),
+    ("plus", "+"),
+    ("minus", "-"),
+    ("times", "*"),
+    ("entities", " entities\n"),
+    ("nan", "NaN"),
+    ("infinity", "inf"),
+    ("zero", "0.0"),
+];
+
+/// The buffers of the prelude and their sizes: the text written to a stream, the digits of a float and its big
+/// integers.
+const PRELUDE_BUFFERS: [(&str, usize); 9] = [
+    ("iovec", 8),
+    ("written", 4),
+    ("text", 48),
+    ("digits", 32),
+    ("big_r", 160),
+    ("big_s", 160),
+    ("big_high", 160),
+    ("big_low", 160),
+    ("big_sum", 160),
+];
+
+struct WasmGenerator<'a> {
+    program: &'a IrProgram,
+    /// The address of the header of each table.
+    headers: HashMap<String, usize>,
+    /// The address of the names of the variants of each enum, a variant being stored as its position.
+    enum_names: HashMap<String, usize>,
+    /// The initial content of the static area.
+    memory: Vec<u8>,
+    strings: HashMap<String, usize>,
+    /// The functions written so far.
+    functions: String,
+    /// The locals of the function being written, declared at its start.
+    locals: String,
+    body: String,
+    indentation: usize,
+    /// The address in the static area of the tables, the lengths and the rows of each join of the function being written.
+    joins: Vec<(usize, usize, usize)>,
+    name_count: usize,
+    context: String,
+}
+
+/// Generates a WebAssembly text module. Tables live in linear memory, each with a header giving its length, its
+/// capacity and the address of each of its columns. Do actions are exported functions, and the exported _start runs the
+/// run blocks then writes the world as run prints it. The module is a WASI command, as run by wasmtime run out.wat.
+pub fn generate_wasm(program: &IrProgram) -> String {
+    let mut generator = WasmGenerator {
+        program,
+        headers: HashMap::new(),
+        enum_names: HashMap::new(),
+        memory: Vec::new(),
+        strings: HashMap::new(),
+        functions: String::new(),
+        locals: String::new(),
+        body: String::new(),
+        indentation: 2,
+        joins: Vec::new(),
+        name_count: 0,
+        context: "".to_string(),
+    };
+
+    let mut prelude = PRELUDE.to_string();
+
+    for (name, text) in PRELUDE_STRINGS {
+        let address = generator.string(text);
+        prelude = prelude.replace(&format!("{{{}}}", name), &address.to_string());
+    }
+
+    for (name, size) in PRELUDE_BUFFERS {
+        let address = generator.reserve(size);
+        prelude = prelude.replace(&format!("{{{}}}", name), &address.to_string());
+    }
+
+    for (enum_name, variants) in &program.enums {
+        generator.generate_enum(enum_name, variants);
+    }
+
+    for table in &program.tables {
+        generator.generate_table(table);
+    }
+
+    let mut run_count = 0;
+
+    for function in &program.functions {
+        let signature = match &function.kind {
+            FunctionKind::Group(group_name) => format!("(func $create.{}", group_name),
+            FunctionKind::Do(action_name) => format!("(func $do.{} (export \"{}\")", action_name, action_name),
+            FunctionKind::Run(_) => {
+                run_count += 1;
+                format!("(func $run.{}", run_count - 1)
+            },
+        };
+
+        generator.generate_function(function, &signature);
+    }
+
+    for run in 0..run_count {
+        generator.emit(&format!("call $run.{}", run));
+    }
+
+    generator.emit("call $print_world");
+    generator.end_function("(func $main (export \"_start\")");
+    generator.generate_print_world();
+
+    let heap_start = (STATIC_START + generator.memory.len()).next_multiple_of(8);
+
+    format!(
+        "(module\n{}\n  (memory (export \"memory\") {})\n  (global $dolang_heap (mut i32) (i32.const {}))\n  (data (i32.const {}) \"{}\")\n{})\n",
+        prelude,
+        heap_start / PAGE_SIZE + 1,
+        heap_start,
+        STATIC_START,
+        escape(&generator.memory),
+        generator.functions
+    )
+}
+
+impl<'a> WasmGenerator<'a> {
+    fn generate_enum(&mut self, name: &str, variants: &[String]) {
+        let name_addresses: Vec<usize> = variants.iter().map(|variant_name| self.string(variant_name)).collect();
+        let names = self.reserve(4 * name_addresses.len());
+
+        for (index, address) in name_addresses.into_iter().enumerate() {
+            let offset = names - STATIC_START + 4 * index;
+            self.memory[offset..offset + 4].copy_from_slice(&(address as u32).to_le_bytes());
+        }
+
+        self.enum_names.insert(name.to_string(), names);
+    }
+
+    /// A table is a header followed by nothing: the header gives the length, the capacity, then the address of the
+    /// entity column and of one column per field, and an optional field has a second column telling whether it is set.
+    /// Each table gets a function that appends an instance and one that prints every instance.
+    fn generate_table(&mut self, table: &IrTable) {
+        let column_count: usize = table.fields.iter().map(|field| if field.optional { 2 } else { 1 }).sum();
+        let header = self.reserve(12 + 4 * column_count);
+
+        self.headers.insert(table.data_name.clone(), header);
+        self.generate_push(table);
+        self.generate_print(table);
+    }
+
+    /// Registers become locals. A function of several blocks is a loop around nested wasm blocks, one per block, that
+    /// br_table enters at the block whose number is in $block; each block sets the number of the next one and goes
+    /// back to the top of the loop, or falls through when the next one follows it. As actions do not call each other,
+    /// each join keeps its rows in a place of the static area of its own.
+    fn generate_function(&mut self, function: &IrFunction, signature: &str) {
+        self.context = function.context.clone();
+
+        let mut parameters = Vec::new();
+
+        for (register, (_, register_type)) in function.registers.iter().enumerate() {
+            match register < function.parameter_count {
+                true  => parameters.push(format!("(param $r{} {})", register, wasm_type(register_type))),
+                false => self.locals.push_str(&format!("    (local $r{} {})\n", register, wasm_type(register_type))),
+            }
+        }
+
+        let joins = function.joins();
+        self.joins.clear();
+
+        for join in 0..function.join_count {
+            let count = joins.get(&join).map_or(0, Vec::len);
+            let headers = self.reserve(4 * count);
+            let lengths = self.reserve(4 * count);
+            let rows = self.reserve(4 * count);
+            self.joins.push((headers, lengths, rows));
+        }
+
+        let blocks = function.reachable_blocks();
+
+        if blocks.len() == 1 {
+            for instruction in &function.blocks[0].instructions {
+                self.generate_instruction(function, instruction, &joins);
+            }
+
+            self.generate_terminator(function, &function.blocks[0].terminator, &joins, &blocks, 0);
+        }
+        else {
+            self.locals.push_str("    (local $block i32)\n");
+            self.open("loop $dispatch");
+
+            for block in blocks.iter().rev() {
+                self.open(&format!("block $block{}", block));
+            }
+
+            let labels: Vec<String> = blocks.iter().map(|block| format!("$block{}", block)).collect();
+            self.emit("local.get $block");
+            self.emit(&format!("br_table {}", labels.join(" ")));
+
+            for (position, block) in blocks.iter().enumerate() {
+                self.close();
+
+                for instruction in &function.blocks[*block].instructions {
+                    self.generate_instruction(function, instruction, &joins);
+                }
+
+                self.generate_terminator(function, &function.blocks[*block].terminator, &joins, &blocks, position);
+            }
+
+            self.close();
+        }
+
+        self.end_function(format!("{} {}", signature, parameters.join(" ")).trim_end());
+    }
+
+    fn generate_instruction(&mut self, function: &IrFunction, instruction: &Instruction, joins: &HashMap<usize, Vec<String>>) {
+        match instruction {
+            Instruction::Copy(target, value) => {
+                self.push(value);
+                self.emit(&format!("local.set $r{}", target));
+            },
+            Instruction::IntToFloat(target, value) => {
+                self.push(value);
+                self.emit("f64.convert_i64_s");
+                self.emit(&format!("local.set $r{}", target));
+            },
+            Instruction::Binary(target, operator, lhs, rhs) => {
+                self.push(lhs);
+                self.push(rhs);
+                self.binary(*operator, &function.operand_type(lhs));
+                self.emit(&format!("local.set $r{}", target));
+            },
+            Instruction::LoadField(target, data_name, field_name, row) => {
+                let field_type = function.registers[*target].1.clone();
+                let header = self.headers[data_name];
+                let (value_offset, _) = self.column_offsets(data_name, field_name);
+                self.emit_column_address(header, value_offset, size(&field_type), &row_local(row));
+                self.emit(&format!("{}.load", wasm_type(&field_type)));
+                self.emit(&format!("local.set $r{}", target));
+            },
+            Instruction::StoreField(data_name, field_name, row, value) => {
+                let field_type = function.operand_type(value);
+                let header = self.headers[data_name];
+                let (value_offset, flag_offset) = self.column_offsets(data_name, field_name);
+                self.emit_column_address(header, value_offset, size(&field_type), &row_local(row));
+                self.push(value);
+                self.emit(&format!("{}.store", wasm_type(&field_type)));
+
+                if let Some(flag_offset) = flag_offset {
+                    self.emit_column_address(header, flag_offset, 4, &row_local(row));
+                    self.emit("i32.const 1");
+                    self.emit("i32.store");
+                }
+            },
+            Instruction::NewEntity(target) => {
+                self.emit("global.get $dolang_entity_count");
+                self.emit(&format!("local.tee $r{}", target));
+                self.emit("i32.const 1");
+                self.emit("i32.add");
+                self.emit("global.set $dolang_entity_count");
+            },
+            Instruction::PushRow(data_name, entity, values) => {
+                self.push(entity);
+
+                let fields = self.program.table(data_name).map_or(&[][..], |table| &table.fields);
+
+                for (field, value) in fields.iter().zip(values) {
+                    match value {
+                        Some(value) => self.push(value),
+                        None => self.emit_zero(&field.field_type),
+                    }
+
+                    if field.optional {
+                        self.emit(&format!("i32.const {}", value.is_some() as i32));
+                    }
+                }
+
+                self.emit(&format!("call $push.{}", data_name));
+            },
+            Instruction::Create(group_name, arguments) => {
+                for argument in arguments {
+                    self.push(argument);
+                }
+
+                self.emit(&format!("call $create.{}", group_name));
+            },
+            Instruction::Do(action_name) => self.emit(&format!("call $do.{}", action_name)),
+            Instruction::StartJoin(join, data_names) => {
+                let (headers, lengths, rows) = self.joins[*join];
+
+                for (index, data_name) in data_names.iter().enumerate() {
+                    let header = self.headers[data_name];
+                    self.emit(&format!("i32.const {}", headers + 4 * index));
+                    self.emit(&format!("i32.const {}", header));
+                    self.emit("i32.store");
+                    self.emit(&format!("i32.const {}", lengths + 4 * index));
+                    self.emit(&format!("i32.const {}", header));
+                    self.emit("i32.load");
+                    self.emit("i32.store");
+                    self.emit(&format!("i32.const {}", rows + 4 * index));
+                    self.emit("i32.const 0");
+                    self.emit("i32.store");
+                }
+            },
+            Instruction::AdvanceJoin(join) => {
+                let rows = self.joins[*join].2;
+
+                for index in 0..joins.get(join).map_or(0, Vec::len) {
+                    self.emit(&format!("i32.const {}", rows + 4 * index));
+                    self.emit(&format!("i32.const {}", rows + 4 * index));
+                    self.emit("i32.load");
+                    self.emit("i32.const 1");
+                    self.emit("i32.add");
+                    self.emit("i32.store");
+                }
+            },
+        }
+    }
+
+    /// Ends the block at that position among the blocks written. $block holds the position of the next block.
+    fn generate_terminator(
+        &mut self,
+        function: &IrFunction,
+        terminator: &Terminator,
+        joins: &HashMap<usize, Vec<String>>,
+        blocks: &[BlockId],
+        position: usize
+    ) {
+        let position_of = |block: &BlockId| blocks.iter().position(|written| written == block).unwrap_or(0);
+
+        match terminator {
+            Terminator::Jump(target) => self.go_to(position_of(target), position),
+            Terminator::Branch(condition, then_block, else_block) => {
+                self.emit(&format!("i32.const {}", position_of(then_block)));
+                self.emit(&format!("i32.const {}", position_of(else_block)));
+                self.push(condition);
+                self.emit("select");
+                self.emit("local.set $block");
+                self.emit("br $dispatch");
+            },
+            Terminator::NextJoin(join, row_registers, body, exit) => {
+                let (headers, lengths, rows) = self.joins[*join];
+                self.emit(&format!("i32.const {}", joins.get(join).map_or(0, Vec::len)));
+                self.emit(&format!("i32.const {}", headers));
+                self.emit(&format!("i32.const {}", lengths));
+                self.emit(&format!("i32.const {}", rows));
+                self.emit("call $dolang_join_next");
+                self.open("if");
+
+                for (index, register) in row_registers.iter().enumerate() {
+                    self.emit(&format!("i32.const {}", rows + 4 * index));
+                    self.emit("i32.load");
+                    self.emit(&format!("local.set $r{}", register));
+                }
+
+                self.emit(&format!("i32.const {}", position_of(body)));
+                self.emit("local.set $block");
+                self.emit("br $dispatch");
+                self.close();
+                self.go_to(position_of(exit), position);
+            },
+            Terminator::NoMatch(_) => {
+                let context = self.string(&function.context);
+                let message = self.string("No match arm matches the value.");
+                self.emit(&format!("i32.const {}", context));
+                self.emit(&format!("i32.const {}", message));
+                self.emit("call $dolang_fail");
+                self.emit("unreachable");
+            },
+            Terminator::Return => self.emit("return"),
+        }
+    }
+
+    /// Goes to the block at the target position, falling through to it when it is the next one.
+    fn go_to(&mut self, target: usize, position: usize) {
+        if target != position + 1 {
+            self.emit(&format!("i32.const {}", target));
+            self.emit("local.set $block");
+            self.emit("br $dispatch");
+        }
+    }
+
+    /// Applies an operator to the two values on top of the stack. Integer arithmetic stops with the error run reports
+    /// when it overflows or divides by zero, and float division when it divides by zero.
+    fn binary(&mut self, operator: BinaryOperator, operand_type: &IrType) {
+        if operator == BinaryOperator::Concat {
+            return self.emit("call $dolang_concat");
+        }
+
+        if operator.is_comparison() {
+            let (operand_type, suffix) = match operand_type {
+                IrType::String => {
+                    self.emit("call $dolang_string_compare");
+                    self.emit("i32.const 0");
+                    ("i32", "_s")
+                },
+                IrType::Float => ("f64", ""),
+                IrType::Int => ("i64", "_s"),
+                _ => ("i32", "_s"),
+            };
+
+            let instruction = match operator {
+                BinaryOperator::Equal => "eq".to_string(),
+                BinaryOperator::NotEqual => "ne".to_string(),
+                BinaryOperator::Greater => format!("gt{}", suffix),
+                BinaryOperator::Less => format!("lt{}", suffix),
+                BinaryOperator::GreaterOrEqual => format!("ge{}", suffix),
+                _ => format!("le{}", suffix),
+            };
+
+            return self.emit(&format!("{}.{}", operand_type, instruction));
+        }
+
+        let helper = match (operator, operand_type) {
+            (BinaryOperator::Add, IrType::Float) => return self.emit("f64.add"),
+            (BinaryOperator::Subtract, IrType::Float) => return self.emit("f64.sub"),
+            (BinaryOperator::Multiply, IrType::Float) => return self.emit("f64.mul"),
+            (BinaryOperator::Add, _) => "add",
+            (BinaryOperator::Subtract, _) => "subtract",
+            (BinaryOperator::Multiply, _) => "multiply",
+            (BinaryOperator::Divide, IrType::Int) => "divide",
+            (_, IrType::Int) => "modulo",
+            (BinaryOperator::Divide, _) => "divide_float",
+            _ => "modulo_float",
+        };
+
+        let context = self.string(&self.context.clone());
+        self.emit(&format!("i32.const {}", context));
+
+        if matches!(operator, BinaryOperator::Divide | BinaryOperator::Modulo) {
+            let operator_string = self.string(operator.symbol());
+            self.emit(&format!("i32.const {}", operator_string));
+        }
+
+        self.emit(&format!("call $dolang_{}", helper));
+    }
+
+    fn push(&mut self, operand: &Operand) {
+        let instruction = match operand {
+            Operand::Register(register) => format!("local.get $r{}", register),
+            Operand::Int(int) => format!("i64.const {}", int),
+            Operand::Float(float) if float.is_nan() => "f64.const nan".to_string(),
+            Operand::Float(float) => format!("f64.const {:?}", float),
+            Operand::Bool(bool) => format!("i32.const {}", *bool as i32),
+            Operand::String(string) => format!("i32.const {}", self.string(string)),
+            Operand::Variant(_, index) => format!("i32.const {}", index),
+        };
+
+        self.emit(&instruction);
+    }
+
+    fn emit_zero(&mut self, value_type: &IrType) {
+        let instruction = match value_type {
+            IrType::Int => "i64.const 0".to_string(),
+            IrType::Float => "f64.const 0".to_string(),
+            IrType::String => format!("i32.const {}", self.string("")),
+            _ => "i32.const 0".to_string(),
+        };
+
+        self.emit(&instruction);
+    }
+
+    fn generate_push(&mut self, table: &IrTable) {
+        let (name, header) = (&table.data_name, self.headers[&table.data_name]);
+        let mut parameters = vec!["(param $entity i32)".to_string()];
+
+        for field in &table.fields {
+            parameters.push(format!("(param $field.{} {})", field.name, wasm_type(&field.field_type)));
+
+            if field.optional {
+                parameters.push(format!("(param $field.{}.is_set i32)", field.name));
+            }
+        }
+
+        let length = self.local("i32", "length");
+        let column = self.local("i32", "column");
+
+        self.emit(&format!("i32.const {}", header));
+        self.emit("i32.load");
+        self.emit(&format!("local.set {}", length));
+        self.emit(&format!("local.get {}", length));
+        self.emit(&format!("i32.const {}", header));
+        self.emit("i32.load offset=4");
+        self.emit("i32.eq");
+        self.open("if");
+        self.emit(&format!("i32.const {}", header));
+        self.emit(&format!("i32.const {}", header));
+        self.emit("i32.load offset=4");
+        self.emit("i32.const 2");
+        self.emit("i32.mul");
+        self.emit("i32.const 8");
+        self.emit("i32.add");
+        self.emit("i32.store offset=4");
+
+        let mut columns = vec![(8, 4)];
+
+        for field in &table.fields {
+            columns.push((8 + 4 * columns.len(), size(&field.field_type)));
+
+            if field.optional {
+                columns.push((8 + 4 * columns.len(), 4));
+            }
+        }
+
+        for (offset, size) in columns {
+            self.emit(&format!("i32.const {}", header));
+            self.emit("i32.load offset=4");
+            self.emit(&format!("i32.const {}", size));
+            self.emit("i32.mul");
+            self.emit("call $dolang_alloc");
+            self.emit(&format!("local.set {}", column));
+            self.emit(&format!("local.get {}", column));
+            self.emit(&format!("i32.const {}", header));
+            self.emit(&format!("i32.load offset={}", offset));
+            self.emit(&format!("local.get {}", length));
+            self.emit(&format!("i32.const {}", size));
+            self.emit("i32.mul");
+            self.emit("memory.copy");
+            self.emit(&format!("i32.const {}", header));
+            self.emit(&format!("local.get {}", column));
+            self.emit(&format!("i32.store offset={}", offset));
+        }
+
+        self.close();
+
+        self.emit_column_address(header, 8, 4, &length);
+        self.emit("local.get $entity");
+        self.emit("i32.store");
+
+        for field in &table.fields {
+            let (value_offset, flag_offset) = self.column_offsets(name, &field.name);
+            self.emit_column_address(header, value_offset, size(&field.field_type), &length);
+            self.emit(&format!("local.get $field.{}", field.name));
+            self.emit(&format!("{}.store", wasm_type(&field.field_type)));
+
+            if let Some(flag_offset) = flag_offset {
+                self.emit_column_address(header, flag_offset, 4, &length);
+                self.emit(&format!("local.get $field.{}.is_set", field.name));
+                self.emit("i32.store");
+            }
+        }
+
+        self.emit(&format!("i32.const {}", header));
+        self.emit(&format!("local.get {}", length));
+        self.emit("i32.const 1");
+        self.emit("i32.add");
+        self.emit("i32.store");
+        self.end_function(&format!("(func $push.{} {}", name, parameters.join(" ")));
+    }
+
+    fn generate_print(&mut self, table: &IrTable) {
+        let (name, header) = (&table.data_name, self.headers[&table.data_name]);
+        let row = self.local("i32", "row");
+        let loop_label = self.label("rows");
+        let end_label = self.label("printed");
+
+        self.write_text(1, &format!("{} (", name));
+        self.emit("i32.const 1");
+        self.emit(&format!("i32.const {}", header));
+        self.emit("i32.load");
+        self.emit("i64.extend_i32_u");
+        self.emit("call $dolang_write_int");
+        self.write_text(1, " instances)\n");
+
+        self.open(&format!("block {}", end_label));
+        self.open(&format!("loop {}", loop_label));
+        self.emit(&format!("local.get {}", row));
+        self.emit(&format!("i32.const {}", header));
+        self.emit("i32.load");
+        self.emit("i32.ge_u");
+        self.emit(&format!("br_if {}", end_label));
+
+        self.write_text(1, "  entity ");
+        self.emit("i32.const 1");
+        self.emit_column_address(header, 8, 4, &row);
+        self.emit("i32.load");
+        self.emit("i64.extend_i32_u");
+        self.emit("call $dolang_write_int");
+        self.write_text(1, ": ");
+
+        for (index, field) in table.fields.iter().enumerate() {
+            let separator = if index == 0 { "" } else { ", " };
+            self.write_text(1, &format!("{}{} = ", separator, field.name));
+
+            let (value_offset, flag_offset) = self.column_offsets(name, &field.name);
+
+            if let Some(flag_offset) = flag_offset {
+                self.emit_column_address(header, flag_offset, 4, &row);
+                self.emit("i32.load");
+                self.open("if");
+            }
+
+            self.emit_column_address(header, value_offset, size(&field.field_type), &row);
+            self.emit(&format!("{}.load", wasm_type(&field.field_type)));
+            self.write_value(&field.field_type);
+
+            if flag_offset.is_some() {
+                self.else_();
+                self.write_text(1, "none");
+                self.close();
+            }
+        }
+
+        self.write_text(1, "\n");
+        self.emit(&format!("local.get {}", row));
+        self.emit("i32.const 1");
+        self.emit("i32.add");
+        self.emit(&format!("local.set {}", row));
+        self.emit(&format!("br {}", loop_label));
+        self.close();
+        self.close();
+        self.end_function(&format!("(func $print.{}", name));
+    }
+
+    /// Writes the value on top of the stack to the output.
+    fn write_value(&mut self, value_type: &IrType) {
+        let value_local = self.local(wasm_type(value_type), "printed");
+        self.emit(&format!("local.set {}", value_local));
+
+        match value_type {
+            IrType::Int => {
+                self.emit("i32.const 1");
+                self.emit(&format!("local.get {}", value_local));
+                self.emit("call $dolang_write_int");
+            },
+            IrType::Float => {
+                self.emit("i32.const 1");
+                self.emit(&format!("local.get {}", value_local));
+                self.emit("call $dolang_write_float");
+            },
+            IrType::Bool => {
+                let true_string = self.string("true");
+                let false_string = self.string("false");
+                self.emit("i32.const 1");
+                self.emit(&format!("i32.const {}", true_string));
+                self.emit(&format!("i32.const {}", false_string));
+                self.emit(&format!("local.get {}", value_local));
+                self.emit("select");
+                self.emit("call $dolang_write_string");
+            },
+            IrType::String => {
+                self.write_text(1, "\"");
+                self.emit("i32.const 1");
+                self.emit(&format!("local.get {}", value_local));
+                self.emit("call $dolang_write_string");
+                self.write_text(1, "\"");
+            },
+            IrType::Enum(enum_name) => {
+                let names = self.enum_names[enum_name];
+                self.emit("i32.const 1");
+                self.emit(&format!("local.get {}", value_local));
+                self.emit("i32.const 4");
+                self.emit("i32.mul");
+                self.emit(&format!("i32.load offset={}", names));
+                self.emit("call $dolang_write_string");
+            },
+            IrType::Instance(data_name) => {
+                self.write_text(1, &format!("{}#", data_name));
+                self.emit("i32.const 1");
+                self.emit(&format!("local.get {}", value_local));
+                self.emit("i64.extend_i32_u");
+                self.emit("call $dolang_write_int");
+            },
+            IrType::Entity => {
+                self.emit("i32.const 1");
+                self.emit(&format!("local.get {}", value_local));
+                self.emit("i64.extend_i32_u");
+                self.emit("call $dolang_write_int");
+            },
+        }
+    }
+
+    fn generate_print_world(&mut self) {
+        let entities = self.string(" entities\n");
+        self.emit("i32.const 1");
+        self.emit("global.get $dolang_entity_count");
+        self.emit("i64.extend_i32_u");
+        self.emit("call $dolang_write_int");
+        self.emit("i32.const 1");
+        self.emit(&format!("i32.const {}", entities));
+        self.emit("call $dolang_write_string");
+
+        for table in &self.program.tables {
+            self.emit(&format!("call $print.{}", table.data_name));
+        }
+
+        self.end_function("(func $print_world");
+    }
+
+    /// The offsets in the header of the column of a field and, for an optional field, of the column telling whether
+    /// it is set.
+    fn column_offsets(&self, data_name: &str, field_name: &str) -> (usize, Option<usize>) {
+        let mut offset = 12;
+
+        for field in self.program.table(data_name).map_or(&[][..], |table| &table.fields) {
+            if field.name == field_name {
+                return (offset, field.optional.then_some(offset + 4));
+            }
+
+            offset += if field.optional { 8 } else { 4 };
+        }
+
+        (offset, None)
+    }
+
+    /// Pushes the address of the element of a row in a column.
+    fn emit_column_address(&mut self, header: usize, offset: usize, size: usize, row: &str) {
+        self.emit(&format!("i32.const {}", header));
+        self.emit(&format!("i32.load offset={}", offset));
+        self.emit(&format!("local.get {}", row));
+        self.emit(&format!("i32.const {}", size));
+        self.emit("i32.mul");
+        self.emit("i32.add");
+    }
+
+    fn write_text(&mut self, stream: usize, text: &str) {
+        let address = self.string(text);
+        self.emit(&format!("i32.const {}", stream));
+        self.emit(&format!("i32.const {}", address));
+        self.emit("call $dolang_write_string");
+    }
+
+    /// Declares a local of the function being written, named after the variable it holds.
+    fn local(&mut self, wasm_type: &str, name: &str) -> String {
+        self.name_count += 1;
+        let local = format!("${}.{}", name, self.name_count);
+        self.locals.push_str(&format!("    (local {} {})\n", local, wasm_type));
+        local
+    }
+
+    fn label(&mut self, name: &str) -> String {
+        self.name_count += 1;
+        format!("${}.{}", name, self.name_count)
+    }
+
+    /// Takes zeroed bytes in the static area, aligned for the columns and headers they may hold, and gives their address.
+    fn reserve(&mut self, size: usize) -> usize {
+        self.memory.resize(self.memory.len().next_multiple_of(4), 0);
+        let address = STATIC_START + self.memory.len();
+        self.memory.resize(self.memory.len() + size, 0);
+        address
+    }
+
+    /// Gives the address of a NUL-terminated copy of the text in the static area, stored once per text.
+    fn string(&mut self, text: &str) -> usize {
+        if let Some(address) = self.strings.get(text) {
+            return *address;
+        }
+
+        let address = self.reserve(text.len() + 1);
+        let offset = address - STATIC_START;
+        self.memory[offset..offset + text.len()].copy_from_slice(text.as_bytes());
+        self.strings.insert(text.to_string(), address);
+        address
+    }
+
+    fn open(&mut self, instruction: &str) {
+        self.emit(instruction);
+        self.indentation += 1;
+    }
+
+    fn else_(&mut self) {
+        self.indentation -= 1;
+        self.emit("else");
+        self.indentation += 1;
+    }
+
+    fn close(&mut self) {
+        self.indentation -= 1;
+        self.emit("end");
+    }
+
+    fn emit(&mut self, instruction: &str) {
+        self.body.push_str(&"  ".repeat(self.indentation));
+        self.body.push_str(instruction);
+        self.body.push('\n');
+    }
+
+    /// Writes the function with its locals first, then starts the next one.
+    fn end_function(&mut self, signature: &str) {
+        self.functions.push_str(&format!("\n  {}\n{}{}  )\n", signature, self.locals, self.body));
+        self.locals.clear();
+        self.body.clear();
+    }
+}
+
+fn wasm_type(value_type: &IrType) -> &'static str {
+    match value_type {
+        IrType::Int => "i64",
+        IrType::Float => "f64",
+        _ => "i32",
+    }
+}
+
+/// The number of bytes a value of this type takes in a column.
+fn size(value_type: &IrType) -> usize {
+    match value_type {
+        IrType::Int | IrType::Float => 8,
+        _ => 4,
+    }
+}
+
+/// The local holding a row, as rows are only ever in registers.
+fn row_local(row: &Operand) -> String {
+    match row {
+        Operand::Register(register) => format!("$r{}", register),
+        _ => unreachable!(),
+    }
+}
+
+fn escape(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            b' '..=b'~' if byte != b'"' && byte != b'\\' => (byte as char).to_string(),
+            _ => format!("\\{:02x}", byte),
+        })
+        .collect()
+}